@@ -0,0 +1,92 @@
+//! Full database scans with `Filter` over the nested entries against the
+//! columnar layout.
+//!
+//! Run with `cargo bench --bench filter`.
+
+use aniodb::query::Filter;
+use aniodb::{AnimeKind, ColumnarDatabase, Database};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_json::json;
+
+/// Roughly the size of the upstream dataset.
+const ENTRIES: usize = 30_000;
+
+const KINDS: [&str; 5] = ["TV", "MOVIE", "OVA", "ONA", "SPECIAL"];
+const SEASONS: [&str; 4] = ["WINTER", "SPRING", "SUMMER", "FALL"];
+
+fn database() -> Database {
+	let tags: Vec<_> = (0..2_000).map(|n| format!("tag {n}")).collect();
+
+	let data: Vec<_> = (0..ENTRIES)
+		.map(|n| {
+			let entry_tags: Vec<_> = (0..12)
+				.map(|i| &tags[(n * 7 + i * 131) % tags.len()])
+				.collect();
+
+			json!({
+				"sources": [format!("https://myanimelist.net/anime/{n}")],
+				"title": format!("Entry {n}"),
+				"type": KINDS[n % KINDS.len()],
+				"episodes": n % 60,
+				"status": "FINISHED",
+				"animeSeason": {
+					"season": SEASONS[n % SEASONS.len()],
+					"year": 1960 + n % 64,
+				},
+				"picture": "https://example.org/picture.png",
+				"thumbnail": "https://example.org/thumbnail.png",
+				"synonyms": [format!("Synonym {n}")],
+				"relations": [],
+				"tags": entry_tags,
+			})
+		})
+		.collect();
+
+	let json = json!({
+		"license": {
+			"name": "GNU Affero General Public License v3.0",
+			"url": "https://github.com/manami-project/anime-offline-database/blob/master/LICENSE",
+		},
+		"repository": "https://github.com/manami-project/anime-offline-database",
+		"lastUpdate": "2022-09-01",
+		"data": data,
+	});
+
+	Database::from_bytes(&serde_json::to_vec(&json).unwrap()).unwrap()
+}
+
+fn filter(c: &mut Criterion) {
+	let db = database();
+	let columns = ColumnarDatabase::new(&db);
+
+	let filter = Filter::All(vec![
+		Filter::Kind(AnimeKind::Tv),
+		Filter::Years(1990..=2010),
+		Filter::Any(vec![
+			Filter::Tag("tag 42".to_owned()),
+			Filter::Tag("tag 1337".to_owned()),
+		]),
+		Filter::Not(Box::new(Filter::MaxEpisodes(12))),
+	]);
+	let predicate = filter.compile();
+
+	let mut group = c.benchmark_group("filter");
+	group.bench_function("nested/matches", |b| {
+		b.iter(|| {
+			db.entries()
+				.iter()
+				.filter(|anime| black_box(&filter).matches(anime))
+				.count()
+		})
+	});
+	group.bench_function("nested/compiled", |b| {
+		b.iter(|| db.entries().iter().filter(|anime| predicate(anime)).count())
+	});
+	group.bench_function("columnar", |b| {
+		b.iter(|| columns.count(black_box(&filter)))
+	});
+	group.finish();
+}
+
+criterion_group!(benches, filter);
+criterion_main!(benches);
@@ -0,0 +1,22 @@
+fn main() {
+	#[cfg(feature = "ffi")]
+	generate_header();
+}
+
+/// Writes the C header for the `ffi` module to `include/aniodb.h`.
+#[cfg(feature = "ffi")]
+fn generate_header() {
+	println!("cargo:rerun-if-changed=src/ffi.rs");
+	println!("cargo:rerun-if-changed=cbindgen.toml");
+
+	let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+	let config = cbindgen::Config::from_file(format!("{dir}/cbindgen.toml"))
+		.expect("invalid cbindgen.toml");
+
+	cbindgen::Builder::new()
+		.with_crate(&dir)
+		.with_config(config)
+		.generate()
+		.expect("failed to generate the C header")
+		.write_to_file(format!("{dir}/include/aniodb.h"));
+}
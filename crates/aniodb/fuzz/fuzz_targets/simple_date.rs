@@ -0,0 +1,10 @@
+#![no_main]
+
+use aniodb::SimpleDate;
+use libfuzzer_sys::fuzz_target;
+use serde_json::Value;
+
+fuzz_target!(|data: &str| {
+	// Goes through `SimpleDateVisitor::visit_str`.
+	let _ = serde_json::from_value::<SimpleDate>(Value::String(data.into()));
+});
@@ -0,0 +1,174 @@
+//! Read-only view of the database borrowing from the input buffer.
+
+use std::borrow::Cow;
+
+use serde::Deserialize;
+
+use crate::{
+	AnimeKind, AnimeSeason, AnimeStatus, EpisodeCount, EpisodeDuration, Score,
+	SimpleDate,
+};
+
+/// A string borrowed from the input, unless it contained escape sequences.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+struct Text<'a>(#[serde(borrow)] Cow<'a, str>);
+
+fn texts<'b>(texts: &'b [Text<'b>]) -> impl Iterator<Item = &'b str> {
+	texts.iter().map(|text| &*text.0)
+}
+
+/// Borrowed counterpart of [`crate::Anime`].
+///
+/// Urls are kept as strings and not parsed.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnimeRef<'a> {
+	#[serde(borrow)]
+	sources: Vec<Text<'a>>,
+	#[serde(borrow)]
+	title: Text<'a>,
+	#[serde(rename = "type")]
+	kind: AnimeKind,
+	episodes: EpisodeCount,
+	status: AnimeStatus,
+	#[serde(default)]
+	anime_season: Option<AnimeSeason>,
+	#[serde(borrow)]
+	picture: Text<'a>,
+	#[serde(borrow)]
+	thumbnail: Text<'a>,
+	#[serde(borrow)]
+	synonyms: Vec<Text<'a>>,
+	#[serde(borrow)]
+	relations: Vec<Text<'a>>,
+	#[serde(borrow)]
+	tags: Vec<Text<'a>>,
+	#[serde(default)]
+	duration: Option<EpisodeDuration>,
+	#[serde(default)]
+	score: Option<Score>,
+	#[serde(borrow, default)]
+	studios: Option<Vec<Text<'a>>>,
+	#[serde(borrow, default)]
+	producers: Option<Vec<Text<'a>>>,
+}
+
+impl<'a> AnimeRef<'a> {
+	pub fn sources(&self) -> impl Iterator<Item = &str> {
+		texts(&self.sources)
+	}
+
+	pub fn title(&self) -> &str {
+		&self.title.0
+	}
+
+	pub fn kind(&self) -> AnimeKind {
+		self.kind
+	}
+
+	pub fn episodes(&self) -> EpisodeCount {
+		self.episodes
+	}
+
+	pub fn status(&self) -> AnimeStatus {
+		self.status
+	}
+
+	pub fn anime_season(&self) -> AnimeSeason {
+		self.anime_season.unwrap_or_default()
+	}
+
+	pub fn picture(&self) -> &str {
+		&self.picture.0
+	}
+
+	pub fn thumbnail(&self) -> &str {
+		&self.thumbnail.0
+	}
+
+	pub fn synonyms(&self) -> impl Iterator<Item = &str> {
+		texts(&self.synonyms)
+	}
+
+	pub fn relations(&self) -> impl Iterator<Item = &str> {
+		texts(&self.relations)
+	}
+
+	pub fn tags(&self) -> impl Iterator<Item = &str> {
+		texts(&self.tags)
+	}
+
+	pub fn duration(&self) -> Option<std::time::Duration> {
+		self.duration.as_ref().map(EpisodeDuration::as_duration)
+	}
+
+	pub fn score(&self) -> Option<Score> {
+		self.score
+	}
+
+	pub fn studios(&self) -> impl Iterator<Item = &str> {
+		texts(self.studios.as_deref().unwrap_or_default())
+	}
+
+	pub fn producers(&self) -> impl Iterator<Item = &str> {
+		texts(self.producers.as_deref().unwrap_or_default())
+	}
+}
+
+/// Borrowed counterpart of [`crate::Database`].
+///
+/// Strings are borrowed from the input instead of being copied, which makes
+/// parsing considerably cheaper for read-only workloads.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseRef<'a> {
+	last_update: SimpleDate,
+	#[serde(borrow)]
+	data: Vec<AnimeRef<'a>>,
+}
+
+impl<'a> DatabaseRef<'a> {
+	pub fn from_slice(bytes: &'a [u8]) -> Result<Self, std::io::Error> {
+		serde_json::from_slice(bytes).map_err(std::io::Error::other)
+	}
+
+	pub fn last_update(&self) -> SimpleDate {
+		self.last_update
+	}
+
+	pub fn entries(&self) -> &[AnimeRef<'a>] {
+		&self.data
+	}
+}
+
+#[test]
+fn db_ref_borrows() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Plain").tag("drama").build(),
+		AnimeBuilder::new("With \"quotes\"").build(),
+	]);
+	let json = serde_json::to_string(&db).unwrap();
+
+	let db_ref = DatabaseRef::from_slice(json.as_bytes()).unwrap();
+	let [plain, quoted] = db_ref.entries() else {
+		panic!("expected two entries");
+	};
+
+	assert!(matches!(plain.title.0, Cow::Borrowed("Plain")));
+	assert!(matches!(plain.tags[0].0, Cow::Borrowed("drama")));
+	assert!(matches!(quoted.title.0, Cow::Owned(_)));
+	assert_eq!(quoted.title(), "With \"quotes\"");
+
+	for (owned, borrowed) in db.data.iter().zip(db_ref.entries()) {
+		assert!(owned
+			.sources
+			.iter()
+			.map(|u| u.as_str())
+			.eq(borrowed.sources()));
+		assert_eq!(owned.picture.as_str(), borrowed.picture());
+		assert_eq!(owned.anime_season(), borrowed.anime_season());
+	}
+}
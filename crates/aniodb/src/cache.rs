@@ -0,0 +1,448 @@
+//! A binary cache of the database, updated with incremental patches.
+//!
+//! The cache starts with a full snapshot in MessagePack, every later
+//! snapshot which differs only slightly is appended as a patch listing the
+//! entries to keep and the changed ones. Loading replays the chain of
+//! patches, so updates only write what changed, which matters on slow flash
+//! storage.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{Anime, Database, License, SimpleDate};
+
+const MAGIC: &[u8; 8] = b"aniodb\0\x01";
+
+/// Frame holding a full [`Database`].
+const SNAPSHOT: u8 = 0;
+/// Frame holding a [`Patch`] of the previous frames.
+const PATCH: u8 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+	#[error("Io operation failed: `{0}`")]
+	IoError(#[from] io::Error),
+	#[error("Encoding failed: `{0}`")]
+	EncodeError(#[from] rmp_serde::encode::Error),
+	#[error("Decoding failed: `{0}`")]
+	DecodeError(#[from] rmp_serde::decode::Error),
+	#[error("Corrupt cache: `{0}`")]
+	Corrupt(String),
+}
+
+/// How [`SnapshotCache::store`] updated the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stored {
+	/// The cache already held the snapshot, nothing was written.
+	Unchanged,
+	/// A patch with the changes was appended.
+	Patched,
+	/// The whole cache was written anew.
+	Rewritten,
+}
+
+/// A database cached at a path, see the [module docs](self).
+///
+/// ```no_run
+/// use aniodb::{Database, SnapshotCache};
+///
+/// let cache = SnapshotCache::new("/data/aniodb.cache").max_patches(8);
+///
+/// let db = Database::load("anime-offline-database.json")?;
+/// cache.store(&db)?;
+///
+/// assert_eq!(cache.load()?, db);
+/// # Ok::<_, aniodb::CacheError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotCache {
+	path: PathBuf,
+	max_patches: usize,
+	max_changed: f64,
+}
+
+/// The replayed contents of a cache.
+struct Contents {
+	db: Database,
+	patches: usize,
+	/// Whether the last frame was written completely.
+	complete: bool,
+}
+
+impl SnapshotCache {
+	/// Caches at `path`, with at most 16 patches changing up to a tenth of
+	/// the entries each.
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self { path: path.into(), max_patches: 16, max_changed: 0.1 }
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Number of patches after which the cache is written anew, which keeps
+	/// loading fast.
+	pub fn max_patches(mut self, max_patches: usize) -> Self {
+		self.max_patches = max_patches;
+		self
+	}
+
+	/// Share of added or modified entries up to which a snapshot is stored
+	/// as a patch.
+	pub fn max_changed(mut self, share: f64) -> Self {
+		self.max_changed = share.clamp(0.0, 1.0);
+		self
+	}
+
+	/// Reads the snapshot and applies the patches stored after it.
+	///
+	/// A last patch which was cut short while writing, e.g. by a power
+	/// loss, is ignored.
+	pub fn load(&self) -> Result<Database, CacheError> {
+		self.read().map(|contents| contents.db)
+	}
+
+	/// Stores `db`, either as a patch of the cached snapshot or, if there is
+	/// none, it differs too much or the chain of patches is too long, by
+	/// writing the cache anew.
+	///
+	/// Writing anew replaces the file atomically. Unreadable caches are
+	/// replaced as well.
+	pub fn store(&self, db: &Database) -> Result<Stored, CacheError> {
+		let cached = self.read().ok().filter(|cached| cached.complete);
+		let Some(cached) = cached else {
+			self.rewrite(db)?;
+			return Ok(Stored::Rewritten);
+		};
+
+		let patch = Patch::between(&cached.db, db);
+		if patch.is_empty(&cached.db) {
+			return Ok(Stored::Unchanged);
+		}
+
+		let small =
+			patch.inserted() as f64 <= self.max_changed * db.data.len() as f64;
+		if small && cached.patches < self.max_patches {
+			self.append(&patch)?;
+			Ok(Stored::Patched)
+		} else {
+			self.rewrite(db)?;
+			Ok(Stored::Rewritten)
+		}
+	}
+
+	fn read(&self) -> Result<Contents, CacheError> {
+		let mut r = BufReader::new(File::open(&self.path)?);
+
+		let mut magic = [0; MAGIC.len()];
+		r.read_exact(&mut magic)?;
+		if magic != *MAGIC {
+			return Err(CacheError::Corrupt(
+				"not a snapshot cache".to_owned(),
+			));
+		}
+
+		let mut db: Database = match read_frame(&mut r)? {
+			Some((SNAPSHOT, payload)) => rmp_serde::from_slice(&payload)?,
+			_ => {
+				return Err(CacheError::Corrupt("missing snapshot".to_owned()))
+			}
+		};
+
+		let mut patches = 0;
+		let complete = loop {
+			match read_frame(&mut r) {
+				Ok(None) => break true,
+				Ok(Some((PATCH, payload))) => {
+					let patch: Patch = rmp_serde::from_slice(&payload)?;
+					db = patch.apply(db)?;
+					patches += 1;
+				}
+				Ok(Some((frame, _))) => {
+					return Err(CacheError::Corrupt(format!(
+						"unknown frame `{frame}`"
+					)));
+				}
+				Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+					break false;
+				}
+				Err(err) => return Err(err.into()),
+			}
+		};
+
+		Ok(Contents { db: db.interned(), patches, complete })
+	}
+
+	/// Writes the patch as one frame, so an interrupted write leaves a
+	/// frame which is cut short instead of a wrong one.
+	fn append(&self, patch: &Patch) -> Result<(), CacheError> {
+		let payload = rmp_serde::to_vec_named(patch)?;
+
+		let mut frame = Vec::with_capacity(payload.len() + 9);
+		frame.push(PATCH);
+		frame.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+		frame.extend_from_slice(&payload);
+
+		let mut file = OpenOptions::new().append(true).open(&self.path)?;
+		file.write_all(&frame)?;
+		file.sync_data()?;
+
+		Ok(())
+	}
+
+	/// Writes the cache next to its path first and renames it into place.
+	fn rewrite(&self, db: &Database) -> Result<(), CacheError> {
+		let mut tmp = self.path.as_os_str().to_owned();
+		tmp.push(".part");
+		let tmp = PathBuf::from(tmp);
+
+		let result = write_snapshot(&tmp, db)
+			.and_then(|_| fs::rename(&tmp, &self.path).map_err(Into::into));
+
+		if result.is_err() {
+			let _ = fs::remove_file(&tmp);
+		}
+
+		result
+	}
+}
+
+/// Streams `db` into a new file at `path`, without buffering the encoded
+/// snapshot in memory.
+fn write_snapshot(path: &Path, db: &Database) -> Result<(), CacheError> {
+	let mut w = BufWriter::new(File::create(path)?);
+
+	w.write_all(MAGIC)?;
+	w.write_all(&[SNAPSHOT])?;
+	// The length is filled in once the snapshot is written.
+	let len_at = w.stream_position()?;
+	w.write_all(&0_u64.to_le_bytes())?;
+
+	rmp_serde::encode::write_named(&mut w, db)?;
+
+	let end = w.stream_position()?;
+	w.seek(SeekFrom::Start(len_at))?;
+	w.write_all(&(end - len_at - 8).to_le_bytes())?;
+
+	w.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+
+	Ok(())
+}
+
+/// Reads the kind and payload of the next frame, `None` at the end of the
+/// cache.
+fn read_frame(r: &mut impl Read) -> io::Result<Option<(u8, Vec<u8>)>> {
+	let mut frame = [0];
+	if r.read(&mut frame)? == 0 {
+		return Ok(None);
+	}
+
+	let mut len = [0; 8];
+	r.read_exact(&mut len)?;
+	let len = u64::from_le_bytes(len);
+
+	// Not allocated upfront, a corrupt length may be huge.
+	let mut payload = Vec::new();
+	r.take(len).read_to_end(&mut payload)?;
+	if payload.len() as u64 != len {
+		return Err(io::ErrorKind::UnexpectedEof.into());
+	}
+
+	Ok(Some((frame[0], payload)))
+}
+
+/// The differences from one snapshot to the next.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Patch {
+	license: License,
+	repository: Url,
+	last_update: SimpleDate,
+	/// Build the entries of the new snapshot in order.
+	ops: Vec<Op>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Op {
+	/// Entries `start..start + len` of the previous snapshot.
+	Copy { start: usize, len: usize },
+	/// An added or modified entry.
+	Insert(Anime),
+}
+
+impl Patch {
+	fn between(old: &Database, new: &Database) -> Self {
+		let mut ops = Vec::new();
+
+		for anime in new.data.iter() {
+			let unchanged = anime
+				.sources
+				.iter()
+				.find_map(|source| old.source_index(source))
+				.filter(|&idx| old.data[idx] == *anime);
+
+			let Some(idx) = unchanged else {
+				ops.push(Op::Insert(anime.clone()));
+				continue;
+			};
+
+			if let Some(Op::Copy { start, len }) = ops.last_mut() {
+				if *start + *len == idx {
+					*len += 1;
+					continue;
+				}
+			}
+			ops.push(Op::Copy { start: idx, len: 1 });
+		}
+
+		Self {
+			license: new.license.clone(),
+			repository: new.repository.clone(),
+			last_update: new.last_update,
+			ops,
+		}
+	}
+
+	/// Whether applying the patch to `old` results in `old` again.
+	fn is_empty(&self, old: &Database) -> bool {
+		let entries = match self.ops.as_slice() {
+			[] => old.data.is_empty(),
+			[Op::Copy { start: 0, len }] => *len == old.data.len(),
+			_ => false,
+		};
+
+		entries
+			&& self.license == old.license
+			&& self.repository == old.repository
+			&& self.last_update == old.last_update
+	}
+
+	fn inserted(&self) -> usize {
+		self.ops.iter().filter(|op| matches!(op, Op::Insert(_))).count()
+	}
+
+	fn apply(self, old: Database) -> Result<Database, CacheError> {
+		let mut data = Vec::new();
+
+		for op in self.ops {
+			match op {
+				Op::Copy { start, len } => {
+					let entries = start
+						.checked_add(len)
+						.and_then(|end| old.data.get(start..end))
+						.ok_or_else(|| {
+							CacheError::Corrupt(format!(
+								"patch copies missing entries {start}..+{len}"
+							))
+						})?;
+					data.extend_from_slice(entries);
+				}
+				Op::Insert(anime) => data.push(anime),
+			}
+		}
+
+		Ok(Database {
+			license: self.license,
+			repository: self.repository,
+			last_update: self.last_update,
+			data: data.into(),
+			dead: old.dead,
+			ids: Default::default(),
+			tags: Default::default(),
+			sources: Default::default(),
+			titles: Default::default(),
+		})
+	}
+}
+
+#[test]
+fn cache_patches() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let dir = std::env::temp_dir().join("aniodb-cache-patches");
+	fs::create_dir_all(&dir).unwrap();
+	let cache = SnapshotCache::new(dir.join("db.cache"))
+		.max_patches(2)
+		.max_changed(0.2);
+	let _ = fs::remove_file(cache.path());
+
+	let entries: Vec<_> = (0..20)
+		.map(|n| AnimeBuilder::new(&format!("Entry {n}")).build())
+		.collect();
+	let first = database(entries.clone());
+	assert!(cache.load().is_err());
+	assert_eq!(cache.store(&first).unwrap(), Stored::Rewritten);
+	assert_eq!(cache.load().unwrap(), first);
+	assert_eq!(cache.store(&first).unwrap(), Stored::Unchanged);
+
+	let mut changed = entries;
+	changed.remove(3);
+	changed[10].episodes = 24.into();
+	changed.insert(0, AnimeBuilder::new("New").build());
+	let mut second = database(changed.clone());
+	second.last_update.day = 8;
+
+	let size = fs::metadata(cache.path()).unwrap().len();
+	assert_eq!(cache.store(&second).unwrap(), Stored::Patched);
+	let patch_size = fs::metadata(cache.path()).unwrap().len() - size;
+	assert!(patch_size < size / 4, "{patch_size} of {size}");
+	assert_eq!(cache.load().unwrap(), second);
+
+	changed.swap(1, 2);
+	let third = database(changed.clone());
+	assert_eq!(cache.store(&third).unwrap(), Stored::Patched);
+	assert_eq!(cache.load().unwrap(), third);
+
+	// The chain is full.
+	changed.pop();
+	let fourth = database(changed);
+	assert_eq!(cache.store(&fourth).unwrap(), Stored::Rewritten);
+	assert_eq!(cache.load().unwrap(), fourth);
+
+	// Too many changes at once.
+	let renamed: Vec<_> = (0..20)
+		.map(|n| AnimeBuilder::new(&format!("Renamed {n}")).build())
+		.collect();
+	let fifth = database(renamed);
+	assert_eq!(cache.store(&fifth).unwrap(), Stored::Rewritten);
+
+	// A patch cut short is ignored and replaced on the next store.
+	let mut file = OpenOptions::new().append(true).open(cache.path()).unwrap();
+	file.write_all(&[PATCH, 200, 0, 0, 0, 0, 0, 0, 0, 1, 2]).unwrap();
+	drop(file);
+	assert_eq!(cache.load().unwrap(), fifth);
+	assert_eq!(cache.store(&first).unwrap(), Stored::Rewritten);
+	assert_eq!(cache.load().unwrap(), first);
+
+	fs::write(cache.path(), b"garbage").unwrap();
+	assert!(matches!(cache.load(), Err(CacheError::IoError(_))));
+	assert_eq!(cache.store(&first).unwrap(), Stored::Rewritten);
+
+	fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(test)]
+proptest::proptest! {
+	#[test]
+	fn cache_roundtrip(
+		first in crate::testing::arb_database(),
+		second in crate::testing::arb_database(),
+	) {
+		let dir = std::env::temp_dir().join("aniodb-cache-roundtrip");
+		fs::create_dir_all(&dir).unwrap();
+		let cache = SnapshotCache::new(dir.join("db.cache"));
+		let _ = fs::remove_file(cache.path());
+
+		cache.store(&first).unwrap();
+		proptest::prop_assert_eq!(&cache.load().unwrap(), &first);
+		// Either patched or rewritten, depending on how much changed.
+		cache.store(&second).unwrap();
+		proptest::prop_assert_eq!(&cache.load().unwrap(), &second);
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
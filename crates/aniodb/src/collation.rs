@@ -0,0 +1,154 @@
+//! Locale-independent ordering of titles.
+
+use std::cmp::Ordering;
+
+use url::Url;
+
+use crate::normalize::fold;
+use crate::{Anime, Database};
+
+/// Ordering used when sorting titles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Collation {
+	/// Plain unicode code point order.
+	Codepoint,
+	/// Ignores case and common latin diacritics (`Shōjo` == `shojo`).
+	#[default]
+	CaseInsensitive,
+	/// Like [`Collation::CaseInsensitive`], but runs of digits are compared
+	/// by their numeric value (`Part 2` < `Part 10`).
+	Natural,
+}
+
+impl Collation {
+	pub fn compare(&self, a: &str, b: &str) -> Ordering {
+		self.key(a).cmp(&self.key(b))
+	}
+
+	fn key(&self, s: &str) -> SortKey {
+		let segments = match self {
+			Self::Codepoint => vec![Segment::Text(s.to_owned())],
+			Self::CaseInsensitive => vec![Segment::Text(fold(s))],
+			Self::Natural => natural_segments(&fold(s)),
+		};
+
+		// Tie-break on the original string so the order is total.
+		SortKey { segments, original: s.to_owned() }
+	}
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct SortKey {
+	segments: Vec<Segment>,
+	original: String,
+}
+
+/// Numbers sort before text, as digits do in code point order.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Segment {
+	Number(u64),
+	Text(String),
+}
+
+fn natural_segments(s: &str) -> Vec<Segment> {
+	let mut segments = Vec::new();
+	let mut rest = s;
+
+	while let Some(c) = rest.chars().next() {
+		let is_digit = c.is_ascii_digit();
+		let end = rest
+			.find(|c: char| c.is_ascii_digit() != is_digit)
+			.unwrap_or(rest.len());
+		let (part, tail) = rest.split_at(end);
+
+		segments.push(if is_digit {
+			Segment::Number(part.parse().unwrap_or(u64::MAX))
+		} else {
+			Segment::Text(part.to_owned())
+		});
+
+		rest = tail;
+	}
+
+	segments
+}
+
+/// A title together with the sources identifying its entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatalogEntry<'a> {
+	title: &'a str,
+	sources: &'a [Url],
+}
+
+impl<'a> CatalogEntry<'a> {
+	pub fn title(&self) -> &'a str {
+		self.title
+	}
+
+	pub fn sources(&self) -> &'a [Url] {
+		self.sources
+	}
+}
+
+impl Database {
+	/// Returns the titles of all entries, sorted by `collation`.
+	///
+	/// Identical titles are only listed once, with the sources of the first
+	/// entry carrying that title.
+	pub fn titles_sorted(
+		&self,
+		collation: Collation,
+	) -> Vec<CatalogEntry<'_>> {
+		let mut entries: Vec<_> = self
+			.data
+			.iter()
+			.map(|anime: &Anime| CatalogEntry {
+				title: &anime.title,
+				sources: &anime.sources,
+			})
+			.collect();
+
+		entries.sort_by_cached_key(|entry| collation.key(entry.title));
+		entries.dedup_by(|a, b| a.title == b.title);
+
+		entries
+	}
+}
+
+#[test]
+fn collation_compare() {
+	assert_eq!(Collation::Codepoint.compare("b", "B"), Ordering::Greater);
+	assert_eq!(
+		Collation::CaseInsensitive.compare("Shōjo", "shojo z"),
+		Ordering::Less
+	);
+	assert_eq!(
+		Collation::CaseInsensitive.compare("Part 10", "Part 2"),
+		Ordering::Less
+	);
+	assert_eq!(
+		Collation::Natural.compare("Part 10", "Part 2"),
+		Ordering::Greater
+	);
+}
+
+#[test]
+fn db_titles_sorted() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("beta").build(),
+		AnimeBuilder::new("Alpha").build(),
+		AnimeBuilder::new("Gamma 10").build(),
+		AnimeBuilder::new("beta").build(),
+		AnimeBuilder::new("Gamma 2").build(),
+	]);
+
+	let titles: Vec<_> = db
+		.titles_sorted(Collation::Natural)
+		.into_iter()
+		.map(|entry| entry.title())
+		.collect();
+
+	assert_eq!(titles, ["Alpha", "beta", "Gamma 2", "Gamma 10"]);
+}
@@ -0,0 +1,352 @@
+//! Filtering over fields stored in parallel arrays.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::query::Filter;
+use crate::{Anime, AnimeKind, AnimeStatus, Database, Season};
+
+/// The entries of a [`Database`] stored column by column, for filter-heavy
+/// scans.
+///
+/// Evaluating a [`Filter`] only walks the columns it needs (e.g. a flat
+/// array of kinds) and combines the results as bitmaps, instead of chasing
+/// pointers through every nested entry. Matches are looked up in the shared
+/// entries of the database afterwards.
+///
+/// ```no_run
+/// use aniodb::query::Filter;
+/// use aniodb::{AnimeKind, ColumnarDatabase, Database};
+///
+/// let db = Database::load("anime-offline-database.json")?;
+/// let columns = ColumnarDatabase::new(&db);
+///
+/// let filter = Filter::All(vec![
+///     Filter::Kind(AnimeKind::Tv),
+///     Filter::Tag("mecha".to_owned()),
+///     Filter::MinEpisodes(24),
+/// ]);
+/// println!("{} long mecha series", columns.count(&filter));
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ColumnarDatabase {
+	entries: Arc<[Anime]>,
+	/// All titles back to back, entry `idx` ends at `title_ends[idx]`.
+	titles: String,
+	title_ends: Vec<usize>,
+	kinds: Vec<AnimeKind>,
+	statuses: Vec<AnimeStatus>,
+	seasons: Vec<Season>,
+	/// `0` for unknown years.
+	years: Vec<u32>,
+	/// `0` for unknown counts, as upstream stores them.
+	episodes: Vec<u32>,
+	/// Bit of every distinct lowercased tag.
+	tag_bits: HashMap<String, usize>,
+	/// `tag_words` words of tag bits per entry.
+	tags: Vec<u64>,
+	tag_words: usize,
+}
+
+impl ColumnarDatabase {
+	pub fn new(db: &Database) -> Self {
+		let entries = db.shared_entries();
+		let len = entries.len();
+
+		let mut tag_bits = HashMap::new();
+		for anime in entries.iter() {
+			for tag in &anime.tags {
+				let next = tag_bits.len();
+				tag_bits.entry(tag.to_ascii_lowercase()).or_insert(next);
+			}
+		}
+		let tag_words = tag_bits.len().div_ceil(64);
+
+		let mut columns = Self {
+			entries: Arc::clone(&entries),
+			titles: String::new(),
+			title_ends: Vec::with_capacity(len),
+			kinds: Vec::with_capacity(len),
+			statuses: Vec::with_capacity(len),
+			seasons: Vec::with_capacity(len),
+			years: Vec::with_capacity(len),
+			episodes: Vec::with_capacity(len),
+			tag_bits,
+			tags: vec![0; len * tag_words],
+			tag_words,
+		};
+
+		for (idx, anime) in entries.iter().enumerate() {
+			let season = anime.anime_season();
+
+			columns.titles.push_str(&anime.title);
+			columns.title_ends.push(columns.titles.len());
+			columns.kinds.push(anime.kind);
+			columns.statuses.push(anime.status);
+			columns.seasons.push(season.season);
+			columns.years.push(season.year.unwrap_or(0));
+			columns.episodes.push(anime.episodes.into());
+
+			let row =
+				&mut columns.tags[idx * tag_words..(idx + 1) * tag_words];
+			for tag in &anime.tags {
+				let bit = columns.tag_bits[&tag.to_ascii_lowercase()];
+				row[bit / 64] |= 1 << (bit % 64);
+			}
+		}
+
+		columns
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	pub fn entries(&self) -> &[Anime] {
+		&self.entries
+	}
+
+	/// Title of the entry at `idx`, read from the title column.
+	pub fn title(&self, idx: usize) -> Option<&str> {
+		let end = *self.title_ends.get(idx)?;
+		let start = idx.checked_sub(1).map_or(0, |prev| self.title_ends[prev]);
+
+		Some(&self.titles[start..end])
+	}
+
+	/// Positions of the entries matching `filter`, in ascending order.
+	///
+	/// Matches the same entries as [`Filter::matches`].
+	pub fn positions(&self, filter: &Filter) -> Vec<usize> {
+		self.select(filter).iter().collect()
+	}
+
+	/// Entries matching `filter`, in database order.
+	pub fn filter(&self, filter: &Filter) -> Vec<&Anime> {
+		self.select(filter).iter().map(|idx| &self.entries[idx]).collect()
+	}
+
+	/// Titles of the entries matching `filter`, without touching the
+	/// entries.
+	pub fn titles(&self, filter: &Filter) -> Vec<&str> {
+		self.select(filter).iter().filter_map(|idx| self.title(idx)).collect()
+	}
+
+	/// Number of entries matching `filter`.
+	pub fn count(&self, filter: &Filter) -> usize {
+		self.select(filter).count()
+	}
+
+	fn select(&self, filter: &Filter) -> Bitmap {
+		let len = self.len();
+
+		match filter {
+			Filter::Kind(kind) => {
+				Bitmap::from_bits(len, self.kinds.iter().map(|k| k == kind))
+			}
+			Filter::Status(status) => Bitmap::from_bits(
+				len,
+				self.statuses.iter().map(|s| s == status),
+			),
+			Filter::Season(season) => Bitmap::from_bits(
+				len,
+				self.seasons.iter().map(|s| s == season),
+			),
+			Filter::Years(years) => Bitmap::from_bits(
+				len,
+				self.years.iter().map(|y| *y != 0 && years.contains(y)),
+			),
+			Filter::Tag(tag) => {
+				let Some(&bit) = self.tag_bits.get(&tag.to_ascii_lowercase())
+				else {
+					return Bitmap::zeros(len);
+				};
+				let (word, mask) = (bit / 64, 1 << (bit % 64));
+
+				Bitmap::from_bits(
+					len,
+					self.tags
+						.chunks_exact(self.tag_words)
+						.map(|row| row[word] & mask != 0),
+				)
+			}
+			Filter::MinEpisodes(min) => Bitmap::from_bits(
+				len,
+				self.episodes.iter().map(|n| *n != 0 && n >= min),
+			),
+			Filter::MaxEpisodes(max) => Bitmap::from_bits(
+				len,
+				self.episodes.iter().map(|n| *n != 0 && n <= max),
+			),
+			Filter::Not(filter) => self.select(filter).not(),
+			Filter::All(filters) => {
+				filters.iter().fold(Bitmap::ones(len), |selected, filter| {
+					selected.and(&self.select(filter))
+				})
+			}
+			Filter::Any(filters) => {
+				filters.iter().fold(Bitmap::zeros(len), |selected, filter| {
+					selected.or(&self.select(filter))
+				})
+			}
+		}
+	}
+}
+
+impl From<&Database> for ColumnarDatabase {
+	fn from(db: &Database) -> Self {
+		Self::new(db)
+	}
+}
+
+/// One bit per entry, set for selected ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bitmap {
+	words: Vec<u64>,
+	len: usize,
+}
+
+impl Bitmap {
+	fn zeros(len: usize) -> Self {
+		Self { words: vec![0; len.div_ceil(64)], len }
+	}
+
+	fn ones(len: usize) -> Self {
+		Self::zeros(len).not()
+	}
+
+	fn from_bits(len: usize, bits: impl Iterator<Item = bool>) -> Self {
+		let mut words = Vec::with_capacity(len.div_ceil(64));
+		let mut word = 0;
+
+		for (idx, bit) in bits.enumerate() {
+			word |= u64::from(bit) << (idx % 64);
+			if idx % 64 == 63 {
+				words.push(word);
+				word = 0;
+			}
+		}
+		if !len.is_multiple_of(64) {
+			words.push(word);
+		}
+
+		Self { words, len }
+	}
+
+	fn not(mut self) -> Self {
+		for word in &mut self.words {
+			*word = !*word;
+		}
+		// Bits past the last entry stay unset.
+		if let (Some(last), rest @ 1..) =
+			(self.words.last_mut(), self.len % 64)
+		{
+			*last &= (1 << rest) - 1;
+		}
+
+		self
+	}
+
+	fn and(mut self, other: &Self) -> Self {
+		for (word, other) in self.words.iter_mut().zip(&other.words) {
+			*word &= other;
+		}
+		self
+	}
+
+	fn or(mut self, other: &Self) -> Self {
+		for (word, other) in self.words.iter_mut().zip(&other.words) {
+			*word |= other;
+		}
+		self
+	}
+
+	fn count(&self) -> usize {
+		self.words.iter().map(|word| word.count_ones() as usize).sum()
+	}
+
+	/// Positions of the set bits, in ascending order.
+	fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+		self.words.iter().enumerate().flat_map(|(idx, &word)| {
+			let mut word = word;
+			std::iter::from_fn(move || {
+				let bit = word.trailing_zeros() as usize;
+				word &= word.checked_sub(1)?;
+				Some(idx * 64 + bit)
+			})
+		})
+	}
+}
+
+#[test]
+fn columnar_filter() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let entries: Vec<_> = (0..150)
+		.map(|n| {
+			let kind =
+				[AnimeKind::Tv, AnimeKind::Movie, AnimeKind::Ova][n % 3];
+			let mut anime = AnimeBuilder::new(&format!("Entry {n}"))
+				.kind(kind)
+				.episodes(u32::try_from(n % 30).unwrap())
+				.tag(if n % 2 == 0 { "Drama" } else { "comedy" });
+			if n % 5 == 0 {
+				anime = anime.tag("mecha");
+			}
+			if n % 7 != 0 {
+				anime = anime.year(1990 + u32::try_from(n % 40).unwrap());
+			}
+			anime.build()
+		})
+		.collect();
+	let db = database(entries);
+	let columns = ColumnarDatabase::new(&db);
+
+	let filters = [
+		Filter::Kind(AnimeKind::Movie),
+		Filter::Tag("drama".to_owned()),
+		Filter::Tag("MECHA".to_owned()),
+		Filter::Tag("missing".to_owned()),
+		Filter::Years(2000..=2010),
+		Filter::MinEpisodes(12),
+		Filter::MaxEpisodes(3),
+		Filter::Not(Box::new(Filter::Tag("mecha".to_owned()))),
+		Filter::All(vec![]),
+		Filter::Any(vec![]),
+		Filter::All(vec![
+			Filter::Kind(AnimeKind::Tv),
+			Filter::Any(vec![
+				Filter::Tag("mecha".to_owned()),
+				Filter::Not(Box::new(Filter::Years(1990..=2020))),
+			]),
+		]),
+	];
+
+	for filter in &filters {
+		let expected: Vec<_> = db
+			.entries()
+			.iter()
+			.filter(|anime| filter.matches(anime))
+			.collect();
+
+		assert_eq!(columns.filter(filter), expected, "{filter:?}");
+		assert_eq!(columns.count(filter), expected.len(), "{filter:?}");
+	}
+
+	assert_eq!(columns.count(&Filter::All(vec![])), 150);
+	assert_eq!(columns.title(149), Some("Entry 149"));
+	assert_eq!(columns.title(150), None);
+	assert_eq!(
+		columns.titles(&Filter::Tag("mecha".to_owned()))[..2],
+		["Entry 0", "Entry 5"]
+	);
+	assert_eq!(
+		columns.positions(&Filter::MaxEpisodes(1)),
+		[1, 31, 61, 91, 121]
+	);
+}
@@ -0,0 +1,309 @@
+//! Autocompletion of tags and titles.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashSet};
+use std::sync::{Arc, OnceLock};
+
+use crate::normalize::normalize;
+use crate::{Anime, Database};
+
+/// A title starting with the prefix passed to [`Database::complete_title`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Completion<'a> {
+	anime: &'a Anime,
+	title: &'a str,
+}
+
+impl<'a> Completion<'a> {
+	pub fn anime(&self) -> &'a Anime {
+		self.anime
+	}
+
+	/// The canonical title or synonym which matched.
+	pub fn title(&self) -> &'a str {
+		self.title
+	}
+}
+
+/// A title of an entry, `0` for the canonical title and `n` for the synonym
+/// `n - 1`.
+#[derive(Debug, Clone, Copy)]
+struct TitleRef {
+	entry: usize,
+	title: usize,
+}
+
+#[derive(Debug)]
+struct Edge {
+	/// Never empty, siblings start with different chars.
+	label: Box<str>,
+	node: usize,
+}
+
+impl Edge {
+	fn first(&self) -> char {
+		self.label.chars().next().expect("edge labels are never empty")
+	}
+}
+
+#[derive(Debug, Default)]
+struct Node {
+	/// Sorted by their first char.
+	children: Vec<Edge>,
+	/// Titles normalizing to the path leading here.
+	titles: Vec<TitleRef>,
+}
+
+/// Radix trie of the normalized titles and synonyms.
+#[derive(Debug)]
+struct TitleTrie {
+	nodes: Vec<Node>,
+}
+
+impl TitleTrie {
+	fn new(entries: &[Anime]) -> Self {
+		let mut trie = Self { nodes: vec![Node::default()] };
+
+		for (entry, anime) in entries.iter().enumerate() {
+			let titles = std::iter::once(&anime.title).chain(&anime.synonyms);
+			for (title, text) in titles.enumerate() {
+				trie.insert(&normalize(text), TitleRef { entry, title });
+			}
+		}
+
+		trie
+	}
+
+	fn insert(&mut self, key: &str, title: TitleRef) {
+		let mut node = 0;
+		let mut rest = key;
+
+		loop {
+			let Some(first) = rest.chars().next() else {
+				self.nodes[node].titles.push(title);
+				return;
+			};
+
+			let children = &self.nodes[node].children;
+			let pos = match children.binary_search_by_key(&first, Edge::first)
+			{
+				Ok(pos) => pos,
+				Err(pos) => {
+					let leaf = self.push(Node {
+						children: Vec::new(),
+						titles: vec![title],
+					});
+					self.nodes[node]
+						.children
+						.insert(pos, Edge { label: rest.into(), node: leaf });
+					return;
+				}
+			};
+
+			let edge = &children[pos];
+			let common = common_prefix(&edge.label, rest);
+			if common == edge.label.len() {
+				node = edge.node;
+				rest = &rest[common..];
+				continue;
+			}
+
+			// Splits the edge where the key branches off.
+			let (label, child) = (edge.label.clone(), edge.node);
+			let middle = self.push(Node {
+				children: vec![Edge {
+					label: label[common..].into(),
+					node: child,
+				}],
+				titles: Vec::new(),
+			});
+			self.nodes[node].children[pos] =
+				Edge { label: label[..common].into(), node: middle };
+
+			node = middle;
+			rest = &rest[common..];
+		}
+	}
+
+	fn push(&mut self, node: Node) -> usize {
+		self.nodes.push(node);
+		self.nodes.len() - 1
+	}
+
+	/// The node below which all keys starting with `prefix` are stored, with
+	/// the length of the key leading to it.
+	fn find(&self, prefix: &str) -> Option<(usize, usize)> {
+		let mut node = 0;
+		let mut rest = prefix;
+
+		while let Some(first) = rest.chars().next() {
+			let children = &self.nodes[node].children;
+			let pos =
+				children.binary_search_by_key(&first, Edge::first).ok()?;
+			let edge = &children[pos];
+
+			if edge.label.starts_with(rest) {
+				return Some((
+					edge.node,
+					prefix.len() - rest.len() + edge.label.len(),
+				));
+			}
+			rest = rest.strip_prefix(&*edge.label)?;
+			node = edge.node;
+		}
+
+		Some((node, prefix.len()))
+	}
+
+	/// Titles below `node`, shortest keys first.
+	fn below(
+		&self,
+		node: usize,
+		len: usize,
+	) -> impl Iterator<Item = TitleRef> + '_ {
+		let mut queue = BinaryHeap::from([Reverse((len, 0, node))]);
+		let mut pushed = 0;
+
+		std::iter::from_fn(move || {
+			let Reverse((len, _, node)) = queue.pop()?;
+
+			// Siblings go in order of their labels on equal lengths.
+			for edge in &self.nodes[node].children {
+				pushed += 1;
+				queue.push(Reverse((
+					len + edge.label.len(),
+					pushed,
+					edge.node,
+				)));
+			}
+
+			Some(self.nodes[node].titles.iter().copied())
+		})
+		.flatten()
+	}
+}
+
+fn common_prefix(a: &str, b: &str) -> usize {
+	a.char_indices()
+		.zip(b.chars())
+		.find(|((_, a), b)| a != b)
+		.map_or(a.len().min(b.len()), |((idx, _), _)| idx)
+}
+
+/// The title trie, built on first use.
+///
+/// Always compares equal, like [`crate::id::IdIndex`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TitleIndex(Arc<OnceLock<TitleTrie>>);
+
+impl PartialEq for TitleIndex {
+	fn eq(&self, _: &Self) -> bool {
+		true
+	}
+}
+
+impl Database {
+	/// All distinct tags of the database, sorted.
+	pub fn tags(&self) -> Vec<&str> {
+		let tags: BTreeSet<_> = self
+			.data
+			.iter()
+			.flat_map(|anime| anime.tags.iter().map(|tag| &**tag))
+			.collect();
+
+		tags.into_iter().collect()
+	}
+
+	/// Returns up to `limit` entries with a title or synonym starting with
+	/// `prefix`, for autocompletion.
+	///
+	/// Titles are compared [normalized](crate::Normalization::Standard), so
+	/// case and punctuation don't matter. Shorter titles come first, every
+	/// entry is returned once with its shortest matching title. The first
+	/// call builds a prefix trie of all titles, later ones only walk the
+	/// matching part of it.
+	pub fn complete_title(
+		&self,
+		prefix: &str,
+		limit: usize,
+	) -> Vec<Completion<'_>> {
+		let trie = self.titles.0.get_or_init(|| TitleTrie::new(&self.data));
+		let Some((node, len)) = trie.find(&normalize(prefix)) else {
+			return Vec::new();
+		};
+
+		let mut seen = HashSet::new();
+
+		trie.below(node, len)
+			.filter(|title| seen.insert(title.entry))
+			.take(limit)
+			.map(|title| {
+				let anime = &self.data[title.entry];
+				let text = match title.title {
+					0 => &anime.title,
+					n => &anime.synonyms[n - 1],
+				};
+
+				Completion { anime, title: text }
+			})
+			.collect()
+	}
+}
+
+#[test]
+fn db_tags() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("A").tag("drama").tag("comedy").build(),
+		AnimeBuilder::new("B").tag("mecha").tag("drama").build(),
+		AnimeBuilder::new("C").build(),
+	]);
+
+	assert_eq!(db.tags(), ["comedy", "drama", "mecha"]);
+}
+
+#[test]
+fn db_complete_title() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Shingeki no Kyojin")
+			.synonym("Attack on Titan")
+			.synonym("AoT")
+			.build(),
+		AnimeBuilder::new("Shingeki no Kyojin Season 2").build(),
+		AnimeBuilder::new("Shin Sekai Yori").build(),
+		AnimeBuilder::new("Attack No. 1").build(),
+		AnimeBuilder::new("Shinsekai").build(),
+	]);
+
+	let complete = |prefix: &str, limit| -> Vec<_> {
+		db.complete_title(prefix, limit)
+			.into_iter()
+			.map(|completion| completion.title())
+			.collect()
+	};
+
+	assert_eq!(
+		complete("shin", 10),
+		[
+			"Shinsekai",
+			"Shin Sekai Yori",
+			"Shingeki no Kyojin",
+			"Shingeki no Kyojin Season 2",
+		]
+	);
+	assert_eq!(complete("SHINGEKI", 1), ["Shingeki no Kyojin"]);
+	assert_eq!(
+		complete("Shingeki no Kyojin s", 10),
+		["Shingeki no Kyojin Season 2"]
+	);
+	assert_eq!(complete("attack", 10), ["Attack No. 1", "Attack on Titan"]);
+	assert_eq!(complete("a", 2), ["AoT", "Attack No. 1"]);
+	assert!(complete("shingeki x", 10).is_empty());
+	assert!(complete("shin", 0).is_empty());
+
+	let completion = db.complete_title("aot", 1)[0];
+	assert_eq!(completion.anime().title(), "Shingeki no Kyojin");
+}
@@ -0,0 +1,184 @@
+//! Calendar arithmetic and parsing of human-friendly dates.
+
+use serde::de::{self, Visitor};
+
+use crate::{SimpleDate, SimpleDateVisitor};
+
+const WEEKDAYS: [&str; 7] = [
+	"monday",
+	"tuesday",
+	"wednesday",
+	"thursday",
+	"friday",
+	"saturday",
+	"sunday",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Unrecognized date: `{0}`")]
+pub struct ParseDateError(String);
+
+impl SimpleDate {
+	/// Converts the date into days since 1970-01-01.
+	///
+	/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+	fn days_since_epoch(&self) -> i64 {
+		let month = i64::from(self.month);
+		let year = i64::from(self.year) - i64::from(month <= 2);
+		let era = year.div_euclid(400);
+		let yoe = year.rem_euclid(400);
+		let doy = (153 * ((month + 9) % 12) + 2) / 5 + i64::from(self.day) - 1;
+		let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+		era * 146_097 + doe - 719_468
+	}
+
+	/// Days since the last monday, `0` for mondays.
+	fn weekday(&self) -> i64 {
+		// 1970-01-01 was a thursday.
+		(self.days_since_epoch() + 3).rem_euclid(7)
+	}
+
+	/// Moves the date by `days`, which may be negative.
+	pub fn add_days(self, days: i64) -> Self {
+		Self::from_days_since_epoch(self.days_since_epoch() + days)
+	}
+
+	/// Moves the date by `months`, which may be negative, clamping the day
+	/// to the length of the target month (Jan 31 + 1 month = Feb 28/29).
+	pub fn add_months(self, months: i64) -> Self {
+		let months =
+			i64::from(self.year) * 12 + i64::from(self.month) - 1 + months;
+		let year = months.div_euclid(12) as u16;
+		let month = (months.rem_euclid(12) + 1) as u8;
+
+		Self { year, month, day: self.day.min(days_in_month(year, month)) }
+	}
+
+	/// Parses dates like `"2022-09-01"`, `"today"`, `"yesterday"`, `"2 weeks
+	/// ago"`, `"in 3 days"`, `"last monday"` or `"next friday"` relative to
+	/// `today`.
+	///
+	/// A bare weekday (`"monday"`) means the most recent one, which may be
+	/// `today`, while `"last monday"` is always in the past.
+	pub fn parse_relative(
+		input: &str,
+		today: SimpleDate,
+	) -> Result<Self, ParseDateError> {
+		let err = || ParseDateError(input.to_owned());
+
+		let input_lower = input.trim().to_lowercase();
+		let words: Vec<_> = input_lower.split_whitespace().collect();
+
+		let date = match words.as_slice() {
+			["today"] | ["now"] => today,
+			["yesterday"] => today.add_days(-1),
+			["tomorrow"] => today.add_days(1),
+			["last", unit] | ["next", unit] => {
+				let sign = if words[0] == "last" { -1 } else { 1 };
+
+				match weekday_index(unit) {
+					Some(day) => {
+						let offset =
+							(sign * (day - today.weekday())).rem_euclid(7);
+						let offset = if offset == 0 { 7 } else { offset };
+						today.add_days(sign * offset)
+					}
+					None => shift(today, sign, unit).ok_or_else(err)?,
+				}
+			}
+			[count, unit, "ago"] => {
+				let count: i64 = count.parse().map_err(|_| err())?;
+				shift(today, -count, unit).ok_or_else(err)?
+			}
+			["in", count, unit] => {
+				let count: i64 = count.parse().map_err(|_| err())?;
+				shift(today, count, unit).ok_or_else(err)?
+			}
+			[word] => match weekday_index(word) {
+				Some(day) => {
+					let back = (today.weekday() - day).rem_euclid(7);
+					today.add_days(-back)
+				}
+				None => SimpleDateVisitor
+					.visit_str::<de::value::Error>(word)
+					.map_err(|_| err())?,
+			},
+			_ => return Err(err()),
+		};
+
+		Ok(date)
+	}
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+	let leap = year.is_multiple_of(4)
+		&& (!year.is_multiple_of(100) || year.is_multiple_of(400));
+
+	match month {
+		2 if leap => 29,
+		2 => 28,
+		4 | 6 | 9 | 11 => 30,
+		_ => 31,
+	}
+}
+
+fn weekday_index(name: &str) -> Option<i64> {
+	WEEKDAYS
+		.iter()
+		.position(|day| *day == name || day[..3] == *name)
+		.map(|idx| idx as i64)
+}
+
+/// Moves `date` by `count` units of `unit` (`"day"`, `"weeks"`, ...).
+fn shift(date: SimpleDate, count: i64, unit: &str) -> Option<SimpleDate> {
+	let date = match unit.strip_suffix('s').unwrap_or(unit) {
+		"day" => date.add_days(count),
+		"week" => date.add_days(count * 7),
+		"month" => date.add_months(count),
+		"year" => date.add_months(count * 12),
+		_ => return None,
+	};
+
+	Some(date)
+}
+
+#[test]
+fn date_arithmetic() {
+	let date = |year, month, day| SimpleDate { year, month, day };
+
+	for days in [-719_468, -1, 0, 59, 11_016, 19_236, 2_932_896] {
+		assert_eq!(
+			SimpleDate::from_days_since_epoch(days).days_since_epoch(),
+			days
+		);
+	}
+
+	assert_eq!(date(2022, 12, 31).add_days(1), date(2023, 1, 1));
+	assert_eq!(date(2024, 1, 31).add_months(1), date(2024, 2, 29));
+	assert_eq!(date(2023, 3, 31).add_months(-13), date(2022, 2, 28));
+	assert_eq!(date(2022, 9, 5).weekday(), 0);
+}
+
+#[test]
+fn date_parse_relative() {
+	let date = |year, month, day| SimpleDate { year, month, day };
+	// A wednesday.
+	let today = date(2022, 9, 14);
+	let parse = |s| SimpleDate::parse_relative(s, today);
+
+	assert_eq!(parse("Today"), Ok(today));
+	assert_eq!(parse("yesterday"), Ok(date(2022, 9, 13)));
+	assert_eq!(parse("2 weeks ago"), Ok(date(2022, 8, 31)));
+	assert_eq!(parse("1 month ago"), Ok(date(2022, 8, 14)));
+	assert_eq!(parse("in 3 days"), Ok(date(2022, 9, 17)));
+	assert_eq!(parse("last year"), Ok(date(2021, 9, 14)));
+	assert_eq!(parse("last monday"), Ok(date(2022, 9, 12)));
+	assert_eq!(parse("last wednesday"), Ok(date(2022, 9, 7)));
+	assert_eq!(parse("next wed"), Ok(date(2022, 9, 21)));
+	assert_eq!(parse("wednesday"), Ok(today));
+	assert_eq!(parse("friday"), Ok(date(2022, 9, 9)));
+	assert_eq!(parse("2021-03-04"), Ok(date(2021, 3, 4)));
+	assert!(parse("3 fortnights ago").is_err());
+	assert!(parse("someday").is_err());
+}
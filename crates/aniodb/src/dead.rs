@@ -0,0 +1,117 @@
+//! Ids upstream removed from the database because the provider deleted
+//! them.
+
+use std::collections::HashSet;
+use std::io::Read;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::{Database, Provider};
+
+/// The dead entries of a single provider, as published in the
+/// `dead-entries` directory upstream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadEntries {
+	provider: Provider,
+	ids: HashSet<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeadEntriesFile {
+	dead_entries: Vec<String>,
+}
+
+impl DeadEntries {
+	/// Name of the file upstream publishes the dead entries of `provider`
+	/// in, without extension. Not every provider has one.
+	#[cfg(feature = "fetch")]
+	pub(crate) fn file_name(provider: Provider) -> Option<&'static str> {
+		match provider {
+			Provider::AniDb => Some("anidb"),
+			Provider::AniList => Some("anilist"),
+			Provider::Kitsu => Some("kitsu"),
+			Provider::MyAnimeList => Some("myanimelist"),
+			_ => None,
+		}
+	}
+
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	pub fn load(
+		provider: Provider,
+		path: impl AsRef<Path>,
+	) -> Result<Self, std::io::Error> {
+		Self::from_reader(provider, std::fs::File::open(path)?)
+	}
+
+	pub fn from_reader(
+		provider: Provider,
+		r: impl Read,
+	) -> Result<Self, std::io::Error> {
+		let file: DeadEntriesFile =
+			serde_json::from_reader(r).map_err(std::io::Error::other)?;
+
+		Ok(Self { provider, ids: file.dead_entries.into_iter().collect() })
+	}
+
+	pub fn provider(&self) -> Provider {
+		self.provider
+	}
+
+	pub fn contains(&self, id: &str) -> bool {
+		self.ids.contains(id)
+	}
+
+	pub fn len(&self) -> usize {
+		self.ids.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.ids.is_empty()
+	}
+}
+
+impl Database {
+	/// Remembers the dead entries of a provider for [`Database::is_dead`],
+	/// adding to those already known.
+	pub fn add_dead_entries(&mut self, dead: DeadEntries) {
+		Arc::make_mut(&mut self.dead)
+			.entry(dead.provider)
+			.or_default()
+			.extend(dead.ids);
+	}
+
+	/// Whether upstream removed the entry `id` of `provider`.
+	///
+	/// Only knows about providers whose dead entries were added with
+	/// [`Database::add_dead_entries`].
+	pub fn is_dead(&self, provider: Provider, id: &str) -> bool {
+		self.dead.get(&provider).is_some_and(|ids| ids.contains(id))
+	}
+}
+
+#[test]
+fn db_dead_entries() {
+	use crate::testing::database;
+
+	let dead = DeadEntries::from_reader(
+		Provider::MyAnimeList,
+		&br#"{ "deadEntries": ["38008", "38009"] }"#[..],
+	)
+	.unwrap();
+	assert_eq!(dead.len(), 2);
+	assert!(dead.contains("38008"));
+
+	let mut db = database(Vec::new());
+	assert!(!db.is_dead(Provider::MyAnimeList, "38008"));
+
+	db.add_dead_entries(dead);
+	assert!(db.is_dead(Provider::MyAnimeList, "38009"));
+	assert!(!db.is_dead(Provider::MyAnimeList, "1535"));
+	assert!(!db.is_dead(Provider::AniList, "38008"));
+
+	assert!(DeadEntries::from_reader(Provider::AniDb, &b"[1, 2]"[..]).is_err());
+}
@@ -0,0 +1,427 @@
+//! Differences between two snapshots of the database.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+
+use url::Url;
+
+use crate::{
+	Anime, AnimeId, AnimeKind, AnimeSeason, AnimeStatus, Database,
+	EpisodeCount, Score,
+};
+
+/// Items added to and removed from a list field, ignoring order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListChange<T> {
+	added: Vec<T>,
+	removed: Vec<T>,
+}
+
+impl<T: Clone + Eq + Hash> ListChange<T> {
+	fn between(old: &[T], new: &[T]) -> Option<Self> {
+		let old_set: HashSet<_> = old.iter().collect();
+		let new_set: HashSet<_> = new.iter().collect();
+
+		let added: Vec<_> =
+			new.iter().filter(|v| !old_set.contains(v)).cloned().collect();
+		let removed: Vec<_> =
+			old.iter().filter(|v| !new_set.contains(v)).cloned().collect();
+
+		(!added.is_empty() || !removed.is_empty())
+			.then_some(Self { added, removed })
+	}
+}
+
+impl<T> ListChange<T> {
+	pub fn added(&self) -> &[T] {
+		&self.added
+	}
+
+	pub fn removed(&self) -> &[T] {
+		&self.removed
+	}
+}
+
+impl<T: fmt::Display> fmt::Display for ListChange<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let added = self.added.iter().map(|v| format!("+{v}"));
+		let removed = self.removed.iter().map(|v| format!("-{v}"));
+
+		f.write_str(&added.chain(removed).collect::<Vec<_>>().join(", "))
+	}
+}
+
+/// The fields of an [`Anime`] which are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+	Sources,
+	Title,
+	Kind,
+	Episodes,
+	Status,
+	Season,
+	Picture,
+	Thumbnail,
+	Synonyms,
+	Relations,
+	Tags,
+	Duration,
+	Score,
+	Studios,
+	Producers,
+}
+
+/// A single changed field with its old and new value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldChange {
+	Sources(ListChange<Url>),
+	Title { old: String, new: String },
+	Kind { old: AnimeKind, new: AnimeKind },
+	Episodes { old: EpisodeCount, new: EpisodeCount },
+	Status { old: AnimeStatus, new: AnimeStatus },
+	Season { old: AnimeSeason, new: AnimeSeason },
+	Picture { old: Url, new: Url },
+	Thumbnail { old: Url, new: Url },
+	Synonyms(ListChange<String>),
+	Relations(ListChange<Url>),
+	Tags(ListChange<Arc<str>>),
+	Duration { old: Option<Duration>, new: Option<Duration> },
+	Score { old: Option<Score>, new: Option<Score> },
+	Studios(ListChange<Arc<str>>),
+	Producers(ListChange<Arc<str>>),
+}
+
+impl FieldChange {
+	pub fn field(&self) -> Field {
+		match self {
+			Self::Sources(_) => Field::Sources,
+			Self::Title { .. } => Field::Title,
+			Self::Kind { .. } => Field::Kind,
+			Self::Episodes { .. } => Field::Episodes,
+			Self::Status { .. } => Field::Status,
+			Self::Season { .. } => Field::Season,
+			Self::Picture { .. } => Field::Picture,
+			Self::Thumbnail { .. } => Field::Thumbnail,
+			Self::Synonyms(_) => Field::Synonyms,
+			Self::Relations(_) => Field::Relations,
+			Self::Tags(_) => Field::Tags,
+			Self::Duration { .. } => Field::Duration,
+			Self::Score { .. } => Field::Score,
+			Self::Studios(_) => Field::Studios,
+			Self::Producers(_) => Field::Producers,
+		}
+	}
+
+	fn between(old: &Anime, new: &Anime) -> Vec<Self> {
+		let mut changes = Vec::new();
+
+		macro_rules! scalar {
+			($variant:ident, $old:expr, $new:expr) => {
+				let (old, new) = ($old, $new);
+				if old != new {
+					changes.push(Self::$variant { old, new });
+				}
+			};
+		}
+
+		macro_rules! list {
+			($variant:ident, $field:ident) => {
+				if let Some(change) =
+					ListChange::between(&old.$field, &new.$field)
+				{
+					changes.push(Self::$variant(change));
+				}
+			};
+		}
+
+		list!(Sources, sources);
+		scalar!(Title, old.title.clone(), new.title.clone());
+		scalar!(Kind, old.kind, new.kind);
+		scalar!(Episodes, old.episodes, new.episodes);
+		scalar!(Status, old.status, new.status);
+		scalar!(Season, old.anime_season(), new.anime_season());
+		scalar!(Picture, old.picture.clone(), new.picture.clone());
+		scalar!(Thumbnail, old.thumbnail.clone(), new.thumbnail.clone());
+		list!(Synonyms, synonyms);
+		list!(Relations, relations);
+		list!(Tags, tags);
+		scalar!(Duration, old.duration(), new.duration());
+		scalar!(Score, old.score, new.score);
+
+		if let Some(change) = ListChange::between(old.studios(), new.studios())
+		{
+			changes.push(Self::Studios(change));
+		}
+
+		if let Some(change) =
+			ListChange::between(old.producers(), new.producers())
+		{
+			changes.push(Self::Producers(change));
+		}
+
+		changes
+	}
+}
+
+impl fmt::Display for FieldChange {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Sources(c) => write!(f, "sources: {c}"),
+			Self::Title { old, new } => {
+				write!(f, "title changed from `{old}` to `{new}`")
+			}
+			Self::Kind { old, new } => {
+				write!(f, "type changed from {old:?} to {new:?}")
+			}
+			Self::Episodes { old, new } => {
+				write!(f, "episode count changed from {old} to {new}")
+			}
+			Self::Status { old, new } => {
+				write!(f, "status changed from {old:?} to {new:?}")
+			}
+			Self::Season { old, new } => {
+				write!(f, "season changed from {old:?} to {new:?}")
+			}
+			Self::Picture { old, new } => {
+				write!(f, "picture changed from {old} to {new}")
+			}
+			Self::Thumbnail { old, new } => {
+				write!(f, "thumbnail changed from {old} to {new}")
+			}
+			Self::Synonyms(c) => write!(f, "synonyms: {c}"),
+			Self::Relations(c) => write!(f, "relations: {c}"),
+			Self::Tags(c) => write!(f, "tags: {c}"),
+			Self::Duration { old, new } => {
+				write!(f, "duration changed from {old:?} to {new:?}")
+			}
+			Self::Score { old, new } => {
+				let mean =
+					|s: &Option<Score>| s.map(|s| s.arithmetic_geometric_mean);
+				write!(
+					f,
+					"score changed from {:?} to {:?}",
+					mean(old),
+					mean(new)
+				)
+			}
+			Self::Studios(c) => write!(f, "studios: {c}"),
+			Self::Producers(c) => write!(f, "producers: {c}"),
+		}
+	}
+}
+
+/// An entry present in both snapshots with at least one changed field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Modified<'a> {
+	old: &'a Anime,
+	new: &'a Anime,
+	changes: Vec<FieldChange>,
+}
+
+impl<'a> Modified<'a> {
+	pub fn before(&self) -> &'a Anime {
+		self.old
+	}
+
+	pub fn after(&self) -> &'a Anime {
+		self.new
+	}
+
+	pub fn changes(&self) -> &[FieldChange] {
+		&self.changes
+	}
+}
+
+/// A changed field of an entry, sent to subscribers of
+/// [`DatabaseHandle::subscribe`](crate::DatabaseHandle::subscribe).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeEvent {
+	id: AnimeId,
+	change: FieldChange,
+}
+
+impl ChangeEvent {
+	/// Id of the entry in the new snapshot.
+	///
+	/// It differs from the old id if the sources changed.
+	pub fn id(&self) -> AnimeId {
+		self.id
+	}
+
+	pub fn field(&self) -> Field {
+		self.change.field()
+	}
+
+	/// The field with its old and new value.
+	pub fn change(&self) -> &FieldChange {
+		&self.change
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DatabaseDiff<'a> {
+	added: Vec<&'a Anime>,
+	removed: Vec<&'a Anime>,
+	modified: Vec<Modified<'a>>,
+}
+
+impl<'a> DatabaseDiff<'a> {
+	pub fn added(&self) -> &[&'a Anime] {
+		&self.added
+	}
+
+	pub fn removed(&self) -> &[&'a Anime] {
+		&self.removed
+	}
+
+	pub fn modified(&self) -> &[Modified<'a>] {
+		&self.modified
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty()
+			&& self.removed.is_empty()
+			&& self.modified.is_empty()
+	}
+
+	/// One event per changed field of the modified entries.
+	pub fn change_events(&self) -> impl Iterator<Item = ChangeEvent> + '_ {
+		self.modified.iter().flat_map(|modified| {
+			let id = modified.new.id();
+			modified
+				.changes
+				.iter()
+				.map(move |change| ChangeEvent { id, change: change.clone() })
+		})
+	}
+}
+
+impl Database {
+	/// Compares two snapshots of the database.
+	///
+	/// Entries are matched by their source urls, an entry in `new` sharing
+	/// at least one source with an entry in `old` is considered the same
+	/// entry. Of several such entries the one with the same
+	/// [`Anime::primary_source`] is preferred.
+	pub fn diff<'a>(old: &'a Database, new: &'a Database) -> DatabaseDiff<'a> {
+		let mut by_source: HashMap<&Url, Vec<usize>> = HashMap::new();
+		for (idx, anime) in old.data.iter().enumerate() {
+			for source in &anime.sources {
+				by_source.entry(source).or_default().push(idx);
+			}
+		}
+
+		let mut matched = vec![false; old.data.len()];
+		let mut diff = DatabaseDiff::default();
+
+		for anime in new.data.iter() {
+			let candidates = || {
+				anime
+					.sources
+					.iter()
+					.filter_map(|source| by_source.get(source))
+					.flatten()
+					.copied()
+					.filter(|&idx| !matched[idx])
+			};
+			let primary = anime.primary_source();
+			let idx = candidates()
+				.find(|&idx| old.data[idx].primary_source() == primary)
+				.or_else(|| candidates().next());
+
+			match idx {
+				Some(idx) => {
+					matched[idx] = true;
+
+					let old = &old.data[idx];
+					let changes = FieldChange::between(old, anime);
+
+					if !changes.is_empty() {
+						diff.modified.push(Modified {
+							old,
+							new: anime,
+							changes,
+						});
+					}
+				}
+				None => diff.added.push(anime),
+			}
+		}
+
+		diff.removed = old
+			.data
+			.iter()
+			.zip(matched)
+			.filter_map(|(anime, matched)| (!matched).then_some(anime))
+			.collect();
+
+		diff
+	}
+}
+
+#[test]
+fn db_diff() {
+	use crate::testing::{database, url, AnimeBuilder};
+
+	let old = database(vec![
+		AnimeBuilder::new("Kept").build(),
+		AnimeBuilder::new("Removed").build(),
+		AnimeBuilder::new("Airing").build(),
+	]);
+
+	let mut airing = AnimeBuilder::new("Airing").build();
+	airing.episodes = 13.into();
+	airing.tags.push("drama".into());
+	airing.sources.push(url("https://example.org/anime/other"));
+
+	let new = database(vec![
+		AnimeBuilder::new("Kept").build(),
+		airing,
+		AnimeBuilder::new("Added").build(),
+	]);
+
+	let diff = Database::diff(&old, &new);
+
+	assert_eq!(diff.added().len(), 1);
+	assert_eq!(diff.added()[0].title, "Added");
+	assert_eq!(diff.removed().len(), 1);
+	assert_eq!(diff.removed()[0].title, "Removed");
+	assert_eq!(diff.modified().len(), 1);
+
+	let changes = diff.modified()[0].changes();
+	let fields: Vec<_> = changes.iter().map(FieldChange::field).collect();
+	assert_eq!(fields, [Field::Sources, Field::Episodes, Field::Tags]);
+	assert_eq!(changes[1].to_string(), "episode count changed from 12 to 13");
+
+	assert!(Database::diff(&old, &old).is_empty());
+}
+
+#[test]
+fn db_diff_shared_source() {
+	use crate::testing::{database, url, AnimeBuilder};
+
+	// Both entries list the same AniDB page, e.g. after upstream merged
+	// them into one there.
+	let shared = url("https://anidb.net/anime/1");
+	let mut first = AnimeBuilder::new("First").build();
+	first.sources =
+		vec![shared.clone(), url("https://myanimelist.net/anime/1")];
+	let mut second = AnimeBuilder::new("Second").build();
+	second.sources =
+		vec![shared.clone(), url("https://myanimelist.net/anime/2")];
+	let old = database(vec![first.clone(), second.clone()]);
+
+	// Listed first, so the shared source alone would pair it with `first`.
+	let mut renamed = second.clone();
+	renamed.title = "Second Season".into();
+	let new = database(vec![renamed, first]);
+
+	let diff = Database::diff(&old, &new);
+	assert!(diff.added().is_empty());
+	assert!(diff.removed().is_empty());
+	assert_eq!(diff.modified().len(), 1);
+	assert_eq!(diff.modified()[0].before().title, "Second");
+}
@@ -0,0 +1,342 @@
+//! Metadata missing from the offline database, fetched from the providers
+//! listed in the sources of an entry.
+
+#[cfg(feature = "anilist")]
+mod anilist;
+#[cfg(feature = "kitsu")]
+mod kitsu;
+#[cfg(feature = "mal")]
+mod mal;
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use url::Url;
+
+#[cfg(feature = "anilist")]
+pub use self::anilist::AniList;
+#[cfg(feature = "kitsu")]
+pub use self::kitsu::Kitsu;
+#[cfg(feature = "mal")]
+pub use self::mal::MyAnimeList;
+pub use crate::fetch::Error;
+use crate::{Anime, Provider, SimpleDate};
+
+/// A scheduled episode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Airing {
+	episode: u32,
+	/// Seconds since the unix epoch.
+	airing_at: u64,
+}
+
+impl Airing {
+	pub fn episode(&self) -> u32 {
+		self.episode
+	}
+
+	pub fn airing_at(&self) -> SystemTime {
+		UNIX_EPOCH + Duration::from_secs(self.airing_at)
+	}
+
+	/// Time left until the episode airs, zero once it aired.
+	pub fn time_until(&self) -> Duration {
+		self.airing_at().duration_since(SystemTime::now()).unwrap_or_default()
+	}
+}
+
+/// A site streaming an entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamingLink {
+	site: Option<String>,
+	url: String,
+	subtitles: Vec<String>,
+	dubs: Vec<String>,
+}
+
+impl StreamingLink {
+	/// Name of the streaming site, e.g. `"Crunchyroll"`.
+	pub fn site(&self) -> Option<&str> {
+		self.site.as_deref()
+	}
+
+	pub fn url(&self) -> &str {
+		&self.url
+	}
+
+	/// Languages of the available subtitles, as language codes.
+	pub fn subtitles(&self) -> &[String] {
+		&self.subtitles
+	}
+
+	/// Languages of the available dubs, as language codes.
+	pub fn dubs(&self) -> &[String] {
+		&self.dubs
+	}
+}
+
+/// Metadata of an entry as reported by a single provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enrichment {
+	provider: Provider,
+	synopsis: Option<String>,
+	genres: Vec<String>,
+	start_date: Option<SimpleDate>,
+	end_date: Option<SimpleDate>,
+	score: Option<f64>,
+	cover_color: Option<String>,
+	next_episode: Option<Airing>,
+	airing_schedule: Vec<Airing>,
+	streaming: Vec<StreamingLink>,
+}
+
+impl Enrichment {
+	#[cfg(any(test, feature = "anilist", feature = "kitsu", feature = "mal"))]
+	pub(crate) fn new(provider: Provider) -> Self {
+		Self {
+			provider,
+			synopsis: None,
+			genres: Vec::new(),
+			start_date: None,
+			end_date: None,
+			score: None,
+			cover_color: None,
+			next_episode: None,
+			airing_schedule: Vec::new(),
+			streaming: Vec::new(),
+		}
+	}
+
+	pub fn provider(&self) -> Provider {
+		self.provider
+	}
+
+	pub fn synopsis(&self) -> Option<&str> {
+		self.synopsis.as_deref()
+	}
+
+	pub fn genres(&self) -> &[String] {
+		&self.genres
+	}
+
+	/// First day of airing, if the provider knows the exact day.
+	pub fn start_date(&self) -> Option<SimpleDate> {
+		self.start_date
+	}
+
+	/// Last day of airing, if the provider knows the exact day.
+	pub fn end_date(&self) -> Option<SimpleDate> {
+		self.end_date
+	}
+
+	/// Mean user score on a scale from 0 to 10.
+	pub fn score(&self) -> Option<f64> {
+		self.score
+	}
+
+	/// Dominant color of the cover as hex string (e.g. `"#e4a15d"`).
+	pub fn cover_color(&self) -> Option<&str> {
+		self.cover_color.as_deref()
+	}
+
+	pub fn next_episode(&self) -> Option<Airing> {
+		self.next_episode
+	}
+
+	/// Episodes scheduled to air, in airing order.
+	pub fn airing_schedule(&self) -> &[Airing] {
+		&self.airing_schedule
+	}
+
+	/// Sites the entry can be streamed on.
+	pub fn streaming(&self) -> &[StreamingLink] {
+		&self.streaming
+	}
+
+	/// Fills the fields missing from `self` with those of `other`.
+	pub fn merge(&mut self, other: &Enrichment) {
+		fn fill<T: Clone>(field: &mut Option<T>, other: &Option<T>) {
+			if field.is_none() {
+				*field = other.clone();
+			}
+		}
+
+		fill(&mut self.synopsis, &other.synopsis);
+		fill(&mut self.start_date, &other.start_date);
+		fill(&mut self.end_date, &other.end_date);
+		fill(&mut self.score, &other.score);
+		fill(&mut self.cover_color, &other.cover_color);
+		fill(&mut self.next_episode, &other.next_episode);
+
+		if self.genres.is_empty() {
+			self.genres = other.genres.clone();
+		}
+		if self.airing_schedule.is_empty() {
+			self.airing_schedule = other.airing_schedule.clone();
+		}
+		if self.streaming.is_empty() {
+			self.streaming = other.streaming.clone();
+		}
+	}
+}
+
+/// Enrichments of many entries, from any number of providers.
+#[derive(Debug, Clone, Default)]
+pub struct EnrichmentLayer {
+	entries: HashMap<Url, Vec<Enrichment>>,
+}
+
+impl EnrichmentLayer {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Entries are identified by their [`Anime::primary_source`], which
+	/// stays the same when upstream adds or reorders sources of less stable
+	/// providers.
+	fn key(anime: &Anime) -> Option<&Url> {
+		anime.primary_source()
+	}
+
+	/// Adds `enrichment` to `anime`, replacing an earlier one from the same
+	/// provider.
+	pub fn insert(&mut self, anime: &Anime, enrichment: Enrichment) {
+		let Some(key) = Self::key(anime) else {
+			return;
+		};
+		let enrichments = self.entries.entry(key.clone()).or_default();
+
+		match enrichments
+			.iter_mut()
+			.find(|e| e.provider == enrichment.provider)
+		{
+			Some(existing) => *existing = enrichment,
+			None => enrichments.push(enrichment),
+		}
+	}
+
+	/// Returns the enrichments of `anime` in insertion order.
+	pub fn get(&self, anime: &Anime) -> &[Enrichment] {
+		Self::key(anime)
+			.and_then(|key| self.entries.get(key))
+			.map(Vec::as_slice)
+			.unwrap_or_default()
+	}
+
+	pub fn get_from(
+		&self,
+		anime: &Anime,
+		provider: Provider,
+	) -> Option<&Enrichment> {
+		self.get(anime).iter().find(|e| e.provider == provider)
+	}
+
+	/// Merges all enrichments of `anime`, earlier inserted providers taking
+	/// precedence.
+	pub fn merged(&self, anime: &Anime) -> Option<Enrichment> {
+		let (first, rest) = self.get(anime).split_first()?;
+
+		Some(rest.iter().fold(first.clone(), |mut merged, other| {
+			merged.merge(other);
+			merged
+		}))
+	}
+
+	/// Fetches and inserts the enrichments of `entries` from `enricher`,
+	/// returning how many entries were found at the provider.
+	///
+	/// Stops at the first failed request, keeping everything fetched until
+	/// then.
+	pub fn enrich<'a>(
+		&mut self,
+		enricher: &impl Enricher,
+		entries: impl IntoIterator<Item = &'a Anime>,
+	) -> Result<usize, Error> {
+		let mut found = 0;
+
+		for anime in entries {
+			if let Some(enrichment) = enricher.enrich(anime)? {
+				self.insert(anime, enrichment);
+				found += 1;
+			}
+		}
+
+		Ok(found)
+	}
+}
+
+/// A client fetching [`Enrichment`]s from one provider.
+pub trait Enricher {
+	fn provider(&self) -> Provider;
+
+	/// Fetches the metadata of `anime`, `None` if it isn't listed at the
+	/// provider.
+	fn enrich(&self, anime: &Anime) -> Result<Option<Enrichment>, Error>;
+}
+
+/// Parses a `yyyy-mm-dd` date, `None` for partial dates like `2013-04`.
+#[cfg(any(feature = "kitsu", feature = "mal"))]
+pub(crate) fn full_date(s: &str) -> Option<SimpleDate> {
+	use serde::de::Visitor;
+
+	use crate::SimpleDateVisitor;
+
+	if s.split('-').count() != 3 {
+		return None;
+	}
+
+	SimpleDateVisitor.visit_str::<serde::de::value::Error>(s).ok()
+}
+
+#[test]
+fn enrichment_layer() {
+	use crate::testing::AnimeBuilder;
+
+	let anime = AnimeBuilder::new("Layered").build();
+	let other = AnimeBuilder::new("Other").build();
+
+	let mal = Enrichment {
+		synopsis: Some("From MAL".into()),
+		score: Some(8.5),
+		..Enrichment::new(Provider::MyAnimeList)
+	};
+	let anilist = Enrichment {
+		synopsis: Some("From AniList".into()),
+		cover_color: Some("#e4a15d".into()),
+		..Enrichment::new(Provider::AniList)
+	};
+
+	let mut layer = EnrichmentLayer::new();
+	layer.insert(&anime, mal.clone());
+	layer.insert(&anime, anilist);
+	layer.insert(&anime, Enrichment { score: Some(9.0), ..mal });
+
+	assert_eq!(layer.get(&anime).len(), 2);
+	assert!(layer.get(&other).is_empty());
+	assert_eq!(
+		layer.get_from(&anime, Provider::MyAnimeList).unwrap().score(),
+		Some(9.0)
+	);
+
+	let merged = layer.merged(&anime).unwrap();
+	assert_eq!(merged.provider(), Provider::MyAnimeList);
+	assert_eq!(merged.synopsis(), Some("From MAL"));
+	assert_eq!(merged.cover_color(), Some("#e4a15d"));
+	assert!(layer.merged(&other).is_none());
+}
+
+#[test]
+fn enrichment_layer_refresh() {
+	use crate::testing::{url, AnimeBuilder};
+
+	let mut anime = AnimeBuilder::new("Refreshed").build();
+	anime.sources = vec![url("https://myanimelist.net/anime/1")];
+
+	let mut layer = EnrichmentLayer::new();
+	layer.insert(&anime, Enrichment::new(Provider::MyAnimeList));
+
+	// A later database version lists another source first.
+	let mut refreshed = anime.clone();
+	refreshed.sources.insert(0, url("https://anilist.co/anime/1"));
+	assert_eq!(layer.get(&refreshed).len(), 1);
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+
+use crate::enrich::{Airing, Enricher, Enrichment, Error};
+use crate::fetch::{default_agent, FetchOptions, Retry};
+use crate::{Anime, Provider, SimpleDate};
+
+const API_URL: &str = "https://graphql.anilist.co";
+
+const MEDIA_QUERY: &str = "
+query ($id: Int) {
+	Media(id: $id, type: ANIME) {
+		description(asHtml: false)
+		genres
+		averageScore
+		startDate { year month day }
+		endDate { year month day }
+		coverImage { color }
+		nextAiringEpisode { episode airingAt }
+		airingSchedule(notYetAired: true) { nodes { episode airingAt } }
+	}
+}";
+
+/// Client for the public AniList GraphQL API, which needs no
+/// authentication.
+#[derive(Debug, Clone)]
+pub struct AniList {
+	agent: ureq::Agent,
+	retry: Retry,
+}
+
+impl AniList {
+	pub fn new() -> Self {
+		Self { agent: default_agent(), retry: Retry::none() }
+	}
+
+	/// Queries with the proxy, timeouts, headers and retries of `options`.
+	pub fn options(mut self, options: &FetchOptions) -> Result<Self, Error> {
+		self.agent = options.agent()?;
+		self.retry = options.retries();
+		Ok(self)
+	}
+
+	/// Runs a GraphQL `query`, returning its `data`.
+	fn query<V, T>(
+		&self,
+		query: &str,
+		variables: V,
+	) -> Result<Option<T>, Error>
+	where
+		V: Serialize,
+		T: for<'de> Deserialize<'de>,
+	{
+		#[derive(Serialize)]
+		struct Request<'a, V> {
+			query: &'a str,
+			variables: V,
+		}
+
+		#[derive(Deserialize)]
+		struct Response<T> {
+			data: Option<T>,
+		}
+
+		let request = Request { query, variables };
+
+		self.retry.run(|| {
+			let response = self.agent.post(API_URL).send_json(&request);
+
+			// Unknown ids are answered with a 404 and errors instead of data.
+			let response: Response<T> = match response {
+				Ok(response) => response.into_json()?,
+				Err(ureq::Error::Status(404, _)) => return Ok(None),
+				Err(err) => return Err(err.into()),
+			};
+
+			Ok(response.data)
+		})
+	}
+}
+
+impl Default for AniList {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[derive(Serialize)]
+struct MediaVariables {
+	id: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct MediaData {
+	media: Option<Media>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Media {
+	description: Option<String>,
+	#[serde(default)]
+	genres: Vec<String>,
+	average_score: Option<u8>,
+	start_date: Option<FuzzyDate>,
+	end_date: Option<FuzzyDate>,
+	cover_image: Option<CoverImage>,
+	next_airing_episode: Option<AiringEpisode>,
+	airing_schedule: Option<AiringSchedule>,
+}
+
+/// A date where any part may be unknown.
+#[derive(Deserialize)]
+struct FuzzyDate {
+	year: Option<u16>,
+	month: Option<u8>,
+	day: Option<u8>,
+}
+
+impl FuzzyDate {
+	fn full(&self) -> Option<SimpleDate> {
+		Some(SimpleDate {
+			year: self.year?,
+			month: self.month?,
+			day: self.day?,
+		})
+	}
+}
+
+#[derive(Deserialize)]
+struct CoverImage {
+	color: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AiringEpisode {
+	episode: u32,
+	airing_at: u64,
+}
+
+impl From<AiringEpisode> for Airing {
+	fn from(airing: AiringEpisode) -> Self {
+		Self { episode: airing.episode, airing_at: airing.airing_at }
+	}
+}
+
+#[derive(Deserialize)]
+struct AiringSchedule {
+	nodes: Vec<AiringEpisode>,
+}
+
+impl From<Media> for Enrichment {
+	fn from(media: Media) -> Self {
+		let mut airing_schedule: Vec<Airing> = media
+			.airing_schedule
+			.map(|schedule| {
+				schedule.nodes.into_iter().map(Into::into).collect()
+			})
+			.unwrap_or_default();
+		airing_schedule.sort();
+
+		Self {
+			synopsis: media.description.filter(|s| !s.is_empty()),
+			genres: media.genres,
+			start_date: media.start_date.as_ref().and_then(FuzzyDate::full),
+			end_date: media.end_date.as_ref().and_then(FuzzyDate::full),
+			score: media.average_score.map(|score| f64::from(score) / 10.0),
+			cover_color: media.cover_image.and_then(|image| image.color),
+			next_episode: media.next_airing_episode.map(Into::into),
+			airing_schedule,
+			..Self::new(Provider::AniList)
+		}
+	}
+}
+
+impl Enricher for AniList {
+	fn provider(&self) -> Provider {
+		Provider::AniList
+	}
+
+	fn enrich(&self, anime: &Anime) -> Result<Option<Enrichment>, Error> {
+		let Some(id) =
+			anime.source_id(Provider::AniList).and_then(|id| id.parse().ok())
+		else {
+			return Ok(None);
+		};
+
+		let data: Option<MediaData> =
+			self.query(MEDIA_QUERY, MediaVariables { id })?;
+
+		Ok(data.and_then(|data| data.media).map(Into::into))
+	}
+}
+
+#[test]
+fn anilist_response() {
+	let data: MediaData = serde_json::from_str(
+		r##"{
+			"Media": {
+				"description": "Light Yagami is a genius high school student...",
+				"genres": ["Mystery", "Psychological"],
+				"averageScore": 84,
+				"startDate": { "year": 2006, "month": 10, "day": 4 },
+				"endDate": { "year": 2007, "month": null, "day": null },
+				"coverImage": { "color": "#e4a15d" },
+				"nextAiringEpisode": { "episode": 3, "airingAt": 1700000000 },
+				"airingSchedule": {
+					"nodes": [
+						{ "episode": 4, "airingAt": 1700604800 },
+						{ "episode": 3, "airingAt": 1700000000 }
+					]
+				}
+			}
+		}"##,
+	)
+	.unwrap();
+
+	let enrichment = Enrichment::from(data.media.unwrap());
+	assert_eq!(enrichment.provider(), Provider::AniList);
+	assert_eq!(enrichment.score(), Some(8.4));
+	assert_eq!(
+		enrichment.start_date(),
+		Some(SimpleDate { year: 2006, month: 10, day: 4 })
+	);
+	assert_eq!(enrichment.end_date(), None);
+	assert_eq!(enrichment.cover_color(), Some("#e4a15d"));
+	assert_eq!(enrichment.next_episode().unwrap().episode(), 3);
+
+	let episodes: Vec<_> =
+		enrichment.airing_schedule().iter().map(Airing::episode).collect();
+	assert_eq!(episodes, [3, 4]);
+}
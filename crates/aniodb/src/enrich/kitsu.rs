@@ -0,0 +1,231 @@
+use serde::Deserialize;
+
+use crate::enrich::{full_date, Enricher, Enrichment, Error, StreamingLink};
+use crate::fetch::{default_agent, FetchOptions, Retry};
+use crate::{Anime, Provider};
+
+const API_URL: &str = "https://kitsu.io/api/edge";
+
+/// Client for the public Kitsu JSON:API, which needs no authentication.
+#[derive(Debug, Clone)]
+pub struct Kitsu {
+	agent: ureq::Agent,
+	retry: Retry,
+}
+
+impl Kitsu {
+	pub fn new() -> Self {
+		Self { agent: default_agent(), retry: Retry::none() }
+	}
+
+	/// Requests with the proxy, timeouts, headers and retries of `options`.
+	pub fn options(mut self, options: &FetchOptions) -> Result<Self, Error> {
+		self.agent = options.agent()?;
+		self.retry = options.retries();
+		Ok(self)
+	}
+}
+
+impl Default for Kitsu {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[derive(Deserialize)]
+struct Document {
+	data: Resource,
+	#[serde(default)]
+	included: Vec<Included>,
+}
+
+#[derive(Deserialize)]
+struct Resource {
+	attributes: AnimeAttributes,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnimeAttributes {
+	synopsis: Option<String>,
+	/// Percentage formatted as string, e.g. `"82.14"`.
+	average_rating: Option<String>,
+	start_date: Option<String>,
+	end_date: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum Included {
+	#[serde(rename = "streamingLinks")]
+	StreamingLink {
+		attributes: LinkAttributes,
+		relationships: LinkRelationships,
+	},
+	#[serde(rename = "streamers")]
+	Streamer { id: String, attributes: StreamerAttributes },
+	#[serde(other)]
+	Other,
+}
+
+#[derive(Deserialize)]
+struct LinkAttributes {
+	url: String,
+	#[serde(default)]
+	subs: Vec<String>,
+	#[serde(default)]
+	dubs: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct LinkRelationships {
+	streamer: Relationship,
+}
+
+#[derive(Deserialize)]
+struct Relationship {
+	data: Option<Identifier>,
+}
+
+#[derive(Deserialize)]
+struct Identifier {
+	id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamerAttributes {
+	site_name: String,
+}
+
+impl From<Document> for Enrichment {
+	fn from(document: Document) -> Self {
+		let attributes = document.data.attributes;
+
+		let site_name = |id: &str| {
+			document.included.iter().find_map(|included| match included {
+				Included::Streamer { id: streamer, attributes }
+					if streamer == id =>
+				{
+					Some(attributes.site_name.clone())
+				}
+				_ => None,
+			})
+		};
+
+		let streaming = document
+			.included
+			.iter()
+			.filter_map(|included| match included {
+				Included::StreamingLink { attributes, relationships } => {
+					let site = relationships
+						.streamer
+						.data
+						.as_ref()
+						.and_then(|streamer| site_name(&streamer.id));
+
+					Some(StreamingLink {
+						site,
+						url: attributes.url.clone(),
+						subtitles: attributes.subs.clone(),
+						dubs: attributes.dubs.clone(),
+					})
+				}
+				_ => None,
+			})
+			.collect();
+
+		Self {
+			synopsis: attributes.synopsis.filter(|s| !s.is_empty()),
+			start_date: attributes.start_date.as_deref().and_then(full_date),
+			end_date: attributes.end_date.as_deref().and_then(full_date),
+			score: attributes
+				.average_rating
+				.and_then(|rating| rating.parse::<f64>().ok())
+				.map(|rating| rating / 10.0),
+			streaming,
+			..Self::new(Provider::Kitsu)
+		}
+	}
+}
+
+impl Enricher for Kitsu {
+	fn provider(&self) -> Provider {
+		Provider::Kitsu
+	}
+
+	fn enrich(&self, anime: &Anime) -> Result<Option<Enrichment>, Error> {
+		let Some(id) = anime.source_id(Provider::Kitsu) else {
+			return Ok(None);
+		};
+
+		self.retry.run(|| {
+			let response = self
+				.agent
+				.get(&format!("{API_URL}/anime/{id}"))
+				.query("include", "streamingLinks,streamingLinks.streamer")
+				.set("Accept", "application/vnd.api+json")
+				.call();
+
+			let document: Document = match response {
+				Ok(response) => response.into_json()?,
+				Err(ureq::Error::Status(404, _)) => return Ok(None),
+				Err(err) => return Err(err.into()),
+			};
+
+			Ok(Some(document.into()))
+		})
+	}
+}
+
+#[test]
+fn kitsu_response() {
+	let document: Document = serde_json::from_str(
+		r#"{
+			"data": {
+				"id": "1376",
+				"type": "anime",
+				"attributes": {
+					"synopsis": "Light Yagami is an ace student...",
+					"averageRating": "84.05",
+					"startDate": "2006-10-04",
+					"endDate": "2007-06-27"
+				}
+			},
+			"included": [
+				{
+					"id": "10",
+					"type": "streamingLinks",
+					"attributes": {
+						"url": "http://www.hulu.com/death-note",
+						"subs": ["en"],
+						"dubs": ["ja"]
+					},
+					"relationships": {
+						"streamer": { "data": { "type": "streamers", "id": "1" } }
+					}
+				},
+				{
+					"id": "1",
+					"type": "streamers",
+					"attributes": { "siteName": "Hulu" }
+				},
+				{ "id": "7", "type": "categories", "attributes": {} }
+			]
+		}"#,
+	)
+	.unwrap();
+
+	let enrichment = Enrichment::from(document);
+	assert_eq!(enrichment.provider(), Provider::Kitsu);
+	assert_eq!(enrichment.score(), Some(8.405));
+	assert!(enrichment.end_date().is_some());
+
+	let [link] = enrichment.streaming() else {
+		panic!("expected a single streaming link");
+	};
+	assert_eq!(link.site(), Some("Hulu"));
+	assert_eq!(link.url(), "http://www.hulu.com/death-note");
+	assert_eq!(link.subtitles(), ["en"]);
+	assert_eq!(link.dubs(), ["ja"]);
+}
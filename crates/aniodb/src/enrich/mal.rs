@@ -0,0 +1,125 @@
+use serde::Deserialize;
+
+use crate::enrich::{full_date, Enricher, Enrichment, Error};
+use crate::fetch::{default_agent, FetchOptions, Retry};
+use crate::{Anime, Provider};
+
+const API_URL: &str = "https://api.myanimelist.net/v2";
+const FIELDS: &str = "synopsis,genres,start_date,end_date,mean";
+
+/// Client for the official MyAnimeList API.
+///
+/// Requests are authenticated with the client id of an API client, which
+/// can be registered at <https://myanimelist.net/apiconfig>.
+#[derive(Debug, Clone)]
+pub struct MyAnimeList {
+	client_id: String,
+	agent: ureq::Agent,
+	retry: Retry,
+}
+
+impl MyAnimeList {
+	pub fn new(client_id: impl Into<String>) -> Self {
+		Self {
+			client_id: client_id.into(),
+			agent: default_agent(),
+			retry: Retry::none(),
+		}
+	}
+
+	/// Requests with the proxy, timeouts, headers and retries of `options`.
+	/// The client id header is sent in addition to its headers.
+	pub fn options(mut self, options: &FetchOptions) -> Result<Self, Error> {
+		self.agent = options.agent()?;
+		self.retry = options.retries();
+		Ok(self)
+	}
+}
+
+#[derive(Deserialize)]
+struct Response {
+	synopsis: Option<String>,
+	#[serde(default)]
+	genres: Vec<Genre>,
+	start_date: Option<String>,
+	end_date: Option<String>,
+	mean: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Genre {
+	name: String,
+}
+
+impl From<Response> for Enrichment {
+	fn from(response: Response) -> Self {
+		Self {
+			synopsis: response.synopsis.filter(|s| !s.is_empty()),
+			genres: response.genres.into_iter().map(|g| g.name).collect(),
+			start_date: response.start_date.as_deref().and_then(full_date),
+			end_date: response.end_date.as_deref().and_then(full_date),
+			score: response.mean,
+			..Self::new(Provider::MyAnimeList)
+		}
+	}
+}
+
+impl Enricher for MyAnimeList {
+	fn provider(&self) -> Provider {
+		Provider::MyAnimeList
+	}
+
+	fn enrich(&self, anime: &Anime) -> Result<Option<Enrichment>, Error> {
+		let Some(id) = anime.source_id(Provider::MyAnimeList) else {
+			return Ok(None);
+		};
+
+		self.retry.run(|| {
+			let response = self
+				.agent
+				.get(&format!("{API_URL}/anime/{id}"))
+				.query("fields", FIELDS)
+				.set("X-MAL-CLIENT-ID", &self.client_id)
+				.call();
+
+			let response: Response = match response {
+				Ok(response) => response.into_json()?,
+				Err(ureq::Error::Status(404, _)) => return Ok(None),
+				Err(err) => return Err(err.into()),
+			};
+
+			Ok(Some(response.into()))
+		})
+	}
+}
+
+#[test]
+fn mal_response() {
+	use crate::SimpleDate;
+
+	let response: Response = serde_json::from_str(
+		r#"{
+			"id": 1535,
+			"title": "Death Note",
+			"synopsis": "A shinigami, as a god of death...",
+			"genres": [
+				{ "id": 37, "name": "Supernatural" },
+				{ "id": 41, "name": "Suspense" }
+			],
+			"start_date": "2006-10-04",
+			"end_date": "2007-06",
+			"mean": 8.62
+		}"#,
+	)
+	.unwrap();
+
+	let enrichment = Enrichment::from(response);
+	assert_eq!(enrichment.provider(), Provider::MyAnimeList);
+	assert_eq!(enrichment.genres(), ["Supernatural", "Suspense"]);
+	assert_eq!(
+		enrichment.start_date(),
+		Some(SimpleDate { year: 2006, month: 10, day: 4 })
+	);
+	assert_eq!(enrichment.end_date(), None);
+	assert_eq!(enrichment.score(), Some(8.62));
+}
@@ -0,0 +1,250 @@
+//! Export of entries to CSV and TSV.
+//!
+//! With the `arrow` feature entries can also be exported to Arrow record
+//! batches and Parquet files, see [`arrow`](self::arrow).
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+
+use std::io::{self, Write};
+
+use crate::{Anime, Database};
+
+/// A column of the exported table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Column {
+	Title,
+	Kind,
+	Episodes,
+	Status,
+	Season,
+	Year,
+	Duration,
+	Score,
+	Sources,
+	Synonyms,
+	Relations,
+	Tags,
+	Studios,
+	Producers,
+	Picture,
+	Thumbnail,
+}
+
+impl Column {
+	/// Name used in the header row.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::Title => "title",
+			Self::Kind => "type",
+			Self::Episodes => "episodes",
+			Self::Status => "status",
+			Self::Season => "season",
+			Self::Year => "year",
+			Self::Duration => "duration",
+			Self::Score => "score",
+			Self::Sources => "sources",
+			Self::Synonyms => "synonyms",
+			Self::Relations => "relations",
+			Self::Tags => "tags",
+			Self::Studios => "studios",
+			Self::Producers => "producers",
+			Self::Picture => "picture",
+			Self::Thumbnail => "thumbnail",
+		}
+	}
+
+	fn value(&self, anime: &Anime, list_separator: &str) -> String {
+		fn join<T: AsRef<str>>(values: &[T], separator: &str) -> String {
+			values
+				.iter()
+				.map(AsRef::as_ref)
+				.collect::<Vec<_>>()
+				.join(separator)
+		}
+
+		let season = anime.anime_season();
+
+		match self {
+			Self::Title => anime.title.clone(),
+			Self::Kind => format!("{:?}", anime.kind),
+			Self::Episodes => {
+				anime.episodes.get().map(|n| n.to_string()).unwrap_or_default()
+			}
+			Self::Status => format!("{:?}", anime.status),
+			Self::Season => format!("{:?}", season.season),
+			Self::Year => {
+				season.year.map(|y| y.to_string()).unwrap_or_default()
+			}
+			Self::Duration => anime
+				.duration()
+				.map(|d| d.as_secs().to_string())
+				.unwrap_or_default(),
+			Self::Score => anime
+				.score
+				.map(|s| s.arithmetic_geometric_mean.to_string())
+				.unwrap_or_default(),
+			Self::Sources => join(&anime.sources, list_separator),
+			Self::Synonyms => join(&anime.synonyms, list_separator),
+			Self::Relations => join(&anime.relations, list_separator),
+			Self::Tags => join(&anime.tags, list_separator),
+			Self::Studios => join(anime.studios(), list_separator),
+			Self::Producers => join(anime.producers(), list_separator),
+			Self::Picture => anime.picture.to_string(),
+			Self::Thumbnail => anime.thumbnail.to_string(),
+		}
+	}
+}
+
+/// Output format of [`write`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+	delimiter: char,
+	list_separator: String,
+	header: bool,
+	columns: Vec<Column>,
+}
+
+impl ExportOptions {
+	/// Comma separated values with a header row.
+	pub fn csv() -> Self {
+		Self {
+			delimiter: ',',
+			list_separator: "|".into(),
+			header: true,
+			columns: vec![
+				Column::Title,
+				Column::Kind,
+				Column::Episodes,
+				Column::Status,
+				Column::Season,
+				Column::Year,
+				Column::Score,
+				Column::Tags,
+				Column::Sources,
+			],
+		}
+	}
+
+	/// Tab separated values with a header row.
+	pub fn tsv() -> Self {
+		Self { delimiter: '\t', ..Self::csv() }
+	}
+
+	pub fn columns(
+		mut self,
+		columns: impl IntoIterator<Item = Column>,
+	) -> Self {
+		self.columns = columns.into_iter().collect();
+		self
+	}
+
+	pub fn header(mut self, header: bool) -> Self {
+		self.header = header;
+		self
+	}
+
+	/// Separator used to join list columns like tags or sources.
+	pub fn list_separator(mut self, separator: impl Into<String>) -> Self {
+		self.list_separator = separator.into();
+		self
+	}
+
+	fn write_row<'v>(
+		&self,
+		w: &mut impl Write,
+		values: impl IntoIterator<Item = &'v str>,
+	) -> io::Result<()> {
+		for (idx, value) in values.into_iter().enumerate() {
+			if idx > 0 {
+				write!(w, "{}", self.delimiter)?;
+			}
+
+			let needs_quotes = value.contains(|c: char| {
+				c == self.delimiter || matches!(c, '"' | '\r' | '\n')
+			});
+
+			if needs_quotes {
+				write!(w, "\"{}\"", value.replace('"', "\"\""))?;
+			} else {
+				w.write_all(value.as_bytes())?;
+			}
+		}
+
+		w.write_all(b"\n")
+	}
+}
+
+impl Default for ExportOptions {
+	fn default() -> Self {
+		Self::csv()
+	}
+}
+
+/// Writes `entries` as a table.
+///
+/// Fields containing the delimiter, quotes or line breaks are quoted.
+pub fn write<'a>(
+	mut w: impl Write,
+	entries: impl IntoIterator<Item = &'a Anime>,
+	opts: &ExportOptions,
+) -> io::Result<()> {
+	if opts.header {
+		opts.write_row(&mut w, opts.columns.iter().map(Column::name))?;
+	}
+
+	for anime in entries {
+		let values: Vec<_> = opts
+			.columns
+			.iter()
+			.map(|column| column.value(anime, &opts.list_separator))
+			.collect();
+
+		opts.write_row(&mut w, values.iter().map(String::as_str))?;
+	}
+
+	Ok(())
+}
+
+impl Database {
+	/// Writes all entries as a table, see [`write`].
+	pub fn export(
+		&self,
+		w: impl Write,
+		opts: &ExportOptions,
+	) -> io::Result<()> {
+		write(w, self.data.iter(), opts)
+	}
+}
+
+#[test]
+fn export_csv() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Plain").tag("a").tag("b").build(),
+		AnimeBuilder::new("Comma, \"Quote\"").year(2001).build(),
+	]);
+
+	let opts = ExportOptions::csv().columns([
+		Column::Title,
+		Column::Episodes,
+		Column::Year,
+		Column::Tags,
+	]);
+
+	let mut out = Vec::new();
+	db.export(&mut out, &opts).unwrap();
+
+	assert_eq!(
+		String::from_utf8(out).unwrap(),
+		"title,episodes,year,tags\nPlain,12,,a|b\n\"Comma, \
+		 \"\"Quote\"\"\",12,2001,\n"
+	);
+
+	let mut out = Vec::new();
+	let opts = ExportOptions::tsv().header(false).columns([Column::Title]);
+	write(&mut out, db.query().year(2001).run(), &opts).unwrap();
+
+	assert_eq!(String::from_utf8(out).unwrap(), "\"Comma, \"\"Quote\"\"\"\n");
+}
@@ -0,0 +1,186 @@
+//! Export of entries to Apache Arrow record batches and Parquet files.
+
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow::array::{
+	ArrayRef, Float64Array, ListBuilder, StringArray, StringBuilder,
+	UInt32Array, UInt64Array,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::errors::ParquetError;
+
+use crate::{Anime, Database};
+
+fn list_field(name: &str) -> Field {
+	let item = Field::new("item", DataType::Utf8, true);
+	Field::new(name, DataType::List(Box::new(item)), false)
+}
+
+/// Schema of the batches returned by [`record_batch`].
+///
+/// Sources, synonyms, relations, tags, studios and producers are lists of
+/// strings. Unknown episode counts, years, durations (in seconds) and
+/// scores are null.
+pub fn schema() -> SchemaRef {
+	Arc::new(Schema::new(vec![
+		Field::new("title", DataType::Utf8, false),
+		Field::new("type", DataType::Utf8, false),
+		Field::new("episodes", DataType::UInt32, true),
+		Field::new("status", DataType::Utf8, false),
+		Field::new("season", DataType::Utf8, false),
+		Field::new("year", DataType::UInt32, true),
+		Field::new("duration", DataType::UInt64, true),
+		Field::new("score", DataType::Float64, true),
+		list_field("sources"),
+		list_field("synonyms"),
+		list_field("relations"),
+		list_field("tags"),
+		list_field("studios"),
+		list_field("producers"),
+		Field::new("picture", DataType::Utf8, false),
+		Field::new("thumbnail", DataType::Utf8, false),
+	]))
+}
+
+fn strings<'a, T: AsRef<str>>(
+	entries: &[&'a Anime],
+	value: impl Fn(&'a Anime) -> T,
+) -> ArrayRef {
+	Arc::new(StringArray::from_iter_values(
+		entries.iter().map(|&anime| value(anime)),
+	))
+}
+
+fn lists<'a, T: AsRef<str> + 'a>(
+	entries: &[&'a Anime],
+	values: impl Fn(&'a Anime) -> &'a [T],
+) -> ArrayRef {
+	let mut builder = ListBuilder::new(StringBuilder::new());
+
+	for &anime in entries {
+		for value in values(anime) {
+			builder.values().append_value(value);
+		}
+		builder.append(true);
+	}
+
+	Arc::new(builder.finish())
+}
+
+/// Converts `entries` into a single record batch with the columns of
+/// [`schema`].
+pub fn record_batch<'a>(
+	entries: impl IntoIterator<Item = &'a Anime>,
+) -> Result<RecordBatch, ArrowError> {
+	let entries: Vec<_> = entries.into_iter().collect();
+
+	let columns: Vec<ArrayRef> = vec![
+		strings(&entries, |anime| anime.title.as_str()),
+		strings(&entries, |anime| format!("{:?}", anime.kind)),
+		Arc::new(
+			entries
+				.iter()
+				.map(|anime| anime.episodes.get())
+				.collect::<UInt32Array>(),
+		),
+		strings(&entries, |anime| format!("{:?}", anime.status)),
+		strings(&entries, |anime| {
+			format!("{:?}", anime.anime_season().season)
+		}),
+		Arc::new(
+			entries
+				.iter()
+				.map(|anime| anime.anime_season().year)
+				.collect::<UInt32Array>(),
+		),
+		Arc::new(
+			entries
+				.iter()
+				.map(|anime| anime.duration().map(|d| d.as_secs()))
+				.collect::<UInt64Array>(),
+		),
+		Arc::new(
+			entries
+				.iter()
+				.map(|anime| anime.score.map(|s| s.arithmetic_geometric_mean))
+				.collect::<Float64Array>(),
+		),
+		lists(&entries, |anime| anime.sources.as_slice()),
+		lists(&entries, |anime| anime.synonyms.as_slice()),
+		lists(&entries, |anime| anime.relations.as_slice()),
+		lists(&entries, |anime| anime.tags.as_slice()),
+		lists(&entries, Anime::studios),
+		lists(&entries, Anime::producers),
+		strings(&entries, |anime| anime.picture.as_str()),
+		strings(&entries, |anime| anime.thumbnail.as_str()),
+	];
+
+	RecordBatch::try_new(schema(), columns)
+}
+
+/// Writes `entries` as a Parquet file with the columns of [`schema`].
+pub fn write_parquet<'a>(
+	w: impl Write + Send,
+	entries: impl IntoIterator<Item = &'a Anime>,
+) -> Result<(), ParquetError> {
+	let batch = record_batch(entries)?;
+
+	let mut writer = ArrowWriter::try_new(w, batch.schema(), None)?;
+	writer.write(&batch)?;
+	writer.close()?;
+
+	Ok(())
+}
+
+impl Database {
+	/// Converts all entries into a record batch, see [`record_batch`].
+	pub fn to_record_batch(&self) -> Result<RecordBatch, ArrowError> {
+		record_batch(self.data.iter())
+	}
+
+	/// Writes all entries as a Parquet file, see [`write_parquet`].
+	pub fn write_parquet(
+		&self,
+		w: impl Write + Send,
+	) -> Result<(), ParquetError> {
+		write_parquet(w, self.data.iter())
+	}
+}
+
+#[test]
+fn export_arrow() {
+	use arrow::array::{Array, ListArray};
+
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Tagged").tag("a").tag("b").year(2001).build(),
+		AnimeBuilder::new("Unknown").episodes(0).build(),
+	]);
+
+	let batch = db.to_record_batch().unwrap();
+	assert_eq!(batch.num_rows(), 2);
+	assert_eq!(batch.schema(), schema());
+
+	let column = |name: &str| batch.column(schema().index_of(name).unwrap());
+
+	let tags = column("tags");
+	let tags = tags.as_any().downcast_ref::<ListArray>().unwrap();
+	let first = tags.value(0);
+	let first = first.as_any().downcast_ref::<StringArray>().unwrap();
+	assert_eq!((first.value(0), first.value(1)), ("a", "b"));
+	assert_eq!(tags.value(1).len(), 0);
+
+	let episodes = column("episodes");
+	let episodes = episodes.as_any().downcast_ref::<UInt32Array>().unwrap();
+	assert_eq!(episodes.value(0), 12);
+	assert!(episodes.is_null(1));
+
+	let mut out = Vec::new();
+	db.write_parquet(&mut out).unwrap();
+	assert!(out.starts_with(b"PAR1") && out.ends_with(b"PAR1"));
+}
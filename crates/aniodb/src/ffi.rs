@@ -0,0 +1,237 @@
+//! C ABI for embedding the database into non-Rust frontends.
+//!
+//! Building with the `ffi` feature generates the matching header into
+//! `include/aniodb.h`.
+//!
+//! Every pointer returned by these functions has to be released with the
+//! matching `*_free` function. Strings passed in have to be nul terminated
+//! UTF-8, strings handed out are owned by the object they were taken from.
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use crate::query::Filter;
+use crate::{Anime, Database};
+
+/// Entries returned by [`aniodb_search`] and [`aniodb_resolve`].
+pub struct AniodbResults {
+	titles: Vec<CString>,
+	ids: Vec<CString>,
+}
+
+impl AniodbResults {
+	fn new<'a>(entries: impl IntoIterator<Item = &'a Anime>) -> Self {
+		let (titles, ids) = entries
+			.into_iter()
+			.map(|anime| {
+				(c_string(&anime.title), c_string(&anime.id().to_string()))
+			})
+			.unzip();
+
+		Self { titles, ids }
+	}
+
+	fn get(list: &[CString], idx: usize) -> *const c_char {
+		list.get(idx).map_or(ptr::null(), |s| s.as_ptr())
+	}
+}
+
+/// Drops interior nul bytes, which C strings can't contain.
+fn c_string(s: &str) -> CString {
+	CString::new(s.replace('\0', "")).unwrap_or_default()
+}
+
+/// # Safety
+///
+/// `s` has to be null or a valid nul terminated string.
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+	if s.is_null() {
+		return None;
+	}
+
+	CStr::from_ptr(s).to_str().ok()
+}
+
+fn into_raw<T>(value: T) -> *mut T {
+	Box::into_raw(Box::new(value))
+}
+
+/// Loads the database at `path`.
+///
+/// Returns null if the file can't be read or parsed.
+///
+/// # Safety
+///
+/// `path` has to be a valid nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn aniodb_load(path: *const c_char) -> *mut Database {
+	let Some(path) = str_arg(path) else {
+		return ptr::null_mut();
+	};
+
+	Database::load(path).map_or(ptr::null_mut(), into_raw)
+}
+
+/// Releases a database returned by [`aniodb_load`], null is ignored.
+///
+/// # Safety
+///
+/// `db` has to come from [`aniodb_load`] and mustn't be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn aniodb_free(db: *mut Database) {
+	if !db.is_null() {
+		drop(Box::from_raw(db));
+	}
+}
+
+/// Returns the entries matching the query string `query`, e.g.
+/// `kind:tv tag:(mecha OR drama)`, in database order. See
+/// [`Filter`](crate::query::Filter) for the syntax.
+///
+/// Returns null if the query is invalid.
+///
+/// # Safety
+///
+/// `db` has to be a live database and `query` a valid nul terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn aniodb_search(
+	db: *const Database,
+	query: *const c_char,
+) -> *mut AniodbResults {
+	let (Some(db), Some(query)) = (db.as_ref(), str_arg(query)) else {
+		return ptr::null_mut();
+	};
+
+	match query.parse::<Filter>() {
+		Ok(filter) => {
+			into_raw(AniodbResults::new(db.query().filter(filter).run()))
+		}
+		Err(_) => ptr::null_mut(),
+	}
+}
+
+/// Looks up the entry titled `title`, see
+/// [`Database::resolve`](crate::Database::resolve). The results are empty
+/// if there is none.
+///
+/// # Safety
+///
+/// `db` has to be a live database and `title` a valid nul terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn aniodb_resolve(
+	db: *const Database,
+	title: *const c_char,
+) -> *mut AniodbResults {
+	let (Some(db), Some(title)) = (db.as_ref(), str_arg(title)) else {
+		return ptr::null_mut();
+	};
+
+	into_raw(AniodbResults::new(
+		db.resolve(title).map(|resolved| resolved.anime()),
+	))
+}
+
+/// Number of entries in `results`.
+///
+/// # Safety
+///
+/// `results` has to be live results.
+#[no_mangle]
+pub unsafe extern "C" fn aniodb_results_len(
+	results: *const AniodbResults,
+) -> usize {
+	results.as_ref().map_or(0, |results| results.titles.len())
+}
+
+/// Title of the entry at `idx`, null if out of bounds.
+///
+/// # Safety
+///
+/// `results` has to be live results, the title is valid until they are
+/// released.
+#[no_mangle]
+pub unsafe extern "C" fn aniodb_results_title(
+	results: *const AniodbResults,
+	idx: usize,
+) -> *const c_char {
+	results.as_ref().map_or(ptr::null(), |results| {
+		AniodbResults::get(&results.titles, idx)
+	})
+}
+
+/// Stable id (see [`AnimeId`](crate::AnimeId)) of the entry at `idx`, null
+/// if out of bounds.
+///
+/// # Safety
+///
+/// `results` has to be live results, the id is valid until they are
+/// released.
+#[no_mangle]
+pub unsafe extern "C" fn aniodb_results_id(
+	results: *const AniodbResults,
+	idx: usize,
+) -> *const c_char {
+	results
+		.as_ref()
+		.map_or(ptr::null(), |results| AniodbResults::get(&results.ids, idx))
+}
+
+/// Releases results, null is ignored.
+///
+/// # Safety
+///
+/// `results` has to come from [`aniodb_search`] or [`aniodb_resolve`] and
+/// mustn't be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn aniodb_results_free(results: *mut AniodbResults) {
+	if !results.is_null() {
+		drop(Box::from_raw(results));
+	}
+}
+
+#[test]
+fn ffi_search() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let dir = std::env::temp_dir().join("aniodb-ffi-search");
+	std::fs::create_dir_all(&dir).unwrap();
+	let path = dir.join("db.json");
+
+	let db = database(vec![
+		AnimeBuilder::new("Mecha").tag("mecha").build(),
+		AnimeBuilder::new("Drama").tag("drama").synonym("Tragedy").build(),
+	]);
+	std::fs::write(&path, serde_json::to_vec(&db).unwrap()).unwrap();
+
+	let c = |s: &str| CString::new(s).unwrap();
+	let text =
+		|s: *const c_char| unsafe { CStr::from_ptr(s) }.to_str().unwrap();
+
+	unsafe {
+		let path = c(path.to_str().unwrap());
+		let db = aniodb_load(path.as_ptr());
+		assert!(!db.is_null());
+
+		let results = aniodb_search(db, c("tag:(drama OR romance)").as_ptr());
+		assert_eq!(aniodb_results_len(results), 1);
+		assert_eq!(text(aniodb_results_title(results, 0)), "Drama");
+		assert_eq!(
+			text(aniodb_results_id(results, 0)),
+			(&(*db)).data[1].id().to_string()
+		);
+		assert!(aniodb_results_title(results, 1).is_null());
+		aniodb_results_free(results);
+
+		let results = aniodb_resolve(db, c("tragedy").as_ptr());
+		assert_eq!(text(aniodb_results_title(results, 0)), "Drama");
+		aniodb_results_free(results);
+
+		assert!(aniodb_search(db, c("tag:(").as_ptr()).is_null());
+
+		aniodb_free(db);
+	}
+
+	assert!(unsafe { aniodb_load(c("/nonexistent").as_ptr()) }.is_null());
+}
@@ -0,0 +1,257 @@
+//! Shared database snapshots that can be replaced while in use.
+
+use std::error::Error;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::{ChangeEvent, Database};
+
+/// Error returned by the loader of a [`DatabaseHandle`].
+pub type LoadError = Box<dyn Error + Send + Sync>;
+
+type Loader = Box<dyn Fn() -> Result<Database, LoadError> + Send + Sync>;
+
+struct Shared {
+	current: RwLock<Arc<Database>>,
+	loader: Loader,
+	subscribers: Mutex<Vec<Sender<ChangeEvent>>>,
+}
+
+/// A cheaply clonable handle to the current snapshot of a database.
+///
+/// [`DatabaseHandle::refresh`] swaps in a newly loaded snapshot, readers
+/// holding on to an older one from [`DatabaseHandle::get`] keep using it
+/// undisturbed until they drop it.
+#[derive(Clone)]
+pub struct DatabaseHandle {
+	shared: Arc<Shared>,
+}
+
+impl DatabaseHandle {
+	/// Wraps `db`, using `loader` to produce newer snapshots.
+	pub fn new<F, E>(db: Database, loader: F) -> Self
+	where
+		F: Fn() -> Result<Database, E> + Send + Sync + 'static,
+		E: Into<LoadError>,
+	{
+		Self {
+			shared: Arc::new(Shared {
+				current: RwLock::new(Arc::new(db)),
+				loader: Box::new(move || loader().map_err(Into::into)),
+				subscribers: Mutex::new(Vec::new()),
+			}),
+		}
+	}
+
+	/// Loads the database at `path`, refreshing reloads the file.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	pub fn load(path: impl Into<PathBuf>) -> Result<Self, std::io::Error> {
+		let path = path.into();
+		let db = Database::load(&path)?;
+
+		Ok(Self::new(db, move || Database::load(&path)))
+	}
+
+	/// Returns the current snapshot.
+	pub fn get(&self) -> Arc<Database> {
+		self.shared.current.read().expect("lock poisoned").clone()
+	}
+
+	/// Loads a new snapshot and makes it the current one.
+	///
+	/// If loading fails the current snapshot is kept. Subscribers are sent
+	/// the changes between the old and the new snapshot.
+	pub fn refresh(&self) -> Result<Arc<Database>, LoadError> {
+		let db = Arc::new((self.shared.loader)()?);
+		let old = std::mem::replace(
+			&mut *self.shared.current.write().expect("lock poisoned"),
+			Arc::clone(&db),
+		);
+
+		self.notify(&old, &db);
+
+		Ok(db)
+	}
+
+	/// Returns a channel receiving a [`ChangeEvent`] for every changed field
+	/// of every entry whenever a refresh swaps in a new snapshot.
+	///
+	/// Entries are matched as by [`Database::diff`], added and removed
+	/// entries aren't reported. Dropping the receiver unsubscribes.
+	pub fn subscribe(&self) -> Receiver<ChangeEvent> {
+		let (tx, rx) = mpsc::channel();
+		self.shared.subscribers.lock().expect("lock poisoned").push(tx);
+
+		rx
+	}
+
+	fn notify(&self, old: &Database, new: &Database) {
+		let mut subscribers =
+			self.shared.subscribers.lock().expect("lock poisoned");
+
+		if subscribers.is_empty() {
+			return;
+		}
+
+		for event in Database::diff(old, new).change_events() {
+			subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+		}
+	}
+
+	/// Refreshes the database every `interval` on a background thread.
+	///
+	/// Failed refreshes are skipped. The thread stops once the returned
+	/// [`RefreshTask`] or every handle is dropped.
+	pub fn refresh_every(&self, interval: Duration) -> RefreshTask {
+		let shared = Arc::downgrade(&self.shared);
+
+		RefreshTask::spawn(interval, move || {
+			let Some(shared) = Weak::upgrade(&shared) else {
+				return false;
+			};
+			let _ = DatabaseHandle { shared }.refresh();
+			true
+		})
+	}
+}
+
+/// Background task started by [`DatabaseHandle::refresh_every`] or
+/// `DatabaseWatcher::spawn`.
+pub struct RefreshTask {
+	stop: Option<Sender<()>>,
+	thread: Option<JoinHandle<()>>,
+}
+
+impl RefreshTask {
+	/// Calls `tick` every `interval` on a new thread until stopped or `tick`
+	/// returns `false`.
+	pub(crate) fn spawn(
+		interval: Duration,
+		mut tick: impl FnMut() -> bool + Send + 'static,
+	) -> Self {
+		let (stop, stopped) = mpsc::channel::<()>();
+
+		let thread = thread::spawn(move || {
+			while let Err(RecvTimeoutError::Timeout) =
+				stopped.recv_timeout(interval)
+			{
+				if !tick() {
+					break;
+				}
+			}
+		});
+
+		Self { stop: Some(stop), thread: Some(thread) }
+	}
+
+	/// Stops refreshing and waits for a refresh in progress to finish.
+	pub fn stop(mut self) {
+		self.shutdown();
+	}
+
+	fn shutdown(&mut self) {
+		// Dropping the sender wakes the thread up.
+		drop(self.stop.take());
+
+		if let Some(thread) = self.thread.take() {
+			let _ = thread.join();
+		}
+	}
+}
+
+impl Drop for RefreshTask {
+	fn drop(&mut self) {
+		self.shutdown();
+	}
+}
+
+#[test]
+fn handle_refresh() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::time::Instant;
+
+	use crate::testing::{database, AnimeBuilder};
+
+	let loads = Arc::new(AtomicUsize::new(0));
+	let handle = {
+		let loads = Arc::clone(&loads);
+		DatabaseHandle::new(database(Vec::new()), move || {
+			let n = loads.fetch_add(1, Ordering::SeqCst) + 1;
+			if n == 2 {
+				return Err("flaky");
+			}
+			Ok(database(
+				(0..n).map(|_| AnimeBuilder::new("Entry").build()).collect(),
+			))
+		})
+	};
+
+	let old = handle.get();
+	assert_eq!(handle.refresh().unwrap().data.len(), 1);
+	assert!(old.data.is_empty());
+	assert_eq!(handle.get().data.len(), 1);
+
+	assert!(handle.refresh().is_err());
+	assert_eq!(handle.get().data.len(), 1);
+
+	let task = handle.refresh_every(Duration::from_millis(5));
+	let started = Instant::now();
+	while handle.get().data.len() < 4 {
+		assert!(started.elapsed() < Duration::from_secs(5));
+		thread::sleep(Duration::from_millis(5));
+	}
+	task.stop();
+
+	let stopped_at = loads.load(Ordering::SeqCst);
+	thread::sleep(Duration::from_millis(20));
+	assert_eq!(loads.load(Ordering::SeqCst), stopped_at);
+}
+
+#[test]
+fn handle_subscribe() {
+	use crate::diff::{Field, FieldChange};
+	use crate::testing::{database, AnimeBuilder};
+	use crate::AnimeStatus;
+
+	let upcoming = AnimeBuilder::new("Airing")
+		.status(AnimeStatus::Upcoming)
+		.episodes(0)
+		.build();
+	let mut airing = upcoming.clone();
+	airing.status = AnimeStatus::Ongoing;
+	airing.episodes = 1.into();
+
+	let handle = DatabaseHandle::new(
+		database(vec![upcoming, AnimeBuilder::new("Same").build()]),
+		move || -> Result<_, LoadError> {
+			Ok(database(vec![
+				airing.clone(),
+				AnimeBuilder::new("Same").build(),
+			]))
+		},
+	);
+
+	let events = handle.subscribe();
+	let dropped = handle.subscribe();
+	drop(dropped);
+
+	handle.refresh().unwrap();
+	let events: Vec<_> = events.try_iter().collect();
+	assert_eq!(
+		events.iter().map(ChangeEvent::field).collect::<Vec<_>>(),
+		[Field::Episodes, Field::Status]
+	);
+	assert_eq!(events[0].id(), handle.get().data[0].id());
+	assert_eq!(
+		events[1].change(),
+		&FieldChange::Status {
+			old: AnimeStatus::Upcoming,
+			new: AnimeStatus::Ongoing,
+		}
+	);
+	assert_eq!(handle.shared.subscribers.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,274 @@
+//! Stable identifiers of entries.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{Anime, Database, Provider};
+
+/// 64 bit FNV-1a, see <http://www.isthe.com/chongo/tech/comp/fnv/>.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+	fnv1a_extend(0xcbf2_9ce4_8422_2325, bytes)
+}
+
+fn fnv1a_extend(hash: u64, bytes: &[u8]) -> u64 {
+	bytes.iter().fold(hash, |hash, byte| {
+		(hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+	})
+}
+
+/// Identifies an entry across database versions.
+///
+/// The id is a hash of the sorted source urls, so it stays the same as long
+/// as upstream doesn't add or remove sources of the entry. The string form
+/// (16 hex digits) is used when serializing.
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Serialize,
+	Deserialize,
+)]
+#[serde(into = "String", try_from = "String")]
+pub struct AnimeId(u64);
+
+impl fmt::Display for AnimeId {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{:016x}", self.0)
+	}
+}
+
+impl FromStr for AnimeId {
+	type Err = ParseIntError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		u64::from_str_radix(s, 16).map(Self)
+	}
+}
+
+impl From<AnimeId> for String {
+	fn from(id: AnimeId) -> Self {
+		id.to_string()
+	}
+}
+
+impl TryFrom<String> for AnimeId {
+	type Error = ParseIntError;
+
+	fn try_from(s: String) -> Result<Self, Self::Error> {
+		s.parse()
+	}
+}
+
+impl Anime {
+	pub fn id(&self) -> AnimeId {
+		let mut sources: Vec<_> =
+			self.sources.iter().map(|url| url.as_str()).collect();
+		sources.sort_unstable();
+
+		// Separates the urls, so moving characters between neighbours
+		// changes the hash.
+		let hash = sources.iter().fold(fnv1a(&[]), |hash, source| {
+			fnv1a_extend(fnv1a_extend(hash, source.as_bytes()), b"\n")
+		});
+
+		AnimeId(hash)
+	}
+}
+
+/// Providers whose source identifies an entry, most stable first.
+const IDENTITY_PROVIDERS: [Provider; 8] = [
+	Provider::MyAnimeList,
+	Provider::AniList,
+	Provider::AniDb,
+	Provider::Kitsu,
+	Provider::AniSearch,
+	Provider::AnimePlanet,
+	Provider::LiveChart,
+	Provider::NotifyMoe,
+];
+
+impl Anime {
+	/// Whether both entries describe the same anime, i.e. share at least one
+	/// source.
+	///
+	/// Unlike `==` this ignores changed metadata, e.g. when comparing
+	/// entries of two snapshots.
+	pub fn same_as(&self, other: &Anime) -> bool {
+		self.sources.iter().any(|url| other.sources.contains(url))
+	}
+
+	/// The source identifying the entry in [`ByIdentity`].
+	///
+	/// This is the source of the first provider out of MyAnimeList, AniList,
+	/// AniDB, Kitsu, aniSearch, Anime-Planet, LiveChart and notify.moe the
+	/// entry is listed at, or the smallest source url if none of them
+	/// matches.
+	pub fn primary_source(&self) -> Option<&Url> {
+		let provider = |url: &Url| {
+			let (provider, _) = Provider::parse(url)?;
+			IDENTITY_PROVIDERS.iter().position(|&p| p == provider)
+		};
+
+		self.sources.iter().min_by(|a, b| {
+			match (provider(a), provider(b)) {
+				(Some(a), Some(b)) => a.cmp(&b),
+				(Some(_), None) => std::cmp::Ordering::Less,
+				(None, Some(_)) => std::cmp::Ordering::Greater,
+				(None, None) => std::cmp::Ordering::Equal,
+			}
+			.then_with(|| a.as_str().cmp(b.as_str()))
+		})
+	}
+}
+
+/// Compares and hashes an entry by its [`Anime::primary_source`] instead of
+/// all fields.
+///
+/// Sets and maps keyed by it treat an entry of an older snapshot and its
+/// updated counterpart as the same key. Equal entries always satisfy
+/// [`Anime::same_as`], the converse doesn't hold if the primary source of an
+/// entry changed, e.g. because a more stable provider was added. Entries
+/// without sources are all equal.
+///
+/// Works with anything borrowing an [`Anime`], e.g. `&Anime` or
+/// `Arc<Anime>`.
+#[derive(Debug, Clone, Copy)]
+pub struct ByIdentity<T>(pub T);
+
+impl<T: Borrow<Anime>> ByIdentity<T> {
+	pub fn get(&self) -> &Anime {
+		self.0.borrow()
+	}
+
+	pub fn into_inner(self) -> T {
+		self.0
+	}
+}
+
+impl<T: Borrow<Anime>> PartialEq for ByIdentity<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.get().primary_source() == other.get().primary_source()
+	}
+}
+
+impl<T: Borrow<Anime>> Eq for ByIdentity<T> {}
+
+impl<T: Borrow<Anime>> Hash for ByIdentity<T> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.get().primary_source().hash(state);
+	}
+}
+
+/// Lookup of entry positions by id, built on first use.
+///
+/// Always compares equal, so databases differing only in whether the index
+/// was built yet are still equal. Clones of a database share the index, as
+/// they share the entries.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IdIndex(Arc<OnceLock<HashMap<AnimeId, usize>>>);
+
+impl IdIndex {
+	/// Whether the index was built yet.
+	#[cfg(test)]
+	pub(crate) fn is_built(&self) -> bool {
+		self.0.get().is_some()
+	}
+
+	/// Whether `self` and `other` are the same index, e.g. of clones.
+	#[cfg(test)]
+	pub(crate) fn shares(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.0, &other.0)
+	}
+}
+
+impl PartialEq for IdIndex {
+	fn eq(&self, _: &Self) -> bool {
+		true
+	}
+}
+
+impl Database {
+	/// Returns the entry with the id `id`.
+	///
+	/// The first call builds an index of all entries, later ones are cheap.
+	pub fn by_id(&self, id: AnimeId) -> Option<&Anime> {
+		let index = self.ids.0.get_or_init(|| {
+			let mut index = HashMap::with_capacity(self.data.len());
+			for (idx, anime) in self.data.iter().enumerate() {
+				index.entry(anime.id()).or_insert(idx);
+			}
+			index
+		});
+
+		index.get(&id).map(|&idx| &self.data[idx])
+	}
+}
+
+#[test]
+fn db_by_id() {
+	use crate::testing::{database, url, AnimeBuilder};
+
+	let mut first = AnimeBuilder::new("First").build();
+	first.sources.push(url("https://anilist.co/anime/1"));
+	let second = AnimeBuilder::new("Second").build();
+
+	let mut reordered = first.clone();
+	reordered.sources.reverse();
+	assert_eq!(first.id(), reordered.id());
+	assert_ne!(first.id(), second.id());
+
+	let db = database(vec![first.clone(), second.clone()]);
+	assert_eq!(db.by_id(second.id()), Some(&second));
+	assert_eq!(db.by_id(first.id()).map(Anime::title), Some("First"));
+	assert_eq!(db.by_id(AnimeId(0)), None);
+
+	let id = first.id();
+	assert_eq!(id.to_string().len(), 16);
+	assert_eq!(id.to_string().parse(), Ok(id));
+	assert_eq!(serde_json::to_string(&id).unwrap(), format!("\"{id}\""));
+	assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+}
+
+#[test]
+fn anime_identity() {
+	use std::collections::HashSet;
+
+	use crate::testing::{url, AnimeBuilder};
+
+	let mut old = AnimeBuilder::new("Old Title").episodes(12).build();
+	old.sources.push(url("https://myanimelist.net/anime/1"));
+
+	let mut new = AnimeBuilder::new("New Title").episodes(24).build();
+	new.sources.push(url("https://myanimelist.net/anime/1"));
+	new.sources.push(url("https://anilist.co/anime/1"));
+
+	let other = AnimeBuilder::new("Other").build();
+
+	assert!(old.same_as(&new) && new.same_as(&old));
+	assert!(!old.same_as(&other));
+	assert_ne!(old, new);
+	assert_eq!(
+		new.primary_source(),
+		Some(&url("https://myanimelist.net/anime/1"))
+	);
+	assert_eq!(other.primary_source(), other.sources.first());
+
+	let snapshot: HashSet<_> =
+		[&old, &other].into_iter().map(ByIdentity).collect();
+	assert!(snapshot.contains(&ByIdentity(&new)));
+	assert_eq!(ByIdentity(old.clone()), ByIdentity(new.clone()));
+	assert_ne!(ByIdentity(&old), ByIdentity(&other));
+}
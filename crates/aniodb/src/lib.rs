@@ -1,14 +1,103 @@
 #![feature(io_error_other)]
 
+#[cfg(all(feature = "fetch", target_arch = "wasm32", target_os = "unknown"))]
+compile_error!(
+	"the `fetch` feature does not support wasm32-unknown-unknown, use \
+	 `web-fetch` instead"
+);
+
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::io::Read;
+use std::num::NonZeroU32;
+use std::ops::RangeInclusive;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::de::{self, Deserializer, Visitor};
 use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+mod borrowed;
+#[cfg(feature = "cache")]
+mod cache;
+mod collation;
+mod columnar;
+mod complete;
+mod date;
+mod dead;
+mod diff;
+#[cfg(feature = "enrich")]
+pub mod enrich;
+pub mod export;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod handle;
+mod id;
+#[cfg(feature = "async")]
+mod links;
+mod matching;
+#[cfg(feature = "media")]
+pub mod media;
+mod normalize;
+mod provider;
+#[cfg(feature = "python")]
+mod python;
+pub mod query;
+#[cfg(feature = "random")]
+mod random;
+mod relation;
+mod resolve;
+mod slim;
+mod stats;
+mod taxonomy;
+mod title;
+mod validate;
+#[cfg(feature = "fetch")]
+mod watch;
+#[cfg(feature = "web-fetch")]
+mod web;
+
+pub use crate::borrowed::{AnimeRef, DatabaseRef};
+#[cfg(feature = "cache")]
+pub use crate::cache::{CacheError, SnapshotCache, Stored};
+pub use crate::collation::{CatalogEntry, Collation};
+pub use crate::columnar::ColumnarDatabase;
+pub use crate::complete::Completion;
+use crate::complete::TitleIndex;
+pub use crate::date::ParseDateError;
+pub use crate::dead::DeadEntries;
+pub use crate::diff::{
+	ChangeEvent, DatabaseDiff, Field, FieldChange, ListChange, Modified,
+};
+pub use crate::handle::{DatabaseHandle, LoadError, RefreshTask};
+use crate::id::IdIndex;
+pub use crate::id::{AnimeId, ByIdentity};
+#[cfg(feature = "async")]
+pub use crate::links::{DeadLink, LinkChecker, LinkStatus};
+pub use crate::matching::{MatchConfig, Scorer};
+pub use crate::normalize::Normalization;
+pub use crate::provider::Provider;
+use crate::query::TagIndex;
+pub use crate::query::{
+	Cursor, Filter, Matches, Order, Page, Predicate, Query,
+};
+use crate::relation::SourceIndex;
+pub use crate::relation::{Relation, RelationKind};
+pub use crate::resolve::Resolved;
+pub use crate::slim::{SlimAnime, SlimDatabase};
+pub use crate::stats::Stats;
+pub use crate::taxonomy::TagCategory;
+pub use crate::title::TitleLocale;
+pub use crate::validate::{Issue, ValidationReport};
+#[cfg(feature = "fetch")]
+pub use crate::watch::{DatabaseWatcher, Update, WatchTarget};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SimpleDate {
 	year: u16,
@@ -16,6 +105,56 @@ pub struct SimpleDate {
 	day: u8,
 }
 
+impl SimpleDate {
+	pub fn year(&self) -> u16 {
+		self.year
+	}
+
+	pub fn month(&self) -> u8 {
+		self.month
+	}
+
+	pub fn day(&self) -> u8 {
+		self.day
+	}
+
+	/// Returns the current date in UTC.
+	pub fn today() -> Self {
+		let secs = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |elapsed| elapsed.as_secs());
+
+		Self::from_days_since_epoch((secs / 86_400) as i64)
+	}
+
+	/// Converts days since 1970-01-01 into a date.
+	///
+	/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+	fn from_days_since_epoch(days: i64) -> Self {
+		let z = days + 719_468;
+		let era = z.div_euclid(146_097);
+		let doe = z.rem_euclid(146_097);
+		let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+		let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+		let mp = (5 * doy + 2) / 153;
+		let day = doy - (153 * mp + 2) / 5 + 1;
+		let month = if mp < 10 { mp + 3 } else { mp - 9 };
+		let year = yoe + era * 400 + i64::from(month <= 2);
+
+		Self { year: year as u16, month: month as u8, day: day as u8 }
+	}
+
+	/// Returns the anime season the date falls into.
+	pub fn season(&self) -> Season {
+		match self.month {
+			1..=3 => Season::Winter,
+			4..=6 => Season::Spring,
+			7..=9 => Season::Summer,
+			_ => Season::Fall,
+		}
+	}
+}
+
 impl Serialize for SimpleDate {
 	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
 	where
@@ -76,7 +215,18 @@ impl<'de> Deserialize<'de> for SimpleDate {
 	}
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Serialize,
+	Deserialize,
+)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum AnimeKind {
 	Tv,
@@ -87,7 +237,18 @@ pub enum AnimeKind {
 	Unknown,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Serialize,
+	Deserialize,
+)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum AnimeStatus {
 	Finished,
@@ -96,13 +257,25 @@ pub enum AnimeStatus {
 	Unknown,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Ordered as the seasons occur within a year.
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Serialize,
+	Deserialize,
+)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Season {
+	Winter,
 	Spring,
 	Summer,
 	Fall,
-	Winter,
 	Undefined,
 }
 
@@ -112,39 +285,301 @@ pub struct License {
 	url: Url,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AnimeSeason {
 	season: Season,
 	year: Option<u32>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+impl AnimeSeason {
+	pub fn new(season: Season, year: Option<u32>) -> Self {
+		Self { season, year }
+	}
+
+	/// Returns the season of today's date.
+	pub fn current() -> Self {
+		let today = SimpleDate::today();
+		Self { season: today.season(), year: Some(u32::from(today.year)) }
+	}
+
+	pub fn season(&self) -> Season {
+		self.season
+	}
+
+	pub fn year(&self) -> Option<u32> {
+		self.year
+	}
+}
+
+impl Default for AnimeSeason {
+	fn default() -> Self {
+		Self { season: Season::Undefined, year: None }
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DurationUnit {
+	Seconds,
+	Minutes,
+	Hours,
+}
+
+/// Duration of a single episode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpisodeDuration {
+	value: u64,
+	unit: DurationUnit,
+}
+
+impl EpisodeDuration {
+	pub fn as_duration(&self) -> std::time::Duration {
+		let secs = match self.unit {
+			DurationUnit::Seconds => self.value,
+			DurationUnit::Minutes => self.value * 60,
+			DurationUnit::Hours => self.value * 60 * 60,
+		};
+
+		std::time::Duration::from_secs(secs)
+	}
+}
+
+/// Number of episodes of an entry.
+///
+/// Upstream stores unknown counts as `0`, which is what
+/// [`EpisodeCount::Unknown`] is read from and written as. Unknown counts are
+/// ordered before all known ones.
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Default,
+	Serialize,
+	Deserialize,
+)]
+#[serde(from = "u32", into = "u32")]
+pub enum EpisodeCount {
+	#[default]
+	Unknown,
+	Known(NonZeroU32),
+}
+
+impl EpisodeCount {
+	/// Returns the count, `None` if unknown.
+	pub fn get(&self) -> Option<u32> {
+		match self {
+			Self::Unknown => None,
+			Self::Known(count) => Some(count.get()),
+		}
+	}
+
+	pub fn is_known(&self) -> bool {
+		matches!(self, Self::Known(_))
+	}
+}
+
+/// `0` is read as [`EpisodeCount::Unknown`].
+impl From<u32> for EpisodeCount {
+	fn from(count: u32) -> Self {
+		NonZeroU32::new(count).map_or(Self::Unknown, Self::Known)
+	}
+}
+
+impl From<EpisodeCount> for u32 {
+	fn from(count: EpisodeCount) -> Self {
+		count.get().unwrap_or(0)
+	}
+}
+
+impl fmt::Display for EpisodeCount {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Unknown => f.write_str("unknown"),
+			Self::Known(count) => write!(f, "{count}"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Score {
+	arithmetic_geometric_mean: f64,
+	arithmetic_mean: f64,
+	median: f64,
+}
+
+impl Score {
+	pub fn arithmetic_geometric_mean(&self) -> f64 {
+		self.arithmetic_geometric_mean
+	}
+
+	pub fn arithmetic_mean(&self) -> f64 {
+		self.arithmetic_mean
+	}
+
+	pub fn median(&self) -> f64 {
+		self.median
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Anime {
 	sources: Vec<Url>,
 	title: String,
 	#[serde(rename = "type")]
 	kind: AnimeKind,
-	episodes: u32,
+	episodes: EpisodeCount,
 	status: AnimeStatus,
-	anime_season: AnimeSeason,
+	/// Missing in the oldest versions of the database.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	anime_season: Option<AnimeSeason>,
 	picture: Url,
 	thumbnail: Url,
 	synonyms: Vec<String>,
 	relations: Vec<Url>,
-	tags: Vec<String>,
+	/// Shared between entries after loading, see [`Database::load`].
+	tags: Vec<Arc<str>>,
+	/// Only present in newer versions of the database.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	duration: Option<EpisodeDuration>,
+	/// Only present in newer versions of the database.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	score: Option<Score>,
+	/// Only present in newer versions of the database.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	studios: Option<Vec<Arc<str>>>,
+	/// Only present in newer versions of the database.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	producers: Option<Vec<Arc<str>>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+impl Anime {
+	pub fn sources(&self) -> &[Url] {
+		&self.sources
+	}
+
+	pub fn title(&self) -> &str {
+		&self.title
+	}
+
+	pub fn kind(&self) -> AnimeKind {
+		self.kind
+	}
+
+	pub fn episodes(&self) -> EpisodeCount {
+		self.episodes
+	}
+
+	pub fn status(&self) -> AnimeStatus {
+		self.status
+	}
+
+	/// Returns the season the entry aired in.
+	///
+	/// Entries from database versions without seasons are reported as
+	/// [`Season::Undefined`] without a year.
+	pub fn anime_season(&self) -> AnimeSeason {
+		self.anime_season.unwrap_or_default()
+	}
+
+	/// Urls of related entries, see [`Database::relations`].
+	pub fn relations(&self) -> &[Url] {
+		&self.relations
+	}
+
+	pub fn tags(&self) -> &[Arc<str>] {
+		&self.tags
+	}
+
+	pub fn duration(&self) -> Option<std::time::Duration> {
+		self.duration.as_ref().map(EpisodeDuration::as_duration)
+	}
+
+	pub fn score(&self) -> Option<Score> {
+		self.score
+	}
+
+	pub fn studios(&self) -> &[Arc<str>] {
+		self.studios.as_deref().unwrap_or_default()
+	}
+
+	pub fn producers(&self) -> &[Arc<str>] {
+		self.producers.as_deref().unwrap_or_default()
+	}
+
+	fn schema_version(&self) -> SchemaVersion {
+		if self.studios.is_some() || self.producers.is_some() {
+			SchemaVersion::Studios
+		} else if self.score.is_some() {
+			SchemaVersion::Score
+		} else if self.duration.is_some() {
+			SchemaVersion::Duration
+		} else if self.anime_season.is_some() {
+			SchemaVersion::Seasons
+		} else {
+			SchemaVersion::Legacy
+		}
+	}
+}
+
+/// Revision of the upstream schema a database was written with.
+///
+/// Every revision is parsed into the same types, fields which did not exist
+/// yet are left empty. Later revisions compare greater than earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SchemaVersion {
+	/// Entries without an `animeSeason`.
+	Legacy,
+	/// Entries with an `animeSeason`.
+	Seasons,
+	/// Adds `duration`.
+	Duration,
+	/// Adds `score`.
+	Score,
+	/// Adds `studios` and `producers`.
+	Studios,
+}
+
+/// The parsed dataset.
+///
+/// Clones are cheap: the entries, dead entries and lookup indexes are shared
+/// between them, which makes it fine to hand copies to threads or keep old
+/// snapshots around while refreshing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Database {
 	license: License,
 	repository: Url,
 	last_update: SimpleDate,
-	data: Vec<Anime>,
+	data: Arc<[Anime]>,
+	/// Dead entry ids per provider, see [`Database::is_dead`].
+	#[serde(skip)]
+	dead: Arc<HashMap<Provider, HashSet<String>>>,
+	#[serde(skip)]
+	ids: IdIndex,
+	#[serde(skip)]
+	tags: TagIndex,
+	#[serde(skip)]
+	sources: SourceIndex,
+	#[serde(skip)]
+	titles: TitleIndex,
 }
 
 impl Database {
+	/// Reads the database from a file.
+	///
+	/// Tags, studios and producers repeat across thousands of entries, equal
+	/// values are interned so every distinct string is allocated only once.
+	///
+	/// Not available on `wasm32-unknown-unknown`, see
+	/// [`Database::from_bytes`].
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 	pub fn load(path: impl AsRef<Path>) -> Result<Self, std::io::Error> {
 		fn _load(path: &Path) -> Result<Database, std::io::Error> {
 			let file = std::fs::File::open(path)?;
@@ -152,62 +587,225 @@ impl Database {
 			// simd_json: 450 ms
 			#[cfg(feature = "simd-json")]
 			{
-				simd_json::from_reader(file)
-					.map_err(|err| std::io::Error::other(err))
+				simd_json::from_reader(file).map_err(std::io::Error::other)
 			}
 
 			// serde_json: 25 s
 			#[cfg(not(feature = "simd-json"))]
 			{
-				serde_json::from_reader(file)
-					.map_err(|err| std::io::Error::other(err))
+				serde_json::from_reader(file).map_err(std::io::Error::other)
 			}
 		}
 
-		_load(path.as_ref())
+		_load(path.as_ref()).map(Database::interned)
 	}
 
+	/// Reads the database from `r`, interning strings like
+	/// [`Database::load`].
 	pub fn from_reader(r: impl Read) -> Result<Self, std::io::Error> {
-		// simd_json: 450 ms
-		#[cfg(feature = "simd-json")]
-		{
-			simd_json::from_reader(r).map_err(|err| std::io::Error::other(err))
+		fn _from_reader(r: impl Read) -> Result<Database, std::io::Error> {
+			// simd_json: 450 ms
+			#[cfg(feature = "simd-json")]
+			{
+				simd_json::from_reader(r).map_err(std::io::Error::other)
+			}
+
+			// serde_json: 25 s
+			#[cfg(not(feature = "simd-json"))]
+			{
+				serde_json::from_reader(r).map_err(std::io::Error::other)
+			}
 		}
 
-		// serde_json: 25 s
+		_from_reader(r).map(Database::interned)
+	}
+
+	/// Parses the database from a buffer, interning strings like
+	/// [`Database::load`].
+	///
+	/// Needs neither file system access nor threads, which makes it the way
+	/// to go in the browser.
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, std::io::Error> {
+		// simd_json parses in place.
+		#[cfg(feature = "simd-json")]
+		let db = simd_json::from_slice(&mut bytes.to_vec());
+
 		#[cfg(not(feature = "simd-json"))]
+		let db = serde_json::from_slice(bytes);
+
+		db.map(Database::interned).map_err(std::io::Error::other)
+	}
+
+	/// Replaces equal tags, studios and producers with one shared
+	/// allocation.
+	///
+	/// Only called on freshly parsed databases, whose entries aren't shared
+	/// yet.
+	fn interned(mut self) -> Self {
+		let mut strings: HashSet<Arc<str>> = HashSet::new();
+		let entries = Arc::get_mut(&mut self.data).into_iter().flatten();
+
+		for anime in entries {
+			let fields = anime
+				.tags
+				.iter_mut()
+				.chain(anime.studios.iter_mut().flatten())
+				.chain(anime.producers.iter_mut().flatten());
+
+			for value in fields {
+				match strings.get(&**value) {
+					Some(shared) => *value = Arc::clone(shared),
+					None => {
+						strings.insert(Arc::clone(value));
+					}
+				}
+			}
+		}
+
+		self
+	}
+
+	pub fn entries(&self) -> &[Anime] {
+		&self.data
+	}
+
+	/// Returns the entries as a shared slice, which outlives the database
+	/// without copying them.
+	pub fn shared_entries(&self) -> Arc<[Anime]> {
+		Arc::clone(&self.data)
+	}
+
+	/// Returns the `n` entries with the highest score, best first.
+	///
+	/// Entries without a score are not considered.
+	pub fn top_rated(&self, n: usize) -> Vec<&Anime> {
+		let mut rated: Vec<_> = self
+			.data
+			.iter()
+			.filter_map(|anime| Some((anime.score?, anime)))
+			.collect();
+
+		rated.sort_by(|(a, _), (b, _)| {
+			b.arithmetic_geometric_mean.total_cmp(&a.arithmetic_geometric_mean)
+		});
+
+		rated.into_iter().take(n).map(|(_, anime)| anime).collect()
+	}
+
+	/// Detects the schema revision from the fields present in the entries.
+	///
+	/// This scans all entries.
+	pub fn schema_version(&self) -> SchemaVersion {
+		self.data
+			.iter()
+			.map(Anime::schema_version)
+			.max()
+			.unwrap_or(SchemaVersion::Legacy)
+	}
+
+	/// Groups all entries by the year they aired in.
+	///
+	/// Entries without a year are skipped.
+	pub fn by_year(&self) -> BTreeMap<u32, Vec<&Anime>> {
+		let mut years: BTreeMap<u32, Vec<&Anime>> = BTreeMap::new();
+
+		for anime in self.data.iter() {
+			if let Some(year) = anime.anime_season().year {
+				years.entry(year).or_default().push(anime);
+			}
+		}
+
+		years
+	}
+
+	/// Groups the entries of `year` by season, for seasonal charts.
+	///
+	/// Each season is sorted by title, ignoring case and diacritics, entries
+	/// with the same title stay in database order. Entries without a known
+	/// season are grouped under [`Season::Undefined`].
+	pub fn calendar(&self, year: u32) -> BTreeMap<Season, Vec<&Anime>> {
+		self.calendar_range(year..=year)
+			.into_iter()
+			.map(|((_, season), entries)| (season, entries))
+			.collect()
+	}
+
+	/// Like [`Database::calendar`] for every year in `years`, keyed by year
+	/// and season.
+	///
+	/// Only seasons with entries are included.
+	pub fn calendar_range(
+		&self,
+		years: RangeInclusive<u32>,
+	) -> BTreeMap<(u32, Season), Vec<&Anime>> {
+		let mut calendar: BTreeMap<_, Vec<_>> = BTreeMap::new();
+
+		for anime in self.query().years(years).sort_by_title(Order::Ascending)
 		{
-			serde_json::from_reader(r)
-				.map_err(|err| std::io::Error::other(err))
+			let AnimeSeason { season, year } = anime.anime_season();
+			if let Some(year) = year {
+				calendar.entry((year, season)).or_default().push(anime);
+			}
 		}
+
+		calendar
+	}
+
+	/// Returns all entries of the given season.
+	pub fn season(&self, season: Season, year: u32) -> Vec<&Anime> {
+		self.query().season(season).year(year).run()
+	}
+
+	/// Returns all ongoing entries of the current season.
+	pub fn airing_now(&self) -> Vec<&Anime> {
+		self.airing_in(AnimeSeason::current())
+	}
+
+	fn airing_in(&self, season: AnimeSeason) -> Vec<&Anime> {
+		let query =
+			self.query().status(AnimeStatus::Ongoing).season(season.season);
+
+		match season.year {
+			Some(year) => query.year(year),
+			None => query,
+		}
+		.run()
+	}
+
+	/// Returns all entries animated by the given studio.
+	///
+	/// Studio names are compared case-insensitively.
+	pub fn by_studio(&self, name: &str) -> Vec<&Anime> {
+		let name = name.to_lowercase();
+
+		self.data
+			.iter()
+			.filter(|anime| {
+				anime
+					.studios()
+					.iter()
+					.any(|studio| studio.to_lowercase() == name)
+			})
+			.collect()
 	}
 }
 
+#[cfg(test)]
+mod testing;
+
 #[cfg(feature = "fetch")]
 mod fetch_shared {
 	pub(crate) const DATABASE_URL: &'static str = "https://github.com/manami-project/anime-offline-database/raw/master/anime-offline-database-minified.json";
-}
-
-#[cfg(feature = "fetch")]
-pub mod fetch {
-	use std::io::Write;
-
-	use crate::fetch_shared::DATABASE_URL;
-	use crate::Database;
+	pub(crate) const RELEASES_URL: &str = "https://api.github.com/repos/manami-project/anime-offline-database/releases?per_page=100";
+	pub(crate) const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/manami-project/anime-offline-database/releases/latest";
+	pub(crate) const LATEST_COMMIT_URL: &str = "https://api.github.com/repos/manami-project/anime-offline-database/commits/master";
 
-	#[derive(Debug, thiserror::Error)]
-	pub enum Error {
-		#[error("Request failed: `{0}`")]
-		RequestError(#[from] ureq::Error),
-		#[error("Io operation failed: `{0}`")]
-		IoError(#[from] std::io::Error),
+	pub(crate) fn dead_entries_url(name: &str) -> String {
+		format!("https://github.com/manami-project/anime-offline-database/raw/master/dead-entries/{name}.json")
 	}
 
-	impl Database {
-		pub fn fetch(mut w: impl Write) -> Result<u64, Error> {
-			let mut reader = ureq::get(DATABASE_URL).call()?.into_reader();
-			std::io::copy(&mut reader, &mut w).map_err(|err| err.into())
-		}
+	pub(crate) fn release_url(tag: &str) -> String {
+		format!("https://github.com/manami-project/anime-offline-database/raw/{tag}/anime-offline-database-minified.json")
 	}
 }
 
@@ -228,19 +826,278 @@ fn db_read() -> anyhow::Result<()> {
 	Ok(())
 }
 
+#[test]
+fn db_top_rated() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Average").score(6.5).build(),
+		AnimeBuilder::new("Unrated").build(),
+		AnimeBuilder::new("Best").score(9.1).build(),
+		AnimeBuilder::new("Good").score(8.0).build(),
+	]);
+
+	let titles: Vec<_> =
+		db.top_rated(2).into_iter().map(|a| a.title.as_str()).collect();
+
+	assert_eq!(titles, ["Best", "Good"]);
+	assert_eq!(db.top_rated(10).len(), 3);
+}
+
+#[test]
+fn db_by_studio() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("A").studio("madhouse").build(),
+		AnimeBuilder::new("B").studio("bones").build(),
+		AnimeBuilder::new("C").studio("bones").studio("madhouse").build(),
+	]);
+
+	let titles: Vec<_> = db
+		.by_studio("Madhouse")
+		.into_iter()
+		.map(|a| a.title.as_str())
+		.collect();
+
+	assert_eq!(titles, ["A", "C"]);
+	assert!(db.by_studio("sunrise").is_empty());
+}
+
+#[test]
+fn db_by_year() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("A").year(2001).build(),
+		AnimeBuilder::new("B").year(1999).build(),
+		AnimeBuilder::new("C").year(2001).build(),
+		AnimeBuilder::new("D").build(),
+	]);
+
+	let histogram: Vec<_> =
+		db.by_year().into_iter().map(|(y, a)| (y, a.len())).collect();
+
+	assert_eq!(histogram, [(1999, 1), (2001, 2)]);
+}
+
+#[test]
+fn db_clone_shares_entries() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![AnimeBuilder::new("Shared").build()]);
+	let id = db.entries()[0].id();
+	assert!(db.by_id(id).is_some());
+
+	let clone = db.clone();
+	assert!(Arc::ptr_eq(&db.data, &clone.data));
+	assert!(db.ids.shares(&clone.ids));
+	assert!(clone.ids.is_built());
+
+	let entries = db.shared_entries();
+	drop((db, clone));
+	assert_eq!(entries[0].title(), "Shared");
+}
+
+#[test]
+fn db_calendar() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("b").year(2001).season(Season::Fall).build(),
+		AnimeBuilder::new("Same").year(2001).season(Season::Winter).build(),
+		AnimeBuilder::new("A").year(2001).season(Season::Fall).build(),
+		AnimeBuilder::new("same").year(2001).season(Season::Winter).build(),
+		AnimeBuilder::new("Unknown")
+			.year(2001)
+			.season(Season::Undefined)
+			.build(),
+		AnimeBuilder::new("Next").year(2002).build(),
+		AnimeBuilder::new("No Year").build(),
+	]);
+
+	let titles = |entries: &[&Anime]| -> Vec<String> {
+		entries.iter().map(|a| a.title.clone()).collect()
+	};
+
+	let calendar = db.calendar(2001);
+	assert_eq!(
+		calendar.keys().copied().collect::<Vec<_>>(),
+		[Season::Winter, Season::Fall, Season::Undefined]
+	);
+	assert_eq!(titles(&calendar[&Season::Winter]), ["Same", "same"]);
+	assert_eq!(titles(&calendar[&Season::Fall]), ["A", "b"]);
+
+	let range = db.calendar_range(2000..=2002);
+	assert_eq!(
+		range.keys().copied().collect::<Vec<_>>(),
+		[
+			(2001, Season::Winter),
+			(2001, Season::Fall),
+			(2001, Season::Undefined),
+			(2002, Season::Spring),
+		]
+	);
+	assert!(db.calendar(1990).is_empty());
+}
+
+#[test]
+fn date_from_days_since_epoch() {
+	let date = |days| {
+		let date = SimpleDate::from_days_since_epoch(days);
+		(date.year, date.month, date.day)
+	};
+
+	assert_eq!(date(0), (1970, 1, 1));
+	assert_eq!(date(11_016), (2000, 2, 29));
+	assert_eq!(date(19_358), (2023, 1, 1));
+	assert_eq!(
+		SimpleDate { year: 2023, month: 11, day: 3 }.season(),
+		Season::Fall
+	);
+}
+
+#[test]
+fn db_airing() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let airing = |title, status, year| {
+		AnimeBuilder::new(title).status(status).year(year).build()
+	};
+
+	let db = database(vec![
+		airing("Finished", AnimeStatus::Finished, 2022),
+		airing("Ongoing", AnimeStatus::Ongoing, 2022),
+		airing("Last Year", AnimeStatus::Ongoing, 2021),
+	]);
+
+	let titles = |animes: Vec<&Anime>| -> Vec<String> {
+		animes.into_iter().map(|a| a.title.clone()).collect()
+	};
+
+	assert_eq!(
+		titles(db.airing_in(AnimeSeason::new(Season::Spring, Some(2022)))),
+		["Ongoing"]
+	);
+	assert_eq!(
+		titles(db.season(Season::Spring, 2022)),
+		["Finished", "Ongoing"]
+	);
+	assert!(db.season(Season::Fall, 2022).is_empty());
+}
+
+#[test]
+fn episode_count_serde() {
+	let counts: Vec<EpisodeCount> = serde_json::from_str("[0, 12]").unwrap();
+
+	assert_eq!(counts[0], EpisodeCount::Unknown);
+	assert_eq!(counts[1].get(), Some(12));
+	assert!(counts[0] < counts[1]);
+	assert_eq!(counts[0].to_string(), "unknown");
+	assert_eq!(serde_json::to_string(&counts).unwrap(), "[0,12]");
+}
+
+#[test]
+fn db_schema_version() {
+	fn parse(entry: &str) -> Database {
+		let json = format!(
+			r#"{{
+				"license": {{
+					"name": "GNU Affero General Public License v3.0",
+					"url": "https://github.com/manami-project/anime-offline-database/blob/master/LICENSE"
+				}},
+				"repository": "https://github.com/manami-project/anime-offline-database",
+				"lastUpdate": "2022-09-01",
+				"data": [{{
+					"sources": ["https://myanimelist.net/anime/1"],
+					"title": "Cowboy Bebop",
+					"type": "TV",
+					"episodes": 26,
+					"status": "FINISHED",
+					"picture": "https://example.org/picture.png",
+					"thumbnail": "https://example.org/thumbnail.png",
+					"synonyms": [],
+					"relations": [],
+					"tags": []
+					{entry}
+				}}]
+			}}"#
+		);
+
+		Database::from_reader(json.as_bytes()).unwrap()
+	}
+
+	let season = r#", "animeSeason": { "season": "SPRING", "year": 1998 }"#;
+	let duration = r#", "duration": { "value": 1440, "unit": "SECONDS" }"#;
+	let score = r#", "score": {
+		"arithmeticGeometricMean": 8.7,
+		"arithmeticMean": 8.7,
+		"median": 8.7
+	}"#;
+	let studios = r#", "studios": ["sunrise"], "producers": []"#;
+
+	let legacy = parse("");
+	assert_eq!(legacy.schema_version(), SchemaVersion::Legacy);
+	assert_eq!(legacy.data[0].anime_season(), AnimeSeason::default());
+
+	assert_eq!(parse(season).schema_version(), SchemaVersion::Seasons);
+
+	let db = parse(&format!("{season}{duration}"));
+	assert_eq!(db.schema_version(), SchemaVersion::Duration);
+	assert_eq!(
+		db.data[0].duration(),
+		Some(std::time::Duration::from_secs(1440))
+	);
+
+	let db = parse(&format!("{season}{duration}{score}"));
+	assert_eq!(db.schema_version(), SchemaVersion::Score);
+
+	let db = parse(&format!("{season}{duration}{score}{studios}"));
+	assert_eq!(db.schema_version(), SchemaVersion::Studios);
+	assert_eq!(db.data[0].studios(), [Arc::from("sunrise")]);
+}
+
+#[test]
+fn db_interned() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("A").tag("mecha").studio("Sunrise").build(),
+		AnimeBuilder::new("B").tag("drama").tag("mecha").build(),
+		AnimeBuilder::new("C").tag("Sunrise").build(),
+	]);
+	let json = serde_json::to_vec(&db).unwrap();
+	let db = Database::from_reader(&json[..]).unwrap();
+
+	let [a, b, c] = &db.data[..] else {
+		panic!("expected three entries");
+	};
+
+	assert!(Arc::ptr_eq(&a.tags()[0], &b.tags()[1]));
+	assert!(Arc::ptr_eq(&a.studios()[0], &c.tags()[0]));
+	assert!(!Arc::ptr_eq(&a.tags()[0], &b.tags()[0]));
+}
+
+#[cfg(test)]
+proptest::proptest! {
+	#[test]
+	fn db_json_roundtrip(db in crate::testing::arb_database()) {
+		let json = serde_json::to_vec(&db).unwrap();
+		let parsed = Database::from_reader(&json[..]).unwrap();
+		let from_bytes = Database::from_bytes(&json).unwrap();
+
+		proptest::prop_assert_eq!(&parsed, &db);
+		proptest::prop_assert_eq!(from_bytes, db);
+	}
+}
+
 #[test]
 #[cfg(feature = "fetch")]
 fn db_fetch_read() -> anyhow::Result<()> {
 	use std::time::Instant;
 
-	// ~31 MB
-	let mut buffer = Vec::with_capacity(40 * 1024 * 1024);
-	let bread = Database::fetch(&mut buffer).unwrap();
-
-	println!("BRead: {bread}");
-
 	let it = Instant::now();
-	let db = Database::from_reader(&buffer[..])?;
+	let db = Database::fetch_parsed()?;
 	let el = it.elapsed();
 
 	println!("Took: {:?}", el);
@@ -0,0 +1,223 @@
+//! Bulk checking of picture and thumbnail urls.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use futures::stream::{self, StreamExt};
+use url::Url;
+
+use crate::fetch::{default_agent, Error, FetchOptions, Retry};
+use crate::Anime;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkStatus {
+	Alive,
+	/// The server answered with an error status code.
+	Dead(u16),
+	/// The request failed before a response was received.
+	Unreachable(String),
+}
+
+impl LinkStatus {
+	pub fn is_alive(&self) -> bool {
+		matches!(self, Self::Alive)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadLink<'a> {
+	anime: &'a Anime,
+	url: Url,
+	status: LinkStatus,
+}
+
+impl<'a> DeadLink<'a> {
+	pub fn anime(&self) -> &'a Anime {
+		self.anime
+	}
+
+	pub fn url(&self) -> &Url {
+		&self.url
+	}
+
+	pub fn status(&self) -> &LinkStatus {
+		&self.status
+	}
+}
+
+/// Checks urls with a bounded number of concurrent requests.
+///
+/// Results are cached for the lifetime of the checker, so checking
+/// overlapping sets of entries only requests each url once.
+pub struct LinkChecker {
+	agent: ureq::Agent,
+	retry: Retry,
+	concurrency: usize,
+	cache: Mutex<HashMap<Url, LinkStatus>>,
+}
+
+impl LinkChecker {
+	pub fn new(concurrency: usize) -> Self {
+		Self {
+			agent: default_agent(),
+			retry: Retry::none(),
+			concurrency: concurrency.max(1),
+			cache: Mutex::default(),
+		}
+	}
+
+	/// Checks through the proxy and with the timeouts, certificates and
+	/// headers of `options`. Urls answering with server errors or timing
+	/// out are tried again as it [retries](FetchOptions::retry).
+	pub fn options(mut self, options: &FetchOptions) -> Result<Self, Error> {
+		self.agent = options.agent()?;
+		self.retry = options.retries();
+		Ok(self)
+	}
+
+	/// Returns the cached status of `url`, if it was already checked.
+	pub fn cached(&self, url: &Url) -> Option<LinkStatus> {
+		self.cache.lock().unwrap().get(url).cloned()
+	}
+
+	/// Removes `url` from the cache so the next check requests it again.
+	pub fn forget(&self, url: &Url) {
+		self.cache.lock().unwrap().remove(url);
+	}
+
+	pub async fn check_url(&self, url: &Url) -> LinkStatus {
+		if let Some(status) = self.cached(url) {
+			return status;
+		}
+
+		let (agent, retry) = (self.agent.clone(), self.retry);
+		let request_url = url.clone();
+		let status =
+			blocking::unblock(move || head(&agent, retry, &request_url)).await;
+
+		self.cache.lock().unwrap().insert(url.clone(), status.clone());
+		status
+	}
+
+	/// Checks the picture and thumbnail of all `entries` and returns the
+	/// ones which are not reachable, in the order of `entries`. Urls shared
+	/// by several entries are requested once.
+	pub async fn check<'a>(
+		&self,
+		entries: impl IntoIterator<Item = &'a Anime>,
+	) -> Vec<DeadLink<'a>> {
+		let links: Vec<_> = entries
+			.into_iter()
+			.flat_map(|anime| {
+				[
+					(anime, anime.picture.clone()),
+					(anime, anime.thumbnail.clone()),
+				]
+			})
+			.collect();
+
+		// Requests every url once, even if entries share it.
+		let mut urls: Vec<_> = links.iter().map(|(_, url)| url).collect();
+		urls.sort_unstable();
+		urls.dedup();
+		let statuses: HashMap<_, _> = stream::iter(urls)
+			.map(|url| async move { (url.clone(), self.check_url(url).await) })
+			.buffer_unordered(self.concurrency)
+			.collect()
+			.await;
+
+		links
+			.into_iter()
+			.filter_map(|(anime, url)| {
+				let status = statuses[&url].clone();
+				(!status.is_alive()).then_some(DeadLink { anime, url, status })
+			})
+			.collect()
+	}
+}
+
+fn head(agent: &ureq::Agent, retry: Retry, url: &Url) -> LinkStatus {
+	let result = retry.run(|| match agent.head(url.as_str()).call() {
+		// Not every server supports `HEAD`.
+		Err(ureq::Error::Status(405, _)) => {
+			Ok(agent.get(url.as_str()).call()?)
+		}
+		result => Ok(result?),
+	});
+
+	match result {
+		Ok(_) => LinkStatus::Alive,
+		Err(Error::RequestError(ureq::Error::Status(code, _))) => {
+			LinkStatus::Dead(code)
+		}
+		Err(err) => LinkStatus::Unreachable(err.to_string()),
+	}
+}
+
+#[test]
+fn links_check_url() {
+	use futures::executor::block_on;
+
+	use crate::fetch::{serve_with, USER_AGENT};
+
+	let checker = LinkChecker::new(2);
+
+	let (alive, server) = serve_with(vec![(200, Vec::new())], "HEAD /db.json");
+	let alive = Url::parse(&alive).unwrap();
+	assert_eq!(block_on(checker.check_url(&alive)), LinkStatus::Alive);
+	let requests = server.join().unwrap();
+	let agent = format!("user-agent: {}\r\n", USER_AGENT.to_lowercase());
+	assert!(requests[0].to_lowercase().contains(&agent));
+
+	let (dead, server) = serve_with(vec![(404, Vec::new())], "HEAD /db.json");
+	let dead = Url::parse(&dead).unwrap();
+	assert_eq!(block_on(checker.check_url(&dead)), LinkStatus::Dead(404));
+	server.join().unwrap();
+
+	// Nothing listens on port 1.
+	let refused = Url::parse("http://127.0.0.1:1/picture.png").unwrap();
+	assert!(matches!(
+		block_on(checker.check_url(&refused)),
+		LinkStatus::Unreachable(_)
+	));
+
+	// Checked urls are answered from the cache, without requests.
+	assert_eq!(block_on(checker.check_url(&dead)), LinkStatus::Dead(404));
+	assert_eq!(checker.cached(&alive), Some(LinkStatus::Alive));
+}
+
+#[test]
+fn links_check() {
+	use futures::executor::block_on;
+
+	use crate::fetch::serve_with;
+	use crate::testing::AnimeBuilder;
+
+	let (alive, alive_server) =
+		serve_with(vec![(200, Vec::new())], "HEAD /db.json");
+	let (dead, dead_server) =
+		serve_with(vec![(404, Vec::new())], "HEAD /db.json");
+
+	let entries: Vec<_> = ["First", "Second"]
+		.into_iter()
+		.map(|title| {
+			let mut anime = AnimeBuilder::new(title).build();
+			anime.picture = Url::parse(&alive).unwrap();
+			anime.thumbnail = Url::parse(&dead).unwrap();
+			anime
+		})
+		.collect();
+
+	let dead_links = block_on(LinkChecker::new(4).check(&entries));
+	let titles: Vec<_> =
+		dead_links.iter().map(|link| link.anime().title()).collect();
+	assert_eq!(titles, ["First", "Second"]);
+	assert!(dead_links.iter().all(|link| link.url().as_str() == dead));
+	assert!(dead_links
+		.iter()
+		.all(|link| link.status() == &LinkStatus::Dead(404)));
+
+	// Each server answers a single request.
+	assert_eq!(alive_server.join().unwrap().len(), 1);
+	assert_eq!(dead_server.join().unwrap().len(), 1);
+}
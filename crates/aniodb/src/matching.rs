@@ -0,0 +1,258 @@
+//! Similarity scores between titles.
+
+use crate::normalize::Normalization;
+
+/// Algorithm used to score how similar two normalized titles are.
+///
+/// Every scorer returns values from `0.0` (nothing in common) to `1.0`
+/// (identical).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Scorer {
+	/// `1.0` if the titles are equal, `0.0` otherwise.
+	#[default]
+	Exact,
+	/// Edit distance relative to the length of the longer title. Tolerates
+	/// typos.
+	Levenshtein,
+	/// Favors titles sharing a prefix, which suits titles cut short.
+	JaroWinkler,
+	/// Compares the sets of words, ignoring their order and words only one
+	/// of the titles has, so `"Titan Attack"` matches `"Attack on Titan"`
+	/// well.
+	TokenSet,
+}
+
+impl Scorer {
+	/// Scores two titles which were already normalized.
+	pub fn score(&self, a: &str, b: &str) -> f64 {
+		match self {
+			Self::Exact => f64::from(u8::from(a == b)),
+			Self::Levenshtein => levenshtein_similarity(a, b),
+			Self::JaroWinkler => jaro_winkler(
+				&a.chars().collect::<Vec<_>>(),
+				&b.chars().collect::<Vec<_>>(),
+			),
+			Self::TokenSet => token_set(a, b),
+		}
+	}
+}
+
+/// How titles are compared by [`Database::search`](crate::Database::search)
+/// and [`Database::resolve_with`](crate::Database::resolve_with).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchConfig {
+	scorer: Scorer,
+	min_score: f64,
+	synonym_weight: f64,
+	normalization: Normalization,
+}
+
+impl MatchConfig {
+	pub fn new(scorer: Scorer) -> Self {
+		Self { scorer, ..Self::default() }
+	}
+
+	pub fn scorer(mut self, scorer: Scorer) -> Self {
+		self.scorer = scorer;
+		self
+	}
+
+	/// Minimum score for a title to match, defaults to `0.8`.
+	///
+	/// Higher values favor precision, lower ones recall.
+	pub fn min_score(mut self, min_score: f64) -> Self {
+		self.min_score = min_score.clamp(0.0, 1.0);
+		self
+	}
+
+	/// Factor applied to scores of synonyms, defaults to `1.0`.
+	///
+	/// Synonyms include loose translations and abbreviations, a lower weight
+	/// makes canonical titles win more often.
+	pub fn synonym_weight(mut self, weight: f64) -> Self {
+		self.synonym_weight = weight.clamp(0.0, 1.0);
+		self
+	}
+
+	pub fn normalization(mut self, normalization: Normalization) -> Self {
+		self.normalization = normalization;
+		self
+	}
+
+	pub(crate) fn normalize(&self, title: &str) -> String {
+		self.normalization.normalize(title)
+	}
+
+	/// Scores `candidate` against the normalized `needle`, `None` if below
+	/// the minimum score.
+	pub(crate) fn score(
+		&self,
+		needle: &str,
+		candidate: &str,
+		synonym: bool,
+	) -> Option<f64> {
+		let mut score = self.scorer.score(needle, &self.normalize(candidate));
+		if synonym {
+			score *= self.synonym_weight;
+		}
+
+		(score > 0.0 && score >= self.min_score).then_some(score)
+	}
+}
+
+/// Exact matching with [`Normalization::Standard`].
+impl Default for MatchConfig {
+	fn default() -> Self {
+		Self {
+			scorer: Scorer::Exact,
+			min_score: 0.8,
+			synonym_weight: 1.0,
+			normalization: Normalization::Standard,
+		}
+	}
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+	let mut previous: Vec<_> = (0..=b.len()).collect();
+	let mut current = vec![0; b.len() + 1];
+
+	for (i, ca) in a.iter().enumerate() {
+		current[0] = i + 1;
+
+		for (j, cb) in b.iter().enumerate() {
+			let substitution = previous[j] + usize::from(ca != cb);
+			current[j + 1] =
+				substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+		}
+
+		std::mem::swap(&mut previous, &mut current);
+	}
+
+	previous[b.len()]
+}
+
+fn levenshtein_similarity(a: &str, b: &str) -> f64 {
+	let (a, b): (Vec<_>, Vec<_>) = (a.chars().collect(), b.chars().collect());
+	let len = a.len().max(b.len());
+
+	if len == 0 {
+		return 1.0;
+	}
+
+	1.0 - levenshtein(&a, &b) as f64 / len as f64
+}
+
+fn jaro(a: &[char], b: &[char]) -> f64 {
+	if a.is_empty() || b.is_empty() {
+		return f64::from(u8::from(a.is_empty() && b.is_empty()));
+	}
+
+	let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+	let mut a_matched = vec![false; a.len()];
+	let mut b_matched = vec![false; b.len()];
+	let mut matches = 0;
+
+	for (i, ca) in a.iter().enumerate() {
+		let start = i.saturating_sub(window);
+		let end = (i + window + 1).min(b.len());
+
+		if start >= end {
+			continue;
+		}
+
+		let candidates = b_matched[start..end].iter_mut().zip(&b[start..end]);
+		for (matched, cb) in candidates {
+			if !*matched && ca == cb {
+				*matched = true;
+				a_matched[i] = true;
+				matches += 1;
+				break;
+			}
+		}
+	}
+
+	if matches == 0 {
+		return 0.0;
+	}
+
+	let a_chars = a.iter().zip(&a_matched).filter(|&(_, &m)| m);
+	let b_chars = b.iter().zip(&b_matched).filter(|&(_, &m)| m);
+	let transpositions =
+		a_chars.zip(b_chars).filter(|((ca, _), (cb, _))| ca != cb).count();
+
+	let m = matches as f64;
+	let t = transpositions as f64 / 2.0;
+
+	(m / a.len() as f64 + m / b.len() as f64 + (m - t) / m) / 3.0
+}
+
+fn jaro_winkler(a: &[char], b: &[char]) -> f64 {
+	let jaro = jaro(a, b);
+	let prefix = a.iter().zip(b).take(4).take_while(|(a, b)| a == b).count();
+
+	jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+/// Token set ratio: compares the shared words with the shared words plus
+/// the remaining words of either title, taking the best of these.
+fn token_set(a: &str, b: &str) -> f64 {
+	let mut a: Vec<_> = a.split(' ').filter(|w| !w.is_empty()).collect();
+	let mut b: Vec<_> = b.split(' ').filter(|w| !w.is_empty()).collect();
+	a.sort_unstable();
+	a.dedup();
+	b.sort_unstable();
+	b.dedup();
+
+	if a.is_empty() || b.is_empty() {
+		return f64::from(u8::from(a == b));
+	}
+
+	let shared: Vec<_> =
+		a.iter().filter(|&&w| b.binary_search(&w).is_ok()).copied().collect();
+	let shared_text = shared.join(" ");
+	let with = |rest: Vec<&str>| {
+		let mut words = shared.clone();
+		words.extend(rest);
+		words.join(" ")
+	};
+	let a_text = with(only(&a, &shared));
+	let b_text = with(only(&b, &shared));
+
+	let mut best = levenshtein_similarity(&a_text, &b_text);
+	if !shared.is_empty() {
+		best = best
+			.max(levenshtein_similarity(&shared_text, &a_text))
+			.max(levenshtein_similarity(&shared_text, &b_text));
+	}
+
+	best
+}
+
+/// The `words` which aren't `shared`.
+fn only<'a>(words: &[&'a str], shared: &[&str]) -> Vec<&'a str> {
+	words.iter().filter(|&&w| !shared.contains(&w)).copied().collect()
+}
+
+#[test]
+fn scorers() {
+	let close = |a: f64, b: f64| (a - b).abs() < 1e-3;
+
+	assert_eq!(Scorer::Exact.score("utena", "utena"), 1.0);
+	assert_eq!(Scorer::Exact.score("utena", "utena movie"), 0.0);
+
+	assert!(close(
+		Scorer::Levenshtein.score("kitten", "sitting"),
+		1.0 - 3.0 / 7.0
+	));
+	assert_eq!(Scorer::Levenshtein.score("", ""), 1.0);
+
+	assert!(close(Scorer::JaroWinkler.score("martha", "marhta"), 0.961));
+	assert!(close(Scorer::JaroWinkler.score("dixon", "dicksonx"), 0.813));
+	assert_eq!(Scorer::JaroWinkler.score("abc", ""), 0.0);
+
+	assert_eq!(Scorer::TokenSet.score("titan attack", "attack on titan"), 1.0);
+	assert!(Scorer::TokenSet.score("a b", "c d") < 0.5);
+	assert!(
+		Scorer::TokenSet.score("attack titan", "attack titan movie") > 0.99
+	);
+}
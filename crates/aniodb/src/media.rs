@@ -0,0 +1,403 @@
+//! Bulk download of pictures and thumbnails.
+
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+use url::Url;
+
+use crate::fetch::{
+	default_agent, download, replace, Error, FetchOptions, Retry,
+};
+use crate::id::fnv1a;
+use crate::{Anime, Database};
+
+/// Which of the images of an entry to download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaKind {
+	Picture,
+	Thumbnail,
+}
+
+impl MediaKind {
+	pub fn url(self, anime: &Anime) -> &Url {
+		match self {
+			Self::Picture => &anime.picture,
+			Self::Thumbnail => &anime.thumbnail,
+		}
+	}
+}
+
+/// Outcome of downloading a single image.
+#[derive(Debug)]
+pub struct Download<'a> {
+	anime: &'a Anime,
+	kind: MediaKind,
+	path: PathBuf,
+	result: Result<(), Error>,
+}
+
+impl<'a> Download<'a> {
+	pub fn anime(&self) -> &'a Anime {
+		self.anime
+	}
+
+	pub fn kind(&self) -> MediaKind {
+		self.kind
+	}
+
+	pub fn url(&self) -> &'a Url {
+		self.kind.url(self.anime)
+	}
+
+	/// Where the image is stored, only valid if there was no error.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	pub fn error(&self) -> Option<&Error> {
+		self.result.as_ref().err()
+	}
+}
+
+/// File name an image url is stored under by
+/// [`Database::download_pictures`].
+///
+/// The name is a hash of the url, so it stays the same across runs and
+/// database versions, followed by the extension of the url if it has one.
+pub fn file_name(url: &Url) -> String {
+	let extension = url
+		.path_segments()
+		.and_then(|mut segments| segments.next_back())
+		.and_then(|name| name.rsplit_once('.'))
+		.map(|(_, extension)| extension)
+		.filter(|extension| {
+			(1..=5).contains(&extension.len())
+				&& extension.chars().all(|c| c.is_ascii_alphanumeric())
+		});
+
+	let hash = fnv1a(url.as_str().as_bytes());
+
+	match extension {
+		Some(extension) => {
+			format!("{hash:016x}.{}", extension.to_ascii_lowercase())
+		}
+		None => format!("{hash:016x}"),
+	}
+}
+
+impl Database {
+	/// Downloads the picture and thumbnail of `entries` into `dir`, using up
+	/// to `concurrency` parallel requests.
+	///
+	/// Files are named by [`file_name`], images already present in `dir` are
+	/// not downloaded again and every distinct url is downloaded once. The
+	/// results are in the order of `entries`, pictures before thumbnails.
+	pub fn download_pictures<'a>(
+		entries: impl IntoIterator<Item = &'a Anime>,
+		dir: impl AsRef<Path>,
+		concurrency: usize,
+	) -> Result<Vec<Download<'a>>, Error> {
+		download_all(
+			entries,
+			dir.as_ref(),
+			concurrency,
+			&default_agent(),
+			Retry::none(),
+		)
+	}
+
+	/// Like [`Database::download_pictures`], with the proxy, timeouts,
+	/// certificates, headers and retries of `options`.
+	pub fn download_pictures_with<'a>(
+		entries: impl IntoIterator<Item = &'a Anime>,
+		dir: impl AsRef<Path>,
+		concurrency: usize,
+		options: &FetchOptions,
+	) -> Result<Vec<Download<'a>>, Error> {
+		let agent = options.agent()?;
+		download_all(
+			entries,
+			dir.as_ref(),
+			concurrency,
+			&agent,
+			options.retries(),
+		)
+	}
+}
+
+/// Downloads the images of `entries` with `agent`, see
+/// [`Database::download_pictures`].
+fn download_all<'a>(
+	entries: impl IntoIterator<Item = &'a Anime>,
+	dir: &Path,
+	concurrency: usize,
+	agent: &ureq::Agent,
+	retry: Retry,
+) -> Result<Vec<Download<'a>>, Error> {
+	std::fs::create_dir_all(dir)?;
+
+	let mut seen = HashSet::new();
+	let jobs: Vec<_> = entries
+		.into_iter()
+		.flat_map(|anime| {
+			[(anime, MediaKind::Picture), (anime, MediaKind::Thumbnail)]
+		})
+		.filter(|&(anime, kind)| seen.insert(kind.url(anime)))
+		.collect();
+
+	let next = AtomicUsize::new(0);
+
+	let work = || {
+		let mut done = Vec::new();
+
+		loop {
+			let idx = next.fetch_add(1, Ordering::Relaxed);
+			let Some(&(anime, kind)) = jobs.get(idx) else {
+				break;
+			};
+
+			let url = kind.url(anime);
+			let path = dir.join(file_name(url));
+			let result = if path.exists() {
+				Ok(())
+			} else {
+				retry.run(|| download(agent, url.as_str())).and_then(|bytes| {
+					replace(&path, &bytes).map_err(Into::into)
+				})
+			};
+
+			done.push((idx, Download { anime, kind, path, result }));
+		}
+
+		done
+	};
+
+	let workers = concurrency.clamp(1, jobs.len().max(1));
+	let mut downloads: Vec<(usize, Download)> = std::thread::scope(|scope| {
+		let handles: Vec<_> =
+			(0..workers).map(|_| scope.spawn(work)).collect();
+
+		handles
+			.into_iter()
+			.flat_map(|handle| handle.join().expect("worker panicked"))
+			.collect()
+	});
+
+	// Workers finish in any order, restore the order of the jobs.
+	downloads.sort_by_key(|&(idx, _)| idx);
+
+	Ok(downloads.into_iter().map(|(_, download)| download).collect())
+}
+
+/// Images of entries stored in a directory, downloaded on first use.
+///
+/// Files are named by [`file_name`], so the cache can share a directory
+/// with [`Database::download_pictures`].
+#[derive(Debug, Clone)]
+pub struct ImageCache {
+	dir: PathBuf,
+	ttl: Option<Duration>,
+	max_size: Option<u64>,
+	agent: ureq::Agent,
+	retry: Retry,
+}
+
+impl ImageCache {
+	/// Caches images in `dir` forever and without size limit.
+	pub fn new(dir: impl Into<PathBuf>) -> Self {
+		Self {
+			dir: dir.into(),
+			ttl: None,
+			max_size: None,
+			agent: default_agent(),
+			retry: Retry::none(),
+		}
+	}
+
+	/// Downloads through the proxy and with the timeouts, certificates,
+	/// headers and retries of `options`.
+	pub fn options(mut self, options: &FetchOptions) -> Result<Self, Error> {
+		self.agent = options.agent()?;
+		self.retry = options.retries();
+		Ok(self)
+	}
+
+	/// Downloads images again once their file is older than `ttl`.
+	pub fn ttl(mut self, ttl: Duration) -> Self {
+		self.ttl = Some(ttl);
+		self
+	}
+
+	/// Keeps the total size of the cached files below `bytes` by removing
+	/// the oldest ones after every download.
+	pub fn max_size(mut self, bytes: u64) -> Self {
+		self.max_size = Some(bytes);
+		self
+	}
+
+	/// Returns the cached image, `None` if it is missing or expired.
+	pub fn cached(&self, anime: &Anime, kind: MediaKind) -> Option<PathBuf> {
+		let path = self.dir.join(file_name(kind.url(anime)));
+		let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+
+		(!self.is_expired(modified)).then_some(path)
+	}
+
+	/// Returns the cached image, downloading it if it is missing or expired.
+	pub fn get(
+		&self,
+		anime: &Anime,
+		kind: MediaKind,
+	) -> Result<PathBuf, Error> {
+		if let Some(path) = self.cached(anime, kind) {
+			return Ok(path);
+		}
+
+		let url = kind.url(anime);
+		let path = self.dir.join(file_name(url));
+
+		let bytes = self.retry.run(|| download(&self.agent, url.as_str()))?;
+		fs::create_dir_all(&self.dir)?;
+		replace(&path, &bytes)?;
+
+		if self.max_size.is_some() {
+			self.evict()?;
+		}
+
+		Ok(path)
+	}
+
+	/// Removes expired files and, if the cache is larger than its maximum
+	/// size, the oldest files until it fits. Returns the number of bytes
+	/// freed.
+	pub fn evict(&self) -> Result<u64, Error> {
+		let mut files = Vec::new();
+
+		for entry in fs::read_dir(&self.dir)? {
+			let entry = entry?;
+			let metadata = entry.metadata()?;
+
+			// Skips downloads in progress.
+			if metadata.is_file()
+				&& entry.path().extension() != Some(OsStr::new("part"))
+			{
+				files.push((
+					entry.path(),
+					metadata.len(),
+					metadata.modified()?,
+				));
+			}
+		}
+
+		// Newest first, so the oldest can be popped off the end.
+		files.sort_by_key(|&(_, _, modified)| std::cmp::Reverse(modified));
+
+		let mut size: u64 = files.iter().map(|&(_, len, _)| len).sum();
+		let max_size = self.max_size.unwrap_or(u64::MAX);
+		let mut freed = 0;
+
+		while let Some((path, len, modified)) = files.pop() {
+			if !self.is_expired(modified) && size <= max_size {
+				break;
+			}
+
+			fs::remove_file(&path)?;
+			size -= len;
+			freed += len;
+		}
+
+		Ok(freed)
+	}
+
+	fn is_expired(&self, modified: SystemTime) -> bool {
+		self.ttl
+			.is_some_and(|ttl| modified.elapsed().is_ok_and(|age| age > ttl))
+	}
+}
+
+#[test]
+fn media_file_name() {
+	let name = |s: &str| file_name(&Url::parse(s).unwrap());
+
+	assert_eq!(
+		name("https://cdn.myanimelist.net/images/anime/10/47347.JPG"),
+		name("https://cdn.myanimelist.net/images/anime/10/47347.JPG")
+	);
+	assert!(name("https://example.org/a.JPG").ends_with(".jpg"));
+	assert_eq!(name("https://example.org/a.webp").len(), 16 + 5);
+	assert_eq!(name("https://example.org/picture").len(), 16);
+	assert_ne!(
+		name("https://example.org/a.png"),
+		name("https://example.org/b.png")
+	);
+}
+
+#[test]
+fn media_download_present() {
+	use crate::testing::AnimeBuilder;
+
+	let anime = AnimeBuilder::new("Cached").build();
+	let dir = std::env::temp_dir().join("aniodb-media-present");
+	std::fs::create_dir_all(&dir).unwrap();
+
+	for kind in [MediaKind::Picture, MediaKind::Thumbnail] {
+		std::fs::write(dir.join(file_name(kind.url(&anime))), b"png").unwrap();
+	}
+
+	// Both files exist, so nothing is requested from example.org.
+	let downloads =
+		Database::download_pictures([&anime, &anime], &dir, 4).unwrap();
+
+	assert_eq!(downloads.len(), 2);
+	assert_eq!(downloads[0].kind(), MediaKind::Picture);
+	assert!(downloads.iter().all(|download| download.error().is_none()));
+
+	std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn media_cache() {
+	use std::fs::File;
+
+	use crate::testing::AnimeBuilder;
+
+	let dir = std::env::temp_dir().join("aniodb-media-cache");
+	let _ = fs::remove_dir_all(&dir);
+	fs::create_dir_all(&dir).unwrap();
+
+	let old = AnimeBuilder::new("Old").build();
+	let new = AnimeBuilder::new("New").build();
+	let age = |anime: &Anime, secs| {
+		let path = dir.join(file_name(&anime.picture));
+		fs::write(&path, [0; 10]).unwrap();
+		File::options()
+			.write(true)
+			.open(&path)
+			.unwrap()
+			.set_modified(SystemTime::now() - Duration::from_secs(secs))
+			.unwrap();
+	};
+
+	age(&old, 120);
+	age(&new, 0);
+
+	let cache = ImageCache::new(&dir).ttl(Duration::from_secs(60));
+	assert!(cache.cached(&old, MediaKind::Picture).is_none());
+	assert!(cache.cached(&new, MediaKind::Picture).is_some());
+	assert!(cache.cached(&new, MediaKind::Thumbnail).is_none());
+
+	assert_eq!(cache.evict().unwrap(), 10);
+	assert!(!dir.join(file_name(&old.picture)).exists());
+
+	// Without expiry only the size limit applies, removing the oldest.
+	age(&old, 120);
+	let cache = ImageCache::new(&dir).max_size(15);
+	assert_eq!(cache.evict().unwrap(), 10);
+	assert!(cache.cached(&new, MediaKind::Picture).is_some());
+
+	fs::remove_dir_all(&dir).unwrap();
+}
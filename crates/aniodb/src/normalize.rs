@@ -0,0 +1,116 @@
+//! Normalization of titles for comparison.
+
+/// Lowercases `s` and strips diacritics from common latin letters.
+pub(crate) fn fold(s: &str) -> String {
+	s.chars().flat_map(char::to_lowercase).map(fold_char).collect()
+}
+
+fn fold_char(c: char) -> char {
+	match c {
+		'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => 'a',
+		'ç' => 'c',
+		'è' | 'é' | 'ê' | 'ë' | 'ē' => 'e',
+		'ì' | 'í' | 'î' | 'ï' | 'ī' => 'i',
+		'ñ' => 'n',
+		'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => 'o',
+		'ù' | 'ú' | 'û' | 'ü' | 'ū' => 'u',
+		'ý' | 'ÿ' => 'y',
+		c => c,
+	}
+}
+
+/// Folds `title` and replaces punctuation and runs of whitespace with a
+/// single space, so `"Shōjo-Kakumei: Utena!"` and `"shojo kakumei utena"`
+/// compare equal.
+pub(crate) fn normalize(title: &str) -> String {
+	let folded = fold(title);
+	let words = folded
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty());
+
+	words.collect::<Vec<_>>().join(" ")
+}
+
+/// How titles are normalized before they are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Normalization {
+	/// Ignores case, diacritics and punctuation.
+	#[default]
+	Standard,
+	/// Additionally ignores differences between romanization systems: long
+	/// vowels (`ou`, `oo`, `uu`, `ō`), the particle `wo` written as `o` and
+	/// apostrophes, so `"Shoujo"` matches `"Shōjo"` and `"Ren'ai"` matches
+	/// `"Renai"`.
+	Romanized,
+}
+
+impl Normalization {
+	pub fn normalize(&self, title: &str) -> String {
+		match self {
+			Self::Standard => normalize(title),
+			Self::Romanized => romanize(title),
+		}
+	}
+}
+
+fn romanize(title: &str) -> String {
+	// Apostrophes separate syllables (`ren'ai`), not words.
+	let title: String = title
+		.chars()
+		.filter(|c| !matches!(c, '\'' | '’' | 'ʼ' | '`'))
+		.collect();
+
+	let words: Vec<_> = normalize(&title)
+		.split(' ')
+		.map(|word| match word {
+			"wo" => "o".to_owned(),
+			word => shorten_long_vowels(word),
+		})
+		.collect();
+
+	words.join(" ")
+}
+
+/// Collapses `ou`, `oo` and `uu` into a single vowel, matching Hepburn
+/// romanization after macrons were stripped by [`fold`].
+fn shorten_long_vowels(word: &str) -> String {
+	let mut short = String::with_capacity(word.len());
+
+	for c in word.chars() {
+		let long = matches!(
+			(short.chars().next_back(), c),
+			(Some('o'), 'o' | 'u') | (Some('u'), 'u')
+		);
+
+		if !long {
+			short.push(c);
+		}
+	}
+
+	short
+}
+
+#[test]
+fn normalize_titles() {
+	assert_eq!(normalize("Shōjo-Kakumei: Utena!"), "shojo kakumei utena");
+	assert_eq!(normalize("  Steins;Gate  0 "), "steins gate 0");
+	assert_eq!(normalize("進撃の巨人"), "進撃の巨人");
+	assert_eq!(normalize("?!"), "");
+}
+
+#[test]
+fn normalize_romanized() {
+	let romanize = |title| Normalization::Romanized.normalize(title);
+
+	assert_eq!(romanize("Shoujo Kakumei Utena"), "shojo kakumei utena");
+	assert_eq!(romanize("Shōjo Kakumei Utena"), "shojo kakumei utena");
+	assert_eq!(romanize("Ookami to Koushinryou"), "okami to koshinryo");
+	assert_eq!(romanize("Ren'ai Boukun"), "renai bokun");
+	assert_eq!(romanize("Kimi wo Aishita"), "kimi o aishita");
+	assert_eq!(romanize("Kyuuketsuki"), "kyuketsuki");
+	assert_eq!(romanize("!"), "");
+	assert_eq!(
+		Normalization::Standard.normalize("Ren'ai"),
+		normalize("Ren'ai")
+	);
+}
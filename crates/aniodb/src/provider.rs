@@ -0,0 +1,113 @@
+//! The sites entries are collected from.
+
+use url::Url;
+
+use crate::Anime;
+
+/// A site listed in the sources of an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Provider {
+	AniDb,
+	AniList,
+	AnimePlanet,
+	AniSearch,
+	Kitsu,
+	LiveChart,
+	MyAnimeList,
+	NotifyMoe,
+}
+
+impl Provider {
+	const ALL: [Self; 8] = [
+		Self::AniDb,
+		Self::AniList,
+		Self::AnimePlanet,
+		Self::AniSearch,
+		Self::Kitsu,
+		Self::LiveChart,
+		Self::MyAnimeList,
+		Self::NotifyMoe,
+	];
+
+	/// Hosts the provider serves entries from, the first being the current
+	/// one.
+	fn hosts(self) -> &'static [&'static str] {
+		match self {
+			Self::AniDb => &["anidb.net"],
+			Self::AniList => &["anilist.co"],
+			Self::AnimePlanet => &["anime-planet.com"],
+			Self::AniSearch => &["anisearch.com"],
+			Self::Kitsu => &["kitsu.app", "kitsu.io"],
+			Self::LiveChart => &["livechart.me"],
+			Self::MyAnimeList => &["myanimelist.net"],
+			Self::NotifyMoe => &["notify.moe"],
+		}
+	}
+
+	/// Splits a source url like `https://myanimelist.net/anime/1535` into
+	/// its provider and the id of the entry there (`"1535"`).
+	///
+	/// Ids are numeric for most providers but slugs for others (e.g.
+	/// anime-planet).
+	pub fn parse(url: &Url) -> Option<(Self, &str)> {
+		let host = url.host_str()?;
+		let host = host.strip_prefix("www.").unwrap_or(host);
+		let provider = Self::ALL
+			.into_iter()
+			.find(|provider| provider.hosts().contains(&host))?;
+
+		let mut segments = url.path_segments()?;
+		let id = match (segments.next(), segments.next(), segments.next()) {
+			(Some("anime"), Some(id), None | Some("")) if !id.is_empty() => id,
+			_ => return None,
+		};
+
+		Some((provider, id))
+	}
+}
+
+impl Anime {
+	/// Returns the id of the entry at `provider`, if it is one of the
+	/// sources.
+	pub fn source_id(&self, provider: Provider) -> Option<&str> {
+		self.sources.iter().find_map(|url| match Provider::parse(url) {
+			Some((p, id)) if p == provider => Some(id),
+			_ => None,
+		})
+	}
+}
+
+#[test]
+fn provider_parse() {
+	let parse = |s: &str| {
+		let url = Url::parse(s).unwrap();
+		Provider::parse(&url).map(|(provider, id)| (provider, id.to_owned()))
+	};
+
+	assert_eq!(
+		parse("https://myanimelist.net/anime/1535"),
+		Some((Provider::MyAnimeList, "1535".into()))
+	);
+	assert_eq!(
+		parse("https://kitsu.io/anime/1376/"),
+		Some((Provider::Kitsu, "1376".into()))
+	);
+	assert_eq!(
+		parse("https://www.anime-planet.com/anime/death-note"),
+		Some((Provider::AnimePlanet, "death-note".into()))
+	);
+	assert_eq!(parse("https://myanimelist.net/manga/1535"), None);
+	assert_eq!(parse("https://example.org/anime/1535"), None);
+	assert_eq!(parse("https://anilist.co/anime/1535/reviews"), None);
+}
+
+#[test]
+fn anime_source_id() {
+	use crate::testing::{url, AnimeBuilder};
+
+	let mut anime = AnimeBuilder::new("Death Note").build();
+	anime.sources.push(url("https://anilist.co/anime/1535"));
+
+	assert_eq!(anime.source_id(Provider::AniList), Some("1535"));
+	assert_eq!(anime.source_id(Provider::MyAnimeList), None);
+}
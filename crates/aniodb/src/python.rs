@@ -0,0 +1,294 @@
+//! Python bindings, built as the `aniodb` extension module.
+//!
+//! ```python
+//! import aniodb
+//!
+//! db = aniodb.Database.load("anime-offline-database.json")
+//! for anime in db.query().filter("kind:tv tag:(mecha OR drama)").run():
+//!     print(anime.title, anime.year)
+//! ```
+
+use std::sync::Arc;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::query::{Filter, Order, ParseError};
+use crate::{Anime, AnimeId, Database};
+
+fn parse_filter(query: &str) -> PyResult<Filter> {
+	query
+		.parse()
+		.map_err(|err: ParseError| PyValueError::new_err(err.to_string()))
+}
+
+/// A single entry, copied out of the database.
+#[pyclass(name = "Anime")]
+#[derive(Clone)]
+struct PyAnime(Anime);
+
+#[pymethods]
+impl PyAnime {
+	#[getter]
+	fn id(&self) -> String {
+		self.0.id().to_string()
+	}
+
+	#[getter]
+	fn title(&self) -> &str {
+		&self.0.title
+	}
+
+	#[getter]
+	fn kind(&self) -> String {
+		format!("{:?}", self.0.kind)
+	}
+
+	/// `None` if unknown.
+	#[getter]
+	fn episodes(&self) -> Option<u32> {
+		self.0.episodes.get()
+	}
+
+	#[getter]
+	fn status(&self) -> String {
+		format!("{:?}", self.0.status)
+	}
+
+	#[getter]
+	fn season(&self) -> String {
+		format!("{:?}", self.0.anime_season().season)
+	}
+
+	#[getter]
+	fn year(&self) -> Option<u32> {
+		self.0.anime_season().year
+	}
+
+	#[getter]
+	fn score(&self) -> Option<f64> {
+		self.0.score.map(|score| score.arithmetic_geometric_mean)
+	}
+
+	#[getter]
+	fn sources(&self) -> Vec<&str> {
+		self.0.sources.iter().map(|url| url.as_str()).collect()
+	}
+
+	#[getter]
+	fn synonyms(&self) -> Vec<&str> {
+		self.0.synonyms.iter().map(String::as_str).collect()
+	}
+
+	#[getter]
+	fn tags(&self) -> Vec<&str> {
+		self.0.tags.iter().map(|tag| &**tag).collect()
+	}
+
+	#[getter]
+	fn studios(&self) -> Vec<&str> {
+		self.0.studios().iter().map(|studio| &**studio).collect()
+	}
+
+	#[getter]
+	fn picture(&self) -> &str {
+		self.0.picture.as_str()
+	}
+
+	#[getter]
+	fn thumbnail(&self) -> &str {
+		self.0.thumbnail.as_str()
+	}
+
+	fn __repr__(&self) -> String {
+		format!("Anime({:?})", self.0.title)
+	}
+}
+
+#[pyclass(name = "Database")]
+struct PyDatabase(Arc<Database>);
+
+#[pymethods]
+impl PyDatabase {
+	#[staticmethod]
+	fn load(path: &str) -> PyResult<Self> {
+		Ok(Self(Arc::new(Database::load(path)?)))
+	}
+
+	#[staticmethod]
+	fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+		Ok(Self(Arc::new(Database::from_bytes(bytes)?)))
+	}
+
+	fn __len__(&self) -> usize {
+		self.0.data.len()
+	}
+
+	fn entries(&self) -> Vec<PyAnime> {
+		self.0.data.iter().cloned().map(PyAnime).collect()
+	}
+
+	fn query(&self) -> PyQuery {
+		PyQuery { db: Arc::clone(&self.0), steps: Vec::new() }
+	}
+
+	/// Entries matching a query string, see `Query.filter`.
+	fn search(&self, query: &str) -> PyResult<Vec<PyAnime>> {
+		self.query().filter(query).map(|query| query.run())
+	}
+
+	/// Looks up an entry by one of its titles, ignoring case, diacritics and
+	/// punctuation.
+	fn resolve(&self, title: &str) -> Option<PyAnime> {
+		self.0.resolve(title).map(|resolved| PyAnime(resolved.anime().clone()))
+	}
+
+	fn by_id(&self, id: &str) -> PyResult<Option<PyAnime>> {
+		let id: AnimeId =
+			id.parse().map_err(|_| PyValueError::new_err("invalid id"))?;
+
+		Ok(self.0.by_id(id).cloned().map(PyAnime))
+	}
+}
+
+#[derive(Clone)]
+enum Step {
+	Filter(Filter),
+	SortByTitle(Order),
+	SortByYear(Order),
+	SortByEpisodes(Order),
+}
+
+/// Builder for selecting entries, every method returns a new query.
+///
+/// Mirrors [`crate::query::Query`], which can't outlive the borrowed
+/// database, by replaying the recorded steps on `run`.
+#[pyclass(name = "Query")]
+#[derive(Clone)]
+struct PyQuery {
+	db: Arc<Database>,
+	steps: Vec<Step>,
+}
+
+impl PyQuery {
+	fn step(&self, step: Step) -> Self {
+		let mut query = self.clone();
+		query.steps.push(step);
+		query
+	}
+}
+
+#[pymethods]
+impl PyQuery {
+	/// Adds a query string like `kind:tv tag:(mecha OR drama)`.
+	fn filter(&self, query: &str) -> PyResult<Self> {
+		Ok(self.step(Step::Filter(parse_filter(query)?)))
+	}
+
+	/// `tv`, `movie`, `ova`, `ona`, `special` or `unknown`.
+	fn kind(&self, kind: &str) -> PyResult<Self> {
+		self.filter(&format!("kind:\"{kind}\""))
+	}
+
+	/// `finished`, `ongoing`, `upcoming` or `unknown`.
+	fn status(&self, status: &str) -> PyResult<Self> {
+		self.filter(&format!("status:\"{status}\""))
+	}
+
+	fn tag(&self, tag: &str) -> Self {
+		self.step(Step::Filter(Filter::Tag(tag.to_owned())))
+	}
+
+	fn year(&self, year: u32) -> Self {
+		self.years(year, year)
+	}
+
+	/// Inclusive range of years.
+	fn years(&self, start: u32, end: u32) -> Self {
+		self.step(Step::Filter(Filter::Years(start..=end)))
+	}
+
+	fn min_episodes(&self, min: u32) -> Self {
+		self.step(Step::Filter(Filter::MinEpisodes(min)))
+	}
+
+	fn max_episodes(&self, max: u32) -> Self {
+		self.step(Step::Filter(Filter::MaxEpisodes(max)))
+	}
+
+	/// Sorts by `title`, `year` or `episodes`, later keys break ties.
+	fn sort_by(&self, key: &str, descending: Option<bool>) -> PyResult<Self> {
+		let order = match descending {
+			Some(true) => Order::Descending,
+			_ => Order::Ascending,
+		};
+
+		let step = match key {
+			"title" => Step::SortByTitle(order),
+			"year" => Step::SortByYear(order),
+			"episodes" => Step::SortByEpisodes(order),
+			_ => {
+				return Err(PyValueError::new_err(format!(
+					"unknown sort key `{key}`"
+				)))
+			}
+		};
+
+		Ok(self.step(step))
+	}
+
+	fn run(&self) -> Vec<PyAnime> {
+		let query =
+			self.steps.iter().fold(
+				self.db.query(),
+				|query, step| match step {
+					Step::Filter(filter) => query.filter(filter.clone()),
+					Step::SortByTitle(order) => query.sort_by_title(*order),
+					Step::SortByYear(order) => query.sort_by_year(*order),
+					Step::SortByEpisodes(order) => {
+						query.sort_by_episodes(*order)
+					}
+				},
+			);
+
+		query.run().into_iter().cloned().map(PyAnime).collect()
+	}
+}
+
+#[pymodule]
+fn aniodb(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+	m.add_class::<PyDatabase>()?;
+	m.add_class::<PyQuery>()?;
+	m.add_class::<PyAnime>()?;
+	Ok(())
+}
+
+#[test]
+fn python_query() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = PyDatabase(Arc::new(database(vec![
+		AnimeBuilder::new("b").tag("drama").year(2001).build(),
+		AnimeBuilder::new("A").tag("Drama").year(1999).build(),
+		AnimeBuilder::new("Movie").kind(crate::AnimeKind::Movie).build(),
+	])));
+
+	let titles = |entries: Vec<PyAnime>| -> Vec<String> {
+		entries.into_iter().map(|anime| anime.0.title).collect()
+	};
+
+	let query = db.query().tag("drama");
+	assert_eq!(titles(query.run()), ["b", "A"]);
+	assert_eq!(
+		titles(query.sort_by("title", None).unwrap().run()),
+		["A", "b"]
+	);
+	assert_eq!(titles(query.years(2000, 2010).run()), ["b"]);
+	assert_eq!(titles(db.search("kind:movie").unwrap()), ["Movie"]);
+	assert!(query.sort_by("score", None).is_err());
+	assert!(db.query().kind("book").is_err());
+	assert_eq!(
+		db.resolve("a").map(|anime| anime.id()),
+		Some(db.0.data[1].id().to_string())
+	);
+}
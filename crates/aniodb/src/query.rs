@@ -0,0 +1,939 @@
+//! Filtering of database entries.
+//!
+//! Filters can be combined into arbitrary boolean expressions:
+//!
+//! ```no_run
+//! use aniodb::query::{any_of, kind, not, tag};
+//! use aniodb::{AnimeKind, Database};
+//!
+//! let db = Database::load("anime-offline-database.json").unwrap();
+//! let movies = db
+//!     .query()
+//!     .filter(any_of([kind(AnimeKind::Movie), kind(AnimeKind::Ova)]))
+//!     .filter(not(tag("ecchi")))
+//!     .run();
+//! ```
+//!
+//! Frontends can accept the same filters as query strings, see the
+//! [`FromStr` implementation](Filter#impl-FromStr-for-Filter) of
+//! [`Filter`]:
+//!
+//! ```no_run
+//! use aniodb::query::Filter;
+//! use aniodb::Database;
+//!
+//! let db = Database::load("anime-offline-database.json").unwrap();
+//! let filter: Filter = "kind:tv season:fall-2022 -tag:ecchi".parse().unwrap();
+//! let entries = db.query().filter(filter).run();
+//! ```
+//!
+//! Queries are also iterators, which only visit as many entries as needed:
+//!
+//! ```no_run
+//! use aniodb::{AnimeKind, AnimeStatus, Database};
+//!
+//! let db = Database::load("anime-offline-database.json").unwrap();
+//! let first: Vec<_> = db
+//!     .query()
+//!     .kind(AnimeKind::Tv)
+//!     .status(AnimeStatus::Ongoing)
+//!     .iter()
+//!     .take(10)
+//!     .collect();
+//! ```
+
+mod parse;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::num::ParseIntError;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+pub use self::parse::ParseError;
+use crate::{Anime, AnimeKind, AnimeStatus, Collation, Database, Season};
+
+/// A single condition an entry has to satisfy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+	Kind(AnimeKind),
+	Status(AnimeStatus),
+	Season(Season),
+	/// Inclusive range of years the entry aired in.
+	///
+	/// Entries without a year never match.
+	Years(RangeInclusive<u32>),
+	/// Tags are compared case-insensitively.
+	Tag(String),
+	/// Entries with an unknown episode count never match.
+	MinEpisodes(u32),
+	/// Entries with an unknown episode count never match.
+	MaxEpisodes(u32),
+	Not(Box<Filter>),
+	/// Matches if all filters match, an empty group always matches.
+	All(Vec<Filter>),
+	/// Matches if any filter matches, an empty group never matches.
+	Any(Vec<Filter>),
+}
+
+/// A compiled [`Filter`].
+pub type Predicate = Box<dyn Fn(&Anime) -> bool + Send + Sync>;
+
+impl Filter {
+	pub fn matches(&self, anime: &Anime) -> bool {
+		match self {
+			Self::Kind(kind) => anime.kind == *kind,
+			Self::Status(status) => anime.status == *status,
+			Self::Season(season) => anime.anime_season().season == *season,
+			Self::Years(years) => {
+				anime.anime_season().year.is_some_and(|y| years.contains(&y))
+			}
+			Self::Tag(tag) => {
+				anime.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+			}
+			Self::MinEpisodes(min) => {
+				anime.episodes.get().is_some_and(|n| n >= *min)
+			}
+			Self::MaxEpisodes(max) => {
+				anime.episodes.get().is_some_and(|n| n <= *max)
+			}
+			Self::Not(filter) => !filter.matches(anime),
+			Self::All(filters) => filters.iter().all(|f| f.matches(anime)),
+			Self::Any(filters) => filters.iter().any(|f| f.matches(anime)),
+		}
+	}
+
+	/// Compiles the filter into a closure.
+	///
+	/// Unlike [`Filter::matches`] this only inspects the filter once, which
+	/// makes it the better choice when applying it to many entries.
+	pub fn compile(&self) -> Predicate {
+		match self {
+			Self::Kind(kind) => {
+				let kind = *kind;
+				Box::new(move |anime: &Anime| anime.kind == kind)
+			}
+			Self::Status(status) => {
+				let status = *status;
+				Box::new(move |anime: &Anime| anime.status == status)
+			}
+			Self::Season(season) => {
+				let season = *season;
+				Box::new(move |anime: &Anime| {
+					anime.anime_season().season == season
+				})
+			}
+			Self::Years(years) => {
+				let years = years.clone();
+				Box::new(move |anime: &Anime| {
+					anime
+						.anime_season()
+						.year
+						.is_some_and(|y| years.contains(&y))
+				})
+			}
+			Self::Tag(tag) => {
+				let tag = tag.clone();
+				Box::new(move |anime: &Anime| {
+					anime.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag))
+				})
+			}
+			Self::MinEpisodes(min) => {
+				let min = *min;
+				Box::new(move |anime: &Anime| {
+					anime.episodes.get().is_some_and(|n| n >= min)
+				})
+			}
+			Self::MaxEpisodes(max) => {
+				let max = *max;
+				Box::new(move |anime: &Anime| {
+					anime.episodes.get().is_some_and(|n| n <= max)
+				})
+			}
+			Self::Not(filter) => match &**filter {
+				Self::Not(inner) => inner.compile(),
+				filter => {
+					let filter = filter.compile();
+					Box::new(move |anime: &Anime| !filter(anime))
+				}
+			},
+			Self::All(filters) => compile_all(filters),
+			Self::Any(filters) => match filters.as_slice() {
+				[] => Box::new(|_: &Anime| false),
+				[filter] => filter.compile(),
+				filters => {
+					let filters: Vec<_> =
+						filters.iter().map(Filter::compile).collect();
+					Box::new(move |anime: &Anime| {
+						filters.iter().any(|f| f(anime))
+					})
+				}
+			},
+		}
+	}
+
+	/// Positions of the entries which can match, in ascending order.
+	///
+	/// Tags are looked up in the tag index. Returns `None` if the filter
+	/// can't be narrowed down that way, e.g. for `Filter::Kind` or negations.
+	/// The returned entries still have to be checked with the filter.
+	fn candidates(&self, db: &Database) -> Option<Vec<usize>> {
+		match self {
+			Self::Tag(tag) => Some(
+				db.tag_index()
+					.get(&tag.to_ascii_lowercase())
+					.cloned()
+					.unwrap_or_default(),
+			),
+			Self::All(filters) => candidates_all(filters, db),
+			Self::Any(filters) => {
+				let mut union = Vec::new();
+				for filter in filters {
+					union.extend(filter.candidates(db)?);
+				}
+				union.sort_unstable();
+				union.dedup();
+				Some(union)
+			}
+			_ => None,
+		}
+	}
+}
+
+fn candidates_all(filters: &[Filter], db: &Database) -> Option<Vec<usize>> {
+	filters.iter().filter_map(|filter| filter.candidates(db)).reduce(
+		|mut intersection, candidates| {
+			intersection.retain(|idx| candidates.binary_search(idx).is_ok());
+			intersection
+		},
+	)
+}
+
+/// Positions of the entries carrying a tag, keyed by the lowercased tag and
+/// built on first use.
+///
+/// Always compares equal, like [`crate::id::IdIndex`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TagIndex(Arc<OnceLock<HashMap<String, Vec<usize>>>>);
+
+impl PartialEq for TagIndex {
+	fn eq(&self, _: &Self) -> bool {
+		true
+	}
+}
+
+impl Database {
+	fn tag_index(&self) -> &HashMap<String, Vec<usize>> {
+		self.tags.0.get_or_init(|| {
+			let mut index: HashMap<_, Vec<_>> = HashMap::new();
+			for (idx, anime) in self.data.iter().enumerate() {
+				for tag in &anime.tags {
+					let entries =
+						index.entry(tag.to_ascii_lowercase()).or_default();
+					// Entries listing a tag twice.
+					if entries.last() != Some(&idx) {
+						entries.push(idx);
+					}
+				}
+			}
+			index
+		})
+	}
+}
+
+fn compile_all(filters: &[Filter]) -> Predicate {
+	match filters {
+		[] => Box::new(|_: &Anime| true),
+		[filter] => filter.compile(),
+		filters => {
+			let filters: Vec<_> =
+				filters.iter().map(Filter::compile).collect();
+			Box::new(move |anime: &Anime| filters.iter().all(|f| f(anime)))
+		}
+	}
+}
+
+pub fn kind(kind: AnimeKind) -> Filter {
+	Filter::Kind(kind)
+}
+
+pub fn status(status: AnimeStatus) -> Filter {
+	Filter::Status(status)
+}
+
+pub fn season(season: Season) -> Filter {
+	Filter::Season(season)
+}
+
+pub fn years(years: RangeInclusive<u32>) -> Filter {
+	Filter::Years(years)
+}
+
+pub fn tag(tag: impl Into<String>) -> Filter {
+	Filter::Tag(tag.into())
+}
+
+pub fn not(filter: Filter) -> Filter {
+	Filter::Not(Box::new(filter))
+}
+
+pub fn all_of(filters: impl IntoIterator<Item = Filter>) -> Filter {
+	Filter::All(filters.into_iter().collect())
+}
+
+pub fn any_of(filters: impl IntoIterator<Item = Filter>) -> Filter {
+	Filter::Any(filters.into_iter().collect())
+}
+
+/// Builder for selecting entries of a [`Database`].
+///
+/// All filters have to match for an entry to be selected.
+#[derive(Debug, Clone)]
+pub struct Query<'a> {
+	db: &'a Database,
+	filters: Vec<Filter>,
+	sort: Vec<(SortKey, Order)>,
+}
+
+/// Direction results are sorted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Order {
+	#[default]
+	Ascending,
+	Descending,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+	Title,
+	Year,
+	Episodes,
+}
+
+impl SortKey {
+	fn compare(&self, order: Order, a: &Anime, b: &Anime) -> Ordering {
+		let apply = |ordering: Ordering| match order {
+			Order::Ascending => ordering,
+			Order::Descending => ordering.reverse(),
+		};
+
+		// Entries without a year or episode count go last in either
+		// direction.
+		let missing_last = |a: Option<u32>, b: Option<u32>| match (a, b) {
+			(Some(a), Some(b)) => apply(a.cmp(&b)),
+			(Some(_), None) => Ordering::Less,
+			(None, Some(_)) => Ordering::Greater,
+			(None, None) => Ordering::Equal,
+		};
+
+		match self {
+			Self::Title => {
+				apply(Collation::CaseInsensitive.compare(&a.title, &b.title))
+			}
+			Self::Year => {
+				missing_last(a.anime_season().year, b.anime_season().year)
+			}
+			Self::Episodes => missing_last(a.episodes.get(), b.episodes.get()),
+		}
+	}
+}
+
+impl<'a> Query<'a> {
+	pub(crate) fn new(db: &'a Database) -> Self {
+		Self { db, filters: Vec::new(), sort: Vec::new() }
+	}
+
+	pub fn filter(mut self, filter: Filter) -> Self {
+		self.filters.push(filter);
+		self
+	}
+
+	pub fn kind(self, kind: AnimeKind) -> Self {
+		self.filter(Filter::Kind(kind))
+	}
+
+	pub fn status(self, status: AnimeStatus) -> Self {
+		self.filter(Filter::Status(status))
+	}
+
+	pub fn season(self, season: Season) -> Self {
+		self.filter(Filter::Season(season))
+	}
+
+	pub fn tag(self, tag: impl Into<String>) -> Self {
+		self.filter(Filter::Tag(tag.into()))
+	}
+
+	pub fn year(self, year: u32) -> Self {
+		self.years(year..=year)
+	}
+
+	pub fn years(self, years: RangeInclusive<u32>) -> Self {
+		self.filter(Filter::Years(years))
+	}
+
+	/// Selects entries from the decade containing `year` (e.g. `1994` and
+	/// `1990` both select 1990 to 1999).
+	pub fn decade(self, year: u32) -> Self {
+		let start = year - year % 10;
+		self.years(start..=start.saturating_add(9))
+	}
+
+	pub fn min_episodes(self, min: u32) -> Self {
+		self.filter(Filter::MinEpisodes(min))
+	}
+
+	pub fn max_episodes(self, max: u32) -> Self {
+		self.filter(Filter::MaxEpisodes(max))
+	}
+
+	/// Sorts the results by title, ignoring case and diacritics.
+	///
+	/// Sorting by multiple keys breaks ties in the order they were added,
+	/// entries equal in all keys stay in database order.
+	pub fn sort_by_title(self, order: Order) -> Self {
+		self.sort_by(SortKey::Title, order)
+	}
+
+	/// Sorts the results by the year they aired in, entries without a year
+	/// go last.
+	pub fn sort_by_year(self, order: Order) -> Self {
+		self.sort_by(SortKey::Year, order)
+	}
+
+	/// Sorts the results by episode count, entries with an unknown count go
+	/// last.
+	pub fn sort_by_episodes(self, order: Order) -> Self {
+		self.sort_by(SortKey::Episodes, order)
+	}
+
+	fn sort_by(mut self, key: SortKey, order: Order) -> Self {
+		self.sort.push((key, order));
+		self
+	}
+
+	/// Entries starting at position `start` which can match, together with
+	/// their position.
+	///
+	/// Tag filters are answered from the tag index, all other entries are
+	/// visited in full.
+	fn candidates(
+		&self,
+		start: usize,
+	) -> Box<dyn Iterator<Item = (usize, &'a Anime)> + 'a> {
+		let db = self.db;
+
+		match candidates_all(&self.filters, db) {
+			Some(indices) => {
+				let skip = indices.partition_point(|&idx| idx < start);
+				Box::new(
+					indices
+						.into_iter()
+						.skip(skip)
+						.map(move |idx| (idx, &db.data[idx])),
+				)
+			}
+			None => Box::new(db.data.iter().enumerate().skip(start)),
+		}
+	}
+
+	/// Returns all matching entries, in database order unless sorted.
+	pub fn run(self) -> Vec<&'a Anime> {
+		self.iter().collect()
+	}
+
+	/// Returns the matching entries lazily, in database order unless
+	/// sorted.
+	///
+	/// Without sorting entries are only filtered as the iterator advances,
+	/// so adapters like [`Iterator::take`] or [`Iterator::find`] stop
+	/// scanning early. Sorted queries have to collect all matches on the
+	/// first call to `next`.
+	pub fn iter(self) -> Matches<'a> {
+		let predicate = compile_all(&self.filters);
+		let candidates = self.candidates(0);
+
+		if self.sort.is_empty() {
+			return Matches(State::Lazy(Box::new(
+				candidates
+					.map(|(_, anime)| anime)
+					.filter(move |&anime| predicate(anime)),
+			)));
+		}
+
+		Matches(State::Unsorted { sort: self.sort, predicate, candidates })
+	}
+
+	/// Splits the matching entries into pages of at most `size` entries.
+	pub fn page(self, size: usize) -> PagedQuery<'a> {
+		PagedQuery { query: self, size, after: None }
+	}
+}
+
+impl<'a> IntoIterator for Query<'a> {
+	type IntoIter = Matches<'a>;
+	type Item = &'a Anime;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
+}
+
+/// Iterator over the matches of a [`Query`], see [`Query::iter`].
+pub struct Matches<'a>(State<'a>);
+
+enum State<'a> {
+	Lazy(Box<dyn Iterator<Item = &'a Anime> + 'a>),
+	/// Not sorted yet, which happens on the first call to `next`.
+	Unsorted {
+		sort: Vec<(SortKey, Order)>,
+		predicate: Predicate,
+		candidates: Box<dyn Iterator<Item = (usize, &'a Anime)> + 'a>,
+	},
+	Sorted(std::vec::IntoIter<&'a Anime>),
+}
+
+impl<'a> Matches<'a> {
+	fn sort(&mut self) {
+		let State::Unsorted { sort, predicate, candidates } = &mut self.0
+		else {
+			return;
+		};
+
+		let mut matches: Vec<_> = candidates
+			.map(|(_, anime)| anime)
+			.filter(|&anime| predicate(anime))
+			.collect();
+
+		matches.sort_by(|a, b| {
+			sort.iter().fold(Ordering::Equal, |ordering, (key, order)| {
+				ordering.then_with(|| key.compare(*order, a, b))
+			})
+		});
+
+		self.0 = State::Sorted(matches.into_iter());
+	}
+}
+
+impl<'a> Iterator for Matches<'a> {
+	type Item = &'a Anime;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.sort();
+
+		match &mut self.0 {
+			State::Lazy(matches) => matches.next(),
+			State::Sorted(matches) => matches.next(),
+			State::Unsorted { .. } => unreachable!(),
+		}
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		match &self.0 {
+			State::Lazy(matches) => matches.size_hint(),
+			State::Unsorted { candidates, .. } => {
+				(0, candidates.size_hint().1)
+			}
+			State::Sorted(matches) => matches.size_hint(),
+		}
+	}
+}
+
+/// Position of an entry within the database, or within the results of a
+/// sorted query, used to resume pagination.
+///
+/// Cursors are stable as long as the database doesn't change, a cursor
+/// taken from one database version may skip or repeat entries in another.
+/// The string form is meant to be passed around opaquely, e.g. as a query
+/// parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cursor(usize);
+
+impl fmt::Display for Cursor {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+impl FromStr for Cursor {
+	type Err = ParseIntError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		s.parse().map(Self)
+	}
+}
+
+/// A [`Query`] returning a single page of its matches.
+pub struct PagedQuery<'a> {
+	query: Query<'a>,
+	size: usize,
+	after: Option<Cursor>,
+}
+
+impl<'a> PagedQuery<'a> {
+	/// Starts the page after the entry `cursor` points to.
+	pub fn after(mut self, cursor: Cursor) -> Self {
+		self.after = Some(cursor);
+		self
+	}
+
+	/// Returns the next page of matching entries.
+	///
+	/// Without sorting only the entries up to the end of the page are
+	/// visited, sorted queries have to collect all matches first.
+	pub fn run(self) -> Page<'a> {
+		let start = self.after.map_or(0, |Cursor(idx)| idx + 1);
+
+		if !self.query.sort.is_empty() {
+			let matches = self.query.run();
+			let items: Vec<_> =
+				matches.iter().skip(start).take(self.size).copied().collect();
+			let end = start + items.len();
+			let next = (!items.is_empty() && end < matches.len())
+				.then(|| Cursor(end - 1));
+
+			return Page { items, next };
+		}
+
+		let predicate = compile_all(&self.query.filters);
+
+		let mut matches = self
+			.query
+			.candidates(start)
+			.filter(|&(_, anime)| predicate(anime));
+
+		let mut items = Vec::with_capacity(self.size);
+		let mut last = None;
+
+		for (idx, anime) in matches.by_ref().take(self.size) {
+			items.push(anime);
+			last = Some(Cursor(idx));
+		}
+
+		// Peek whether anything is left to avoid handing out a cursor to an
+		// empty page.
+		let next = matches.next().and(last);
+
+		Page { items, next }
+	}
+}
+
+/// A slice of query results, see [`Query::page`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<'a> {
+	items: Vec<&'a Anime>,
+	next: Option<Cursor>,
+}
+
+impl<'a> Page<'a> {
+	pub fn items(&self) -> &[&'a Anime] {
+		&self.items
+	}
+
+	pub fn into_items(self) -> Vec<&'a Anime> {
+		self.items
+	}
+
+	/// Cursor of the following page, `None` if this is the last one.
+	pub fn next(&self) -> Option<Cursor> {
+		self.next
+	}
+}
+
+impl Database {
+	pub fn query(&self) -> Query<'_> {
+		Query::new(self)
+	}
+}
+
+#[test]
+fn query_years() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Eighties").year(1989).build(),
+		AnimeBuilder::new("Nineties").year(1995).build(),
+		AnimeBuilder::new("Nineties Movie")
+			.year(1999)
+			.kind(AnimeKind::Movie)
+			.build(),
+		AnimeBuilder::new("Unknown").build(),
+	]);
+
+	let titles = |q: Query| -> Vec<String> {
+		q.run().into_iter().map(|a| a.title.clone()).collect()
+	};
+
+	assert_eq!(
+		titles(db.query().decade(1994)),
+		["Nineties", "Nineties Movie"]
+	);
+	assert_eq!(
+		titles(db.query().decade(1990).kind(AnimeKind::Tv)),
+		["Nineties"]
+	);
+	assert!(titles(db.query().decade(u32::MAX)).is_empty());
+	assert_eq!(
+		titles(db.query().years(1980..=1995)),
+		["Eighties", "Nineties"]
+	);
+	assert_eq!(titles(db.query().year(2000)), Vec::<String>::new());
+}
+
+#[test]
+fn query_episodes() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Unknown").episodes(0).build(),
+		AnimeBuilder::new("Long").episodes(50).build(),
+		AnimeBuilder::new("Short").episodes(1).build(),
+	]);
+
+	let titles = |entries: Vec<&Anime>| -> Vec<String> {
+		entries.into_iter().map(|a| a.title.clone()).collect()
+	};
+
+	assert_eq!(titles(db.query().max_episodes(12).run()), ["Short"]);
+	assert_eq!(titles(db.query().min_episodes(0).run()), ["Long", "Short"]);
+	assert_eq!(
+		titles(db.query().sort_by_episodes(Order::Descending).run()),
+		["Long", "Short", "Unknown"]
+	);
+}
+
+#[test]
+fn query_combinators() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Tv").tag("ecchi").build(),
+		AnimeBuilder::new("Movie").kind(AnimeKind::Movie).build(),
+		AnimeBuilder::new("Ova").kind(AnimeKind::Ova).tag("ecchi").build(),
+		AnimeBuilder::new("Special").kind(AnimeKind::Special).build(),
+	]);
+
+	let filter = all_of([
+		not(tag("Ecchi")),
+		any_of([kind(AnimeKind::Movie), kind(AnimeKind::Special)]),
+	]);
+
+	let titles = |filter: &Filter| -> Vec<String> {
+		let predicate = filter.compile();
+		let compiled: Vec<_> = db
+			.data
+			.iter()
+			.filter(|&a| predicate(a))
+			.map(|a| &a.title)
+			.collect();
+		let matched: Vec<_> = db
+			.data
+			.iter()
+			.filter(|a| filter.matches(a))
+			.map(|a| &a.title)
+			.collect();
+
+		assert_eq!(compiled, matched);
+		compiled.into_iter().cloned().collect()
+	};
+
+	assert_eq!(titles(&filter), ["Movie", "Special"]);
+	assert_eq!(titles(&not(not(tag("ecchi")))), ["Tv", "Ova"]);
+	assert_eq!(titles(&Filter::Any(vec![])), Vec::<String>::new());
+	assert_eq!(titles(&Filter::All(vec![])).len(), 4);
+	assert_eq!(
+		db.query().filter(not(kind(AnimeKind::Tv))).tag("ecchi").run().len(),
+		1
+	);
+}
+
+#[test]
+fn query_pages() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(
+		(0..8)
+			.map(|i| {
+				let kind =
+					if i % 3 == 0 { AnimeKind::Movie } else { AnimeKind::Tv };
+				AnimeBuilder::new(&format!("Entry {}", i)).kind(kind).build()
+			})
+			.collect(),
+	);
+
+	let titles = |page: &Page| -> Vec<String> {
+		page.items().iter().map(|a| a.title.clone()).collect()
+	};
+
+	let first = db.query().kind(AnimeKind::Tv).page(2).run();
+	assert_eq!(titles(&first), ["Entry 1", "Entry 2"]);
+
+	let cursor: Cursor = first.next().unwrap().to_string().parse().unwrap();
+	let second = db.query().kind(AnimeKind::Tv).page(2).after(cursor).run();
+	assert_eq!(titles(&second), ["Entry 4", "Entry 5"]);
+
+	let last = db
+		.query()
+		.kind(AnimeKind::Tv)
+		.page(2)
+		.after(second.next().unwrap())
+		.run();
+	assert_eq!(titles(&last), ["Entry 7"]);
+	assert_eq!(last.next(), None);
+
+	// The last page being exactly full doesn't yield a cursor either.
+	let all = db.query().kind(AnimeKind::Movie).page(3).run();
+	assert_eq!(titles(&all), ["Entry 0", "Entry 3", "Entry 6"]);
+	assert_eq!(all.next(), None);
+	assert!("x".parse::<Cursor>().is_err());
+}
+
+#[test]
+fn query_sort() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("beta").year(2001).build(),
+		AnimeBuilder::new("Alpha").year(2001).build(),
+		AnimeBuilder::new("Élan").build(),
+		AnimeBuilder::new("gamma").year(1999).build(),
+	]);
+
+	let titles = |entries: &[&Anime]| -> Vec<String> {
+		entries.iter().map(|a| a.title.clone()).collect()
+	};
+
+	assert_eq!(
+		titles(&db.query().sort_by_title(Order::Ascending).run()),
+		["Alpha", "beta", "Élan", "gamma"]
+	);
+	assert_eq!(
+		titles(
+			&db.query()
+				.sort_by_year(Order::Descending)
+				.sort_by_title(Order::Descending)
+				.run()
+		),
+		["beta", "Alpha", "gamma", "Élan"]
+	);
+
+	let query = || db.query().sort_by_year(Order::Ascending);
+	let first = query().page(2).run();
+	assert_eq!(titles(first.items()), ["gamma", "beta"]);
+
+	let second = query().page(2).after(first.next().unwrap()).run();
+	assert_eq!(titles(second.items()), ["Alpha", "Élan"]);
+	assert_eq!(second.next(), None);
+}
+
+#[test]
+fn query_tag_expression() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Mecha").tag("mecha").build(),
+		AnimeBuilder::new("Mecha Romance").tag("Mecha").tag("romance").build(),
+		AnimeBuilder::new("Mecha Drama Ecchi")
+			.tag("mecha")
+			.tag("drama")
+			.tag("ecchi")
+			.build(),
+		AnimeBuilder::new("Drama").tag("drama").tag("drama").build(),
+		AnimeBuilder::new("Mecha Drama Movie")
+			.tag("mecha")
+			.tag("drama")
+			.kind(AnimeKind::Movie)
+			.build(),
+	]);
+
+	let titles = |entries: Vec<&Anime>| -> Vec<String> {
+		entries.into_iter().map(|a| a.title.clone()).collect()
+	};
+	let run = |query: &str| {
+		let filter: Filter = query.parse().unwrap();
+		let scanned: Vec<_> =
+			db.data.iter().filter(|a| filter.matches(a)).collect();
+		let indexed = db.query().filter(filter).run();
+
+		assert_eq!(indexed, scanned);
+		titles(indexed)
+	};
+
+	assert_eq!(
+		run("tag:(mecha AND (romance OR drama)) NOT tag:ecchi"),
+		["Mecha Romance", "Mecha Drama Movie"]
+	);
+	assert_eq!(
+		run("tag:drama OR kind:movie"),
+		["Mecha Drama Ecchi", "Drama", "Mecha Drama Movie"]
+	);
+	assert_eq!(run("tag:drama kind:movie"), ["Mecha Drama Movie"]);
+	assert_eq!(run("tag:(unknown OR romance)"), ["Mecha Romance"]);
+
+	let query = || db.query().filter("tag:drama".parse().unwrap());
+	let first = query().page(2).run();
+	assert_eq!(titles(first.into_items()), ["Mecha Drama Ecchi", "Drama"]);
+
+	let cursor = query().page(2).run().next().unwrap();
+	let second = query().page(2).after(cursor).run();
+	assert_eq!(titles(second.items().to_vec()), ["Mecha Drama Movie"]);
+	assert_eq!(second.next(), None);
+}
+
+#[test]
+fn query_iter() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(
+		(0..6)
+			.map(|i| {
+				let status = if i % 2 == 0 {
+					AnimeStatus::Ongoing
+				} else {
+					AnimeStatus::Finished
+				};
+				AnimeBuilder::new(&format!("Entry {}", i))
+					.status(status)
+					.year(2000 + i)
+					.build()
+			})
+			.collect(),
+	);
+
+	let titles = |entries: Vec<&Anime>| -> Vec<String> {
+		entries.into_iter().map(|a| a.title.clone()).collect()
+	};
+
+	let mut ongoing = db.query().status(AnimeStatus::Ongoing).iter();
+	assert_eq!(
+		titles(ongoing.by_ref().take(2).collect()),
+		["Entry 0", "Entry 2"]
+	);
+	assert_eq!(titles(ongoing.collect()), ["Entry 4"]);
+
+	let finished = || {
+		db.query()
+			.status(AnimeStatus::Finished)
+			.sort_by_year(Order::Descending)
+	};
+	assert_eq!(
+		titles(
+			finished()
+				.into_iter()
+				.skip_while(|a| a.title == "Entry 5")
+				.collect()
+		),
+		["Entry 3", "Entry 1"]
+	);
+	assert_eq!(finished().iter().count(), 3);
+	assert_eq!(finished().tag("unknown").iter().next(), None);
+
+	let mut count = 0;
+	for anime in db.query().year(2003) {
+		assert_eq!(anime.title, "Entry 3");
+		count += 1;
+	}
+	assert_eq!(count, 1);
+}
@@ -0,0 +1,502 @@
+//! Parsing of query strings into filters.
+
+use std::iter::Peekable;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::query::Filter;
+use crate::{AnimeKind, AnimeStatus, Season};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+	#[error("Expected `key:value`, found `{0}`")]
+	MissingKey(String),
+	#[error("Unknown key `{0}`")]
+	UnknownKey(String),
+	#[error("Invalid value `{value}` for `{key}`")]
+	InvalidValue { key: String, value: String },
+	#[error("Unterminated quote in `{0}`")]
+	UnterminatedQuote(String),
+	#[error("Unbalanced parentheses in `{0}`")]
+	UnbalancedParentheses(String),
+	#[error("Missing operand for `{0}`")]
+	MissingOperand(String),
+	#[error("Query nested deeper than {MAX_DEPTH} levels")]
+	TooDeep,
+}
+
+/// Maximum nesting of groups and negations, bounding the recursion of the
+/// parser on untrusted input.
+const MAX_DEPTH: usize = 128;
+
+/// Parses space separated `key:value` terms which all have to match, e.g.
+/// `kind:tv status:ongoing season:fall-2024 tag:"slice of life"
+/// episodes:>=12`.
+///
+/// | Key        | Values                                                 |
+/// |------------|--------------------------------------------------------|
+/// | `kind`     | `tv`, `movie`, `ova`, `ona`, `special`, `unknown`      |
+/// | `status`   | `finished`, `ongoing`, `upcoming`, `unknown`           |
+/// | `season`   | `winter`, `spring`, `summer`, `fall`, optionally with a year (`fall-2024`) |
+/// | `year`     | `2024`, `1990..1999`, `>=2000`, `<2000`, ...           |
+/// | `tag`      | any tag, quoted if it contains spaces or parentheses   |
+/// | `episodes` | `12`, `12..24`, `>=12`, `<13`, ...                     |
+///
+/// Prefixing a term with `-` negates it. An empty query matches every
+/// entry.
+///
+/// Terms can be combined with `AND`, `OR` and `NOT` and grouped with
+/// parentheses, `NOT` binding tightest and `OR` loosest. Terms next to each
+/// other are implicitly joined with `AND`. A group following a key applies
+/// the key to every bare value inside it:
+///
+/// ```text
+/// tag:(mecha AND (romance OR drama)) NOT tag:ecchi
+/// ```
+///
+/// Groups and negations can be nested up to 128 levels.
+impl FromStr for Filter {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parser = Parser {
+			input: s,
+			tokens: tokens(s)?.into_iter().peekable(),
+			depth: 0,
+		};
+
+		let filter = parser.expr(None)?;
+
+		match parser.tokens.next() {
+			None => Ok(filter),
+			Some(_) => Err(parser.unbalanced()),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+	Open,
+	Close,
+	And,
+	Or,
+	Not,
+	Term(String),
+}
+
+/// Splits `s` into parentheses, operators and terms.
+///
+/// Terms end at whitespace or parentheses outside of double quotes, the
+/// quotes are removed. Quoted operators are terms.
+fn tokens(s: &str) -> Result<Vec<Token>, ParseError> {
+	let mut tokens = Vec::new();
+	let mut chars = s.chars().peekable();
+
+	while let Some(&c) = chars.peek() {
+		match c {
+			c if c.is_whitespace() => {
+				chars.next();
+				continue;
+			}
+			'(' | ')' => {
+				chars.next();
+				tokens.push(if c == '(' { Token::Open } else { Token::Close });
+				continue;
+			}
+			_ => {}
+		}
+
+		let mut term = String::new();
+		let mut quoted = false;
+
+		while let Some(&c) = chars.peek() {
+			match c {
+				'"' => {
+					chars.next();
+					quoted = true;
+
+					loop {
+						match chars.next() {
+							Some('"') => break,
+							Some(c) => term.push(c),
+							None => {
+								return Err(ParseError::UnterminatedQuote(
+									s.to_owned(),
+								))
+							}
+						}
+					}
+				}
+				'(' | ')' => break,
+				c if c.is_whitespace() => break,
+				c => {
+					chars.next();
+					term.push(c);
+				}
+			}
+		}
+
+		tokens.push(match term.as_str() {
+			"AND" if !quoted => Token::And,
+			"OR" if !quoted => Token::Or,
+			"NOT" if !quoted => Token::Not,
+			_ => Token::Term(term),
+		});
+	}
+
+	Ok(tokens)
+}
+
+/// Recursive descent over the tokens of a query.
+///
+/// `key` is the key of the innermost `key:(...)` group, bare values inside
+/// it are parsed as `key:value`.
+struct Parser<'s> {
+	input: &'s str,
+	tokens: Peekable<std::vec::IntoIter<Token>>,
+	/// Groups and negations around the current token.
+	depth: usize,
+}
+
+impl Parser<'_> {
+	/// Runs `parse` one level deeper, failing past [`MAX_DEPTH`].
+	fn nested<T>(
+		&mut self,
+		parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+	) -> Result<T, ParseError> {
+		if self.depth == MAX_DEPTH {
+			return Err(ParseError::TooDeep);
+		}
+
+		self.depth += 1;
+		let result = parse(self);
+		self.depth -= 1;
+
+		result
+	}
+
+	fn expr(&mut self, key: Option<&str>) -> Result<Filter, ParseError> {
+		let mut alternatives = vec![self.conjunction(key)?];
+
+		while self.tokens.next_if_eq(&Token::Or).is_some() {
+			alternatives.push(self.conjunction(key)?);
+		}
+
+		if alternatives.len() == 1 {
+			return Ok(all(alternatives.remove(0)));
+		}
+
+		if alternatives.iter().any(Vec::is_empty) {
+			return Err(ParseError::MissingOperand("OR".into()));
+		}
+
+		Ok(Filter::Any(alternatives.into_iter().map(all).collect()))
+	}
+
+	fn conjunction(
+		&mut self,
+		key: Option<&str>,
+	) -> Result<Vec<Filter>, ParseError> {
+		let mut filters = Vec::new();
+
+		loop {
+			match self.tokens.peek() {
+				None | Some(Token::Or | Token::Close) => return Ok(filters),
+				Some(Token::And) => {
+					self.tokens.next();
+
+					let operand = matches!(
+						self.tokens.peek(),
+						Some(Token::Open | Token::Not | Token::Term(_))
+					);
+					if filters.is_empty() || !operand {
+						return Err(ParseError::MissingOperand("AND".into()));
+					}
+				}
+				Some(_) => filters.push(self.unary(key)?),
+			}
+		}
+	}
+
+	fn unary(&mut self, key: Option<&str>) -> Result<Filter, ParseError> {
+		match self.tokens.next() {
+			Some(Token::Open) => self.group(key),
+			Some(Token::Term(term)) => self.term(&term, key),
+			Some(Token::Not) => match self.tokens.peek() {
+				Some(Token::Open | Token::Not | Token::Term(_)) => {
+					let filter = self.nested(|parser| parser.unary(key))?;
+					Ok(Filter::Not(Box::new(filter)))
+				}
+				_ => Err(ParseError::MissingOperand("NOT".into())),
+			},
+			_ => unreachable!("conjunction only passes on operands"),
+		}
+	}
+
+	/// Parses the rest of a group after its opening parenthesis.
+	fn group(&mut self, key: Option<&str>) -> Result<Filter, ParseError> {
+		let filter = self.nested(|parser| parser.expr(key))?;
+
+		match self.tokens.next() {
+			Some(Token::Close) => Ok(filter),
+			_ => Err(self.unbalanced()),
+		}
+	}
+
+	fn term(
+		&mut self,
+		term: &str,
+		key: Option<&str>,
+	) -> Result<Filter, ParseError> {
+		if let Some(term) = term.strip_prefix('-') {
+			let filter = self.nested(|parser| parser.term(term, key))?;
+			return Ok(Filter::Not(Box::new(filter)));
+		}
+
+		if self.tokens.peek() == Some(&Token::Open) {
+			// `-(...)`
+			if term.is_empty() {
+				self.tokens.next();
+				return self.group(key);
+			}
+
+			if let Some(key) = term.strip_suffix(':') {
+				self.tokens.next();
+				return self.group(Some(key));
+			}
+		}
+
+		match key {
+			Some(key) => term_filter(&format!("{key}:{term}")),
+			None => term_filter(term),
+		}
+	}
+
+	fn unbalanced(&self) -> ParseError {
+		ParseError::UnbalancedParentheses(self.input.to_owned())
+	}
+}
+
+/// Joins `filters` with `AND`, unwrapping single filters.
+fn all(mut filters: Vec<Filter>) -> Filter {
+	if filters.len() == 1 {
+		filters.remove(0)
+	} else {
+		Filter::All(filters)
+	}
+}
+
+fn term_filter(term: &str) -> Result<Filter, ParseError> {
+	if let Some(term) = term.strip_prefix('-') {
+		return Ok(Filter::Not(Box::new(term_filter(term)?)));
+	}
+
+	let (key, value) = term
+		.split_once(':')
+		.ok_or_else(|| ParseError::MissingKey(term.to_owned()))?;
+
+	let invalid = || ParseError::InvalidValue {
+		key: key.to_owned(),
+		value: value.to_owned(),
+	};
+	let lower = value.to_lowercase();
+
+	let filter = match key.to_lowercase().as_str() {
+		"kind" | "type" => Filter::Kind(match lower.as_str() {
+			"tv" => AnimeKind::Tv,
+			"movie" => AnimeKind::Movie,
+			"ova" => AnimeKind::Ova,
+			"ona" => AnimeKind::Ona,
+			"special" => AnimeKind::Special,
+			"unknown" => AnimeKind::Unknown,
+			_ => return Err(invalid()),
+		}),
+		"status" => Filter::Status(match lower.as_str() {
+			"finished" => AnimeStatus::Finished,
+			"ongoing" => AnimeStatus::Ongoing,
+			"upcoming" => AnimeStatus::Upcoming,
+			"unknown" => AnimeStatus::Unknown,
+			_ => return Err(invalid()),
+		}),
+		"season" => {
+			let (season, year) = match lower.split_once('-') {
+				Some((season, year)) => (season, Some(year)),
+				None => (lower.as_str(), None),
+			};
+
+			let season = Filter::Season(match season {
+				"winter" => Season::Winter,
+				"spring" => Season::Spring,
+				"summer" => Season::Summer,
+				"fall" | "autumn" => Season::Fall,
+				_ => return Err(invalid()),
+			});
+
+			match year {
+				Some(year) => {
+					let year = year.parse().map_err(|_| invalid())?;
+					Filter::All(vec![season, Filter::Years(year..=year)])
+				}
+				None => season,
+			}
+		}
+		"year" | "years" => Filter::Years(range(&lower).ok_or_else(invalid)?),
+		"tag" => Filter::Tag(value.to_owned()),
+		"episodes" | "eps" => {
+			let episodes = range(&lower).ok_or_else(invalid)?;
+			let (min, max) = (*episodes.start(), *episodes.end());
+
+			match (min, max) {
+				(0, u32::MAX) => Filter::All(Vec::new()),
+				(min, u32::MAX) => Filter::MinEpisodes(min),
+				(0, max) => Filter::MaxEpisodes(max),
+				(min, max) => Filter::All(vec![
+					Filter::MinEpisodes(min),
+					Filter::MaxEpisodes(max),
+				]),
+			}
+		}
+		_ => return Err(ParseError::UnknownKey(key.to_owned())),
+	};
+
+	Ok(filter)
+}
+
+/// Parses `12`, `12..24` (inclusive), `>=12`, `>12`, `<=12` and `<12`.
+fn range(s: &str) -> Option<RangeInclusive<u32>> {
+	let range = if let Some(min) = s.strip_prefix(">=") {
+		min.parse().ok()?..=u32::MAX
+	} else if let Some(min) = s.strip_prefix('>') {
+		min.parse::<u32>().ok()?.checked_add(1)?..=u32::MAX
+	} else if let Some(max) = s.strip_prefix("<=") {
+		0..=max.parse().ok()?
+	} else if let Some(max) = s.strip_prefix('<') {
+		0..=max.parse::<u32>().ok()?.checked_sub(1)?
+	} else if let Some((min, max)) = s.split_once("..") {
+		min.parse().ok()?..=max.parse().ok()?
+	} else {
+		let n = s.parse().ok()?;
+		n..=n
+	};
+
+	Some(range)
+}
+
+#[test]
+fn parse_query() {
+	use crate::query::{all_of, kind, not, season, status, tag, years};
+
+	let parse = |s: &str| s.parse::<Filter>();
+
+	assert_eq!(
+		parse(
+			"kind:tv status:ongoing season:fall-2024 tag:\"slice of life\" \
+			 episodes:>=12"
+		),
+		Ok(all_of([
+			kind(AnimeKind::Tv),
+			status(AnimeStatus::Ongoing),
+			all_of([season(Season::Fall), years(2024..=2024)]),
+			tag("slice of life"),
+			Filter::MinEpisodes(12),
+		]))
+	);
+	assert_eq!(parse("-Tag:Ecchi"), Ok(not(tag("Ecchi"))));
+	assert_eq!(parse("year:1990..1999"), Ok(years(1990..=1999)));
+	assert_eq!(parse("  "), Ok(Filter::All(Vec::new())));
+	assert_eq!(parse("episodes:<13"), Ok(Filter::MaxEpisodes(12)));
+	assert_eq!(
+		parse("episodes:12"),
+		Ok(all_of([Filter::MinEpisodes(12), Filter::MaxEpisodes(12)]))
+	);
+
+	assert_eq!(parse("tv"), Err(ParseError::MissingKey("tv".into())));
+	assert_eq!(
+		parse("studio:bones"),
+		Err(ParseError::UnknownKey("studio".into()))
+	);
+	assert_eq!(
+		parse("episodes:<0"),
+		Err(ParseError::InvalidValue {
+			key: "episodes".into(),
+			value: "<0".into()
+		})
+	);
+	assert!(matches!(
+		parse("tag:\"slice of"),
+		Err(ParseError::UnterminatedQuote(_))
+	));
+}
+
+#[test]
+fn parse_query_expressions() {
+	use crate::query::{all_of, any_of, kind, not, tag};
+
+	let parse = |s: &str| s.parse::<Filter>();
+
+	assert_eq!(
+		parse("tag:(mecha AND (romance OR drama)) NOT tag:ecchi"),
+		Ok(all_of([
+			all_of([tag("mecha"), any_of([tag("romance"), tag("drama")])]),
+			not(tag("ecchi")),
+		]))
+	);
+	assert_eq!(
+		parse("kind:tv OR kind:movie tag:drama"),
+		Ok(any_of([
+			kind(AnimeKind::Tv),
+			all_of([kind(AnimeKind::Movie), tag("drama")]),
+		]))
+	);
+	assert_eq!(
+		parse("-(kind:tv OR tag:\"AND\")"),
+		Ok(not(any_of([kind(AnimeKind::Tv), tag("AND")])))
+	);
+	assert_eq!(
+		parse("tag:(-ecchi \"slice of life\")"),
+		Ok(all_of([not(tag("ecchi")), tag("slice of life")]))
+	);
+	assert_eq!(parse("NOT NOT tag:a"), Ok(not(not(tag("a")))));
+
+	assert_eq!(
+		parse("tag:(a OR b"),
+		Err(ParseError::UnbalancedParentheses("tag:(a OR b".into()))
+	);
+	assert_eq!(
+		parse("tag:a)"),
+		Err(ParseError::UnbalancedParentheses("tag:a)".into()))
+	);
+	assert_eq!(
+		parse("tag:a OR"),
+		Err(ParseError::MissingOperand("OR".into()))
+	);
+	assert_eq!(
+		parse("AND tag:a"),
+		Err(ParseError::MissingOperand("AND".into()))
+	);
+	assert_eq!(
+		parse("tag:a NOT"),
+		Err(ParseError::MissingOperand("NOT".into()))
+	);
+}
+
+#[test]
+fn parse_query_depth() {
+	let parse = |s: &str| s.parse::<Filter>();
+	let nested = |open: &str, close: &str, depth| {
+		format!("{}tag:a{}", open.repeat(depth), close.repeat(depth))
+	};
+
+	assert!(parse(&nested("(", ")", MAX_DEPTH)).is_ok());
+	assert!(parse(&nested("NOT ", "", MAX_DEPTH)).is_ok());
+	assert!(parse(&nested("-", "", MAX_DEPTH)).is_ok());
+
+	// Deep enough to overflow the stack without the limit.
+	for (open, close) in [("(", ")"), ("NOT ", ""), ("-", ""), ("tag:(", ")")]
+	{
+		assert_eq!(
+			parse(&nested(open, close, 300_000)),
+			Err(ParseError::TooDeep)
+		);
+	}
+}
@@ -0,0 +1,80 @@
+//! Random selection of entries.
+
+use rand::seq::{IteratorRandom, SliceRandom};
+
+use crate::{Anime, Database, Filter};
+
+impl Database {
+	/// Picks a single entry uniformly at random.
+	pub fn random(&self) -> Option<&Anime> {
+		self.data.choose(&mut rand::thread_rng())
+	}
+
+	/// Picks up to `n` distinct entries matching `filter`.
+	///
+	/// The returned entries are in no particular order.
+	pub fn sample(&self, n: usize, filter: &Filter) -> Vec<&Anime> {
+		let predicate = filter.compile();
+
+		self.data
+			.iter()
+			.filter(|&anime| predicate(anime))
+			.choose_multiple(&mut rand::thread_rng(), n)
+	}
+
+	/// Like [`Database::sample`], but entries are picked with a probability
+	/// proportional to `weight`.
+	///
+	/// Entries with a weight which is not greater than zero are never picked.
+	pub fn sample_weighted(
+		&self,
+		n: usize,
+		filter: &Filter,
+		weight: impl Fn(&Anime) -> f64,
+	) -> Vec<&Anime> {
+		let predicate = filter.compile();
+
+		let candidates: Vec<_> = self
+			.data
+			.iter()
+			.filter(|&anime| predicate(anime))
+			.map(|anime| (anime, weight(anime)))
+			.filter(|(_, weight)| *weight > 0.0)
+			.collect();
+
+		candidates
+			.choose_multiple_weighted(&mut rand::thread_rng(), n, |(_, w)| *w)
+			.map(|chosen| chosen.map(|(anime, _)| *anime).collect())
+			.unwrap_or_default()
+	}
+}
+
+#[test]
+fn db_sample() {
+	use crate::testing::{database, AnimeBuilder};
+	use crate::AnimeKind;
+
+	let db = database(vec![
+		AnimeBuilder::new("A").build(),
+		AnimeBuilder::new("B").kind(AnimeKind::Movie).build(),
+		AnimeBuilder::new("C").build(),
+		AnimeBuilder::new("D").build(),
+	]);
+
+	assert!(db.random().is_some());
+
+	let tv = Filter::Kind(AnimeKind::Tv);
+	let mut titles: Vec<_> =
+		db.sample(10, &tv).into_iter().map(|a| a.title.as_str()).collect();
+	titles.sort_unstable();
+	assert_eq!(titles, ["A", "C", "D"]);
+	assert_eq!(db.sample(2, &tv).len(), 2);
+
+	let weighted = db.sample_weighted(2, &tv, |a| match a.title.as_str() {
+		"A" => 1.0,
+		"C" => 0.0,
+		_ => f64::NAN,
+	});
+	assert_eq!(weighted.len(), 1);
+	assert_eq!(weighted[0].title, "A");
+}
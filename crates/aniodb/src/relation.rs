@@ -0,0 +1,230 @@
+//! Classification of related entries.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use url::Url;
+
+use crate::{Anime, AnimeKind, Database, Season};
+
+/// How a related entry relates to an entry, as guessed by
+/// [`Database::relations`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RelationKind {
+	/// Same kind of entry, aired later.
+	Sequel,
+	/// Same kind of entry, aired earlier.
+	Prequel,
+	/// An OVA, ONA or special of a series.
+	SideStory,
+	/// A movie of a series.
+	Movie,
+	/// Neither of the above could be told apart, e.g. because of unknown
+	/// seasons.
+	Other,
+}
+
+impl RelationKind {
+	/// Guesses the relation from the kind and airing order of both entries.
+	fn infer(anime: &Anime, related: &Anime) -> Self {
+		let side_story = matches!(
+			related.kind,
+			AnimeKind::Ova | AnimeKind::Ona | AnimeKind::Special
+		);
+
+		if related.kind != anime.kind {
+			if related.kind == AnimeKind::Movie {
+				return Self::Movie;
+			}
+			if side_story && anime.kind == AnimeKind::Tv {
+				return Self::SideStory;
+			}
+		}
+
+		match aired_before(anime, related) {
+			Some(Ordering::Less) => Self::Sequel,
+			Some(Ordering::Greater) => Self::Prequel,
+			_ => Self::Other,
+		}
+	}
+}
+
+/// Compares when both entries aired, `None` if that is unknown.
+fn aired_before(a: &Anime, b: &Anime) -> Option<Ordering> {
+	let (a, b) = (a.anime_season(), b.anime_season());
+
+	match a.year?.cmp(&b.year?) {
+		Ordering::Equal
+			if a.season != Season::Undefined
+				&& b.season != Season::Undefined =>
+		{
+			Some(a.season.cmp(&b.season))
+		}
+		Ordering::Equal => None,
+		ordering => Some(ordering),
+	}
+}
+
+/// A related entry found by [`Database::relations`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Relation<'a> {
+	anime: &'a Anime,
+	kind: RelationKind,
+}
+
+impl<'a> Relation<'a> {
+	pub fn anime(&self) -> &'a Anime {
+		self.anime
+	}
+
+	pub fn kind(&self) -> RelationKind {
+		self.kind
+	}
+}
+
+/// Lookup of entry positions by source, built on first use.
+///
+/// Always compares equal, like [`crate::id::IdIndex`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SourceIndex(Arc<OnceLock<HashMap<Url, usize>>>);
+
+impl PartialEq for SourceIndex {
+	fn eq(&self, _: &Self) -> bool {
+		true
+	}
+}
+
+impl Database {
+	/// Returns the entry listing `url` as one of its sources.
+	///
+	/// The first call builds an index of all sources, later ones are cheap.
+	pub fn by_source(&self, url: &Url) -> Option<&Anime> {
+		self.source_index(url).map(|idx| &self.data[idx])
+	}
+
+	pub(crate) fn source_index(&self, url: &Url) -> Option<usize> {
+		let index = self.sources.0.get_or_init(|| {
+			let mut index = HashMap::new();
+			for (idx, anime) in self.data.iter().enumerate() {
+				for source in &anime.sources {
+					index.entry(source.clone()).or_insert(idx);
+				}
+			}
+			index
+		});
+
+		index.get(url).copied()
+	}
+
+	/// Returns the entries `anime` relates to, with a guess of how they
+	/// relate.
+	///
+	/// Upstream only lists the related urls. Movies, side stories (OVAs,
+	/// ONAs and specials of a TV series) are told apart by their kind,
+	/// sequels and prequels by airing earlier or later. Relations pointing
+	/// to entries which aren't in the database are skipped, entries related
+	/// through multiple sources are returned once, in the order of their
+	/// first relation.
+	pub fn relations(&self, anime: &Anime) -> Vec<Relation<'_>> {
+		let mut seen = Vec::new();
+
+		anime
+			.relations
+			.iter()
+			.filter_map(|url| self.source_index(url))
+			.filter(|&idx| {
+				let new = !seen.contains(&idx);
+				seen.push(idx);
+				new
+			})
+			.map(|idx| &self.data[idx])
+			.filter(|related| !related.same_as(anime))
+			.map(|related| Relation {
+				anime: related,
+				kind: RelationKind::infer(anime, related),
+			})
+			.collect()
+	}
+
+	/// Returns the entries guessed to be sequels of `anime`, see
+	/// [`Database::relations`], in the order they aired.
+	pub fn sequels(&self, anime: &Anime) -> Vec<&Anime> {
+		let mut sequels: Vec<_> = self
+			.relations(anime)
+			.into_iter()
+			.filter(|relation| relation.kind == RelationKind::Sequel)
+			.map(|relation| relation.anime)
+			.collect();
+
+		// Unknown years go last.
+		sequels.sort_by_key(|anime| {
+			let season = anime.anime_season();
+			(season.year.unwrap_or(u32::MAX), season.season)
+		});
+
+		sequels
+	}
+}
+
+#[test]
+fn db_relations() {
+	use crate::testing::{database, url, AnimeBuilder};
+
+	let season = |title: &str, year, season| {
+		AnimeBuilder::new(title).year(year).season(season).build()
+	};
+
+	let mut first = season("First", 2013, Season::Spring);
+	let mut second = season("Second", 2017, Season::Spring);
+	let third = season("Third", 2018, Season::Summer);
+	let mut movie = season("Movie", 2015, Season::Summer);
+	movie.kind = AnimeKind::Movie;
+	let mut ova = season("Ova", 2014, Season::Winter);
+	ova.kind = AnimeKind::Ova;
+	let unknown = AnimeBuilder::new("Unknown").build();
+
+	let source = |anime: &Anime| anime.sources[0].clone();
+	for related in [&third, &second, &movie, &ova, &unknown] {
+		first.relations.push(source(related));
+	}
+	first.relations.push(url("https://example.org/anime/Gone"));
+	second.relations.push(source(&first));
+	second.sources.push(url("https://anilist.co/anime/2"));
+	first.relations.push(url("https://anilist.co/anime/2"));
+
+	let db = database(vec![
+		first.clone(),
+		second.clone(),
+		third,
+		movie,
+		ova,
+		unknown,
+	]);
+
+	let relations: Vec<_> = db
+		.relations(&first)
+		.into_iter()
+		.map(|relation| (relation.anime().title(), relation.kind()))
+		.collect();
+	assert_eq!(
+		relations,
+		[
+			("Third", RelationKind::Sequel),
+			("Second", RelationKind::Sequel),
+			("Movie", RelationKind::Movie),
+			("Ova", RelationKind::SideStory),
+			("Unknown", RelationKind::Other),
+		]
+	);
+
+	let sequels: Vec<_> =
+		db.sequels(&first).into_iter().map(Anime::title).collect();
+	assert_eq!(sequels, ["Second", "Third"]);
+
+	assert_eq!(db.relations(&second)[0].kind(), RelationKind::Prequel);
+	assert_eq!(
+		db.by_source(&url("https://anilist.co/anime/2")).map(Anime::title),
+		Some("Second")
+	);
+}
@@ -0,0 +1,202 @@
+//! Lookup of entries by any of their titles.
+
+use crate::matching::MatchConfig;
+use crate::{Anime, Database};
+
+/// An entry found by [`Database::resolve`] or [`Database::search`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Resolved<'a> {
+	anime: &'a Anime,
+	synonym: Option<&'a str>,
+	score: f64,
+}
+
+impl<'a> Resolved<'a> {
+	pub fn anime(&self) -> &'a Anime {
+		self.anime
+	}
+
+	/// The synonym that matched, `None` if the canonical title did.
+	pub fn synonym(&self) -> Option<&'a str> {
+		self.synonym
+	}
+
+	/// How well the title matched, from `0.0` to `1.0`, including the
+	/// synonym weight.
+	pub fn score(&self) -> f64 {
+		self.score
+	}
+}
+
+impl Database {
+	/// Looks up the entry for `title`, comparing it against canonical titles
+	/// and synonyms after normalizing case, diacritics and punctuation.
+	///
+	/// A match on a canonical title is preferred over a synonym match,
+	/// otherwise the first matching entry in database order is returned.
+	pub fn resolve(&self, title: &str) -> Option<Resolved<'_>> {
+		self.resolve_with(title, &MatchConfig::default())
+	}
+
+	/// Like [`Database::resolve`], comparing titles as configured by
+	/// `config`. Returns the best hit of [`Database::search`].
+	pub fn resolve_with(
+		&self,
+		title: &str,
+		config: &MatchConfig,
+	) -> Option<Resolved<'_>> {
+		self.search(title, config).into_iter().next()
+	}
+
+	/// Returns all entries with a title or synonym scoring at least the
+	/// minimum score of `config` against `title`, each with its best scoring
+	/// title.
+	///
+	/// Sorted by descending score, canonical titles before synonyms and then
+	/// in database order.
+	pub fn search(
+		&self,
+		title: &str,
+		config: &MatchConfig,
+	) -> Vec<Resolved<'_>> {
+		let needle = config.normalize(title);
+
+		if needle.is_empty() {
+			return Vec::new();
+		}
+
+		let mut hits: Vec<_> = self
+			.data
+			.iter()
+			.filter_map(|anime| {
+				let canonical = config
+					.score(&needle, &anime.title, false)
+					.map(|score| Resolved { anime, synonym: None, score });
+
+				let synonym = anime
+					.synonyms
+					.iter()
+					.filter_map(|synonym| {
+						config.score(&needle, synonym, true).map(|score| {
+							Resolved {
+								anime,
+								synonym: Some(synonym.as_str()),
+								score,
+							}
+						})
+					})
+					.fold(None, |best: Option<Resolved>, hit| match best {
+						Some(best) if best.score >= hit.score => Some(best),
+						_ => Some(hit),
+					});
+
+				match (canonical, synonym) {
+					(Some(canonical), Some(synonym))
+						if synonym.score > canonical.score =>
+					{
+						Some(synonym)
+					}
+					(None, synonym) => synonym,
+					(canonical, _) => canonical,
+				}
+			})
+			.collect();
+
+		hits.sort_by(|a, b| {
+			b.score
+				.total_cmp(&a.score)
+				.then_with(|| a.synonym.is_some().cmp(&b.synonym.is_some()))
+		});
+
+		hits
+	}
+}
+
+#[test]
+fn db_resolve() {
+	use crate::normalize::Normalization;
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Shingeki no Kyojin")
+			.synonym("Attack on Titan")
+			.build(),
+		AnimeBuilder::new("Attack on Titan: Junior High").build(),
+		AnimeBuilder::new("Kyojin").synonym("Attack on Titan Movie").build(),
+		AnimeBuilder::new("Attack on Titan (Movie)").build(),
+	]);
+
+	let resolved = db.resolve("attack-on-titan").unwrap();
+	assert_eq!(resolved.anime().title(), "Shingeki no Kyojin");
+	assert_eq!(resolved.synonym(), Some("Attack on Titan"));
+
+	// The canonical title wins over an earlier synonym.
+	let resolved = db.resolve("Attack on Titan Movie").unwrap();
+	assert_eq!(resolved.anime().title(), "Attack on Titan (Movie)");
+	assert_eq!(resolved.synonym(), None);
+
+	assert!(db.resolve("Attack on").is_none());
+
+	assert!(db.resolve("Shingeki no Kyoujin").is_none());
+	let romanized =
+		MatchConfig::default().normalization(Normalization::Romanized);
+	let resolved = db.resolve_with("Shingeki no Kyoujin", &romanized).unwrap();
+	assert_eq!(resolved.anime().title(), "Shingeki no Kyojin");
+	assert!(db.resolve("").is_none());
+
+	assert_eq!(db.resolve("Attack on Titan Movie").unwrap().score(), 1.0);
+}
+
+#[test]
+fn db_search() {
+	use crate::matching::Scorer;
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Shingeki no Kyojin")
+			.synonym("Attack on Titan")
+			.build(),
+		AnimeBuilder::new("Attack on Titan: Junior High").build(),
+		AnimeBuilder::new("Kyojin").synonym("Attack on Titam").build(),
+		AnimeBuilder::new("Cowboy Bebop").build(),
+	]);
+
+	let titles = |hits: Vec<Resolved<'_>>| -> Vec<String> {
+		hits.iter().map(|hit| hit.anime().title.clone()).collect()
+	};
+
+	// Exact matching only finds the identical synonym.
+	let exact = MatchConfig::default();
+	assert_eq!(
+		titles(db.search("Attack on Titan", &exact)),
+		["Shingeki no Kyojin"]
+	);
+
+	let typos = MatchConfig::new(Scorer::Levenshtein).min_score(0.9);
+	let hits = db.search("Attack on Titan", &typos);
+	assert_eq!(titles(hits.clone()), ["Shingeki no Kyojin", "Kyojin"]);
+	assert_eq!(hits[0].score(), 1.0);
+	assert_eq!(hits[1].synonym(), Some("Attack on Titam"));
+	assert!(hits[1].score() < 1.0);
+
+	// Lowering the minimum score trades precision for recall.
+	let loose = typos.min_score(0.5);
+	assert_eq!(db.search("Attack on Titan", &loose).len(), 3);
+
+	// Down-weighted synonyms lose against a worse canonical title.
+	let resolved = db.resolve_with("Attack on Titan", &loose).unwrap();
+	assert_eq!(resolved.anime().title(), "Shingeki no Kyojin");
+	let weighted = loose.synonym_weight(0.5);
+	let resolved = db.resolve_with("Attack on Titan", &weighted).unwrap();
+	assert_eq!(resolved.anime().title(), "Attack on Titan: Junior High");
+	assert_eq!(resolved.synonym(), None);
+
+	// Word order doesn't matter, ties prefer canonical titles.
+	let words = MatchConfig::new(Scorer::TokenSet);
+	assert_eq!(
+		titles(db.search("titan attack", &words)),
+		["Attack on Titan: Junior High", "Shingeki no Kyojin"]
+	);
+
+	assert!(db.search("", &loose).is_empty());
+}
@@ -0,0 +1,116 @@
+//! Loading only the fields needed for identifying entries.
+
+use std::io::Read;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::path::Path;
+
+use serde::Deserialize;
+use url::Url;
+
+use crate::{Anime, AnimeKind, AnimeStatus, EpisodeCount, SimpleDate};
+
+/// An entry reduced to its title, sources, kind, episode count and status.
+///
+/// All other fields are skipped while parsing, so pictures, tags, synonyms
+/// and relations are never allocated.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SlimAnime {
+	sources: Vec<Url>,
+	title: String,
+	#[serde(rename = "type")]
+	kind: AnimeKind,
+	episodes: EpisodeCount,
+	status: AnimeStatus,
+}
+
+impl SlimAnime {
+	pub fn sources(&self) -> &[Url] {
+		&self.sources
+	}
+
+	pub fn title(&self) -> &str {
+		&self.title
+	}
+
+	pub fn kind(&self) -> AnimeKind {
+		self.kind
+	}
+
+	pub fn episodes(&self) -> EpisodeCount {
+		self.episodes
+	}
+
+	pub fn status(&self) -> AnimeStatus {
+		self.status
+	}
+}
+
+impl From<&Anime> for SlimAnime {
+	fn from(anime: &Anime) -> Self {
+		Self {
+			sources: anime.sources.clone(),
+			title: anime.title.clone(),
+			kind: anime.kind,
+			episodes: anime.episodes,
+			status: anime.status,
+		}
+	}
+}
+
+/// A [`Database`](crate::Database) of [`SlimAnime`]s, for memory
+/// constrained use like title matching.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlimDatabase {
+	last_update: SimpleDate,
+	data: Vec<SlimAnime>,
+}
+
+impl SlimDatabase {
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+		Self::from_reader(std::fs::File::open(path)?)
+	}
+
+	pub fn from_reader(r: impl Read) -> Result<Self, std::io::Error> {
+		#[cfg(feature = "simd-json")]
+		{
+			simd_json::from_reader(r).map_err(std::io::Error::other)
+		}
+
+		#[cfg(not(feature = "simd-json"))]
+		{
+			serde_json::from_reader(r).map_err(std::io::Error::other)
+		}
+	}
+
+	pub fn last_update(&self) -> SimpleDate {
+		self.last_update
+	}
+
+	pub fn entries(&self) -> &[SlimAnime] {
+		&self.data
+	}
+}
+
+#[test]
+fn slim_db_read() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("Full")
+			.tag("drama")
+			.studio("bones")
+			.score(7.0)
+			.build(),
+		AnimeBuilder::new("Other").kind(AnimeKind::Movie).build(),
+	]);
+	let json = serde_json::to_vec(&db).unwrap();
+
+	let slim = SlimDatabase::from_reader(&json[..]).unwrap();
+	let expected: Vec<_> = db.data.iter().map(SlimAnime::from).collect();
+
+	assert_eq!(slim.entries(), expected);
+	assert_eq!(slim.last_update(), db.last_update);
+	assert_eq!(slim.entries()[1].kind(), AnimeKind::Movie);
+}
@@ -0,0 +1,96 @@
+//! Aggregate statistics over all entries.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{AnimeKind, AnimeStatus, Database, EpisodeCount, Season};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Stats<'a> {
+	total: usize,
+	by_kind: BTreeMap<AnimeKind, usize>,
+	by_status: BTreeMap<AnimeStatus, usize>,
+	by_season: BTreeMap<(Option<u32>, Season), usize>,
+	tags: Vec<(&'a str, usize)>,
+	episodes: BTreeMap<EpisodeCount, usize>,
+}
+
+impl<'a> Stats<'a> {
+	pub fn total(&self) -> usize {
+		self.total
+	}
+
+	pub fn by_kind(&self) -> &BTreeMap<AnimeKind, usize> {
+		&self.by_kind
+	}
+
+	pub fn by_status(&self) -> &BTreeMap<AnimeStatus, usize> {
+		&self.by_status
+	}
+
+	/// Counts per year and season, entries without a year come first.
+	pub fn by_season(&self) -> &BTreeMap<(Option<u32>, Season), usize> {
+		&self.by_season
+	}
+
+	/// Tag frequencies, most frequent first.
+	pub fn tags(&self) -> &[(&'a str, usize)] {
+		&self.tags
+	}
+
+	/// Number of entries per episode count, unknown counts come first.
+	pub fn episodes(&self) -> &BTreeMap<EpisodeCount, usize> {
+		&self.episodes
+	}
+}
+
+impl Database {
+	pub fn stats(&self) -> Stats<'_> {
+		let mut stats = Stats { total: self.data.len(), ..Default::default() };
+		let mut tags: HashMap<&str, usize> = HashMap::new();
+
+		for anime in self.data.iter() {
+			let season = anime.anime_season();
+
+			*stats.by_kind.entry(anime.kind).or_default() += 1;
+			*stats.by_status.entry(anime.status).or_default() += 1;
+			*stats
+				.by_season
+				.entry((season.year, season.season))
+				.or_default() += 1;
+			*stats.episodes.entry(anime.episodes).or_default() += 1;
+
+			for tag in &anime.tags {
+				*tags.entry(&**tag).or_default() += 1;
+			}
+		}
+
+		stats.tags = tags.into_iter().collect();
+		stats.tags.sort_by(|(a_tag, a), (b_tag, b)| {
+			b.cmp(a).then_with(|| a_tag.cmp(b_tag))
+		});
+
+		stats
+	}
+}
+
+#[test]
+fn db_stats() {
+	use crate::testing::{database, AnimeBuilder};
+
+	let db = database(vec![
+		AnimeBuilder::new("A").tag("action").tag("mecha").build(),
+		AnimeBuilder::new("B").kind(AnimeKind::Movie).tag("action").build(),
+		AnimeBuilder::new("C").year(2001).tag("drama").build(),
+	]);
+
+	let stats = db.stats();
+
+	assert_eq!(stats.total(), 3);
+	assert_eq!(stats.by_kind()[&AnimeKind::Tv], 2);
+	assert_eq!(stats.by_kind()[&AnimeKind::Movie], 1);
+	assert_eq!(stats.by_status()[&AnimeStatus::Finished], 3);
+	assert_eq!(stats.by_season()[&(None, Season::Spring)], 2);
+	assert_eq!(stats.by_season()[&(Some(2001), Season::Spring)], 1);
+	assert_eq!(stats.tags(), [("action", 2), ("drama", 1), ("mecha", 1)]);
+	assert_eq!(stats.episodes()[&EpisodeCount::from(12)], 3);
+}
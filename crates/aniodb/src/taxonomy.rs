@@ -0,0 +1,163 @@
+//! Grouping of upstream tags into broader categories.
+
+use crate::Anime;
+
+/// Broad category of a tag, see [`TagCategory::of`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TagCategory {
+	/// What kind of story is told, e.g. `comedy` or `horror`.
+	Genre,
+	/// Recurring setting or subject, e.g. `mecha` or `time travel`.
+	Theme,
+	/// The audience an entry is marketed to, e.g. `shounen`.
+	Demographic,
+	/// Content some viewers want to be warned about, e.g. `gore`.
+	ContentWarning,
+}
+
+/// Curated tags, sorted by tag for binary searching.
+///
+/// Tags describing neither of the categories (e.g. `based on a manga` or
+/// `present`) are left out on purpose.
+const TAXONOMY: &[(&str, TagCategory)] = &[
+	("action", TagCategory::Genre),
+	("adventure", TagCategory::Genre),
+	("aliens", TagCategory::Theme),
+	("angst", TagCategory::Genre),
+	("animal abuse", TagCategory::ContentWarning),
+	("avant garde", TagCategory::Genre),
+	("boys love", TagCategory::Genre),
+	("bullying", TagCategory::ContentWarning),
+	("child abuse", TagCategory::ContentWarning),
+	("comedy", TagCategory::Genre),
+	("cyberpunk", TagCategory::Theme),
+	("demons", TagCategory::Theme),
+	("detective", TagCategory::Theme),
+	("domestic abuse", TagCategory::ContentWarning),
+	("drama", TagCategory::Genre),
+	("drugs", TagCategory::ContentWarning),
+	("dystopia", TagCategory::Theme),
+	("ecchi", TagCategory::Genre),
+	("fantasy", TagCategory::Genre),
+	("game", TagCategory::Theme),
+	("girls love", TagCategory::Genre),
+	("gore", TagCategory::ContentWarning),
+	("gourmet", TagCategory::Genre),
+	("harem", TagCategory::Theme),
+	("historical", TagCategory::Theme),
+	("horror", TagCategory::Genre),
+	("idol", TagCategory::Theme),
+	("isekai", TagCategory::Theme),
+	("josei", TagCategory::Demographic),
+	("kids", TagCategory::Demographic),
+	("magic", TagCategory::Theme),
+	("martial arts", TagCategory::Theme),
+	("mecha", TagCategory::Theme),
+	("military", TagCategory::Theme),
+	("music", TagCategory::Theme),
+	("mystery", TagCategory::Genre),
+	("mythology", TagCategory::Theme),
+	("nudity", TagCategory::ContentWarning),
+	("parody", TagCategory::Theme),
+	("post-apocalyptic", TagCategory::Theme),
+	("psychological", TagCategory::Genre),
+	("rape", TagCategory::ContentWarning),
+	("reverse harem", TagCategory::Theme),
+	("romance", TagCategory::Genre),
+	("samurai", TagCategory::Theme),
+	("school", TagCategory::Theme),
+	("sci-fi", TagCategory::Genre),
+	("science fiction", TagCategory::Genre),
+	("seinen", TagCategory::Demographic),
+	("self-harm", TagCategory::ContentWarning),
+	("sexual abuse", TagCategory::ContentWarning),
+	("shoujo", TagCategory::Demographic),
+	("shoujo ai", TagCategory::Genre),
+	("shounen", TagCategory::Demographic),
+	("shounen ai", TagCategory::Genre),
+	("slice of life", TagCategory::Genre),
+	("space", TagCategory::Theme),
+	("sports", TagCategory::Genre),
+	("suicide", TagCategory::ContentWarning),
+	("super power", TagCategory::Theme),
+	("supernatural", TagCategory::Genre),
+	("suspense", TagCategory::Genre),
+	("thriller", TagCategory::Genre),
+	("time travel", TagCategory::Theme),
+	("torture", TagCategory::ContentWarning),
+	("vampire", TagCategory::Theme),
+	("violence", TagCategory::ContentWarning),
+	("yaoi", TagCategory::Genre),
+	("yuri", TagCategory::Genre),
+];
+
+impl TagCategory {
+	/// Looks up the category of an upstream tag, ignoring case.
+	///
+	/// Returns `None` for tags which aren't part of the curated mapping.
+	pub fn of(tag: &str) -> Option<Self> {
+		let tag = tag.to_lowercase();
+
+		TAXONOMY
+			.binary_search_by(|&(t, _)| t.cmp(tag.as_str()))
+			.ok()
+			.map(|idx| TAXONOMY[idx].1)
+	}
+}
+
+impl Anime {
+	/// Tags of the entry belonging to `category`, in upstream order.
+	pub fn tags_in(
+		&self,
+		category: TagCategory,
+	) -> impl Iterator<Item = &str> + '_ {
+		self.tags
+			.iter()
+			.map(|tag| &**tag)
+			.filter(move |tag| TagCategory::of(tag) == Some(category))
+	}
+
+	pub fn genres(&self) -> impl Iterator<Item = &str> + '_ {
+		self.tags_in(TagCategory::Genre)
+	}
+
+	pub fn themes(&self) -> impl Iterator<Item = &str> + '_ {
+		self.tags_in(TagCategory::Theme)
+	}
+
+	pub fn demographics(&self) -> impl Iterator<Item = &str> + '_ {
+		self.tags_in(TagCategory::Demographic)
+	}
+
+	pub fn content_warnings(&self) -> impl Iterator<Item = &str> + '_ {
+		self.tags_in(TagCategory::ContentWarning)
+	}
+}
+
+#[test]
+fn taxonomy_sorted() {
+	assert!(TAXONOMY.windows(2).all(|pair| pair[0].0 < pair[1].0));
+	assert!(TAXONOMY.iter().all(|(tag, _)| tag.to_lowercase() == *tag));
+}
+
+#[test]
+fn anime_tag_categories() {
+	use crate::testing::AnimeBuilder;
+
+	assert_eq!(TagCategory::of("Mecha"), Some(TagCategory::Theme));
+	assert_eq!(TagCategory::of("based on a manga"), None);
+
+	let anime = AnimeBuilder::new("Tagged")
+		.tag("mecha")
+		.tag("based on a manga")
+		.tag("Drama")
+		.tag("shounen")
+		.tag("gore")
+		.tag("comedy")
+		.build();
+
+	assert_eq!(anime.genres().collect::<Vec<_>>(), ["Drama", "comedy"]);
+	assert_eq!(anime.themes().collect::<Vec<_>>(), ["mecha"]);
+	assert_eq!(anime.demographics().collect::<Vec<_>>(), ["shounen"]);
+	assert_eq!(anime.content_warnings().collect::<Vec<_>>(), ["gore"]);
+}
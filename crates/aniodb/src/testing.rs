@@ -0,0 +1,251 @@
+//! Builders and proptest strategies for constructing database entries in
+//! tests.
+
+use std::sync::Arc;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use url::Url;
+
+use crate::{
+	Anime, AnimeKind, AnimeSeason, AnimeStatus, Database, DurationUnit,
+	EpisodeCount, EpisodeDuration, License, Score, Season, SimpleDate,
+};
+
+pub(crate) fn url(s: &str) -> Url {
+	Url::parse(s).expect("valid test url")
+}
+
+pub(crate) fn database(data: Vec<Anime>) -> Database {
+	Database {
+		license: License {
+			name: "GNU Affero General Public License v3.0".into(),
+			url: url("https://github.com/manami-project/anime-offline-database/blob/master/LICENSE"),
+		},
+		repository: url(
+			"https://github.com/manami-project/anime-offline-database",
+		),
+		last_update: SimpleDate { year: 2022, month: 9, day: 1 },
+		data: data.into(),
+		dead: Default::default(),
+		ids: Default::default(),
+		tags: Default::default(),
+		sources: Default::default(),
+		titles: Default::default(),
+	}
+}
+
+pub(crate) struct AnimeBuilder(Anime);
+
+impl AnimeBuilder {
+	pub(crate) fn new(title: &str) -> Self {
+		let slug: String = title
+			.chars()
+			.map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+			.collect();
+
+		Self(Anime {
+			sources: vec![url(&format!("https://example.org/anime/{slug}"))],
+			title: title.into(),
+			kind: AnimeKind::Tv,
+			episodes: 12.into(),
+			status: AnimeStatus::Finished,
+			anime_season: Some(AnimeSeason {
+				season: Season::Spring,
+				year: None,
+			}),
+			picture: url(&format!("https://example.org/pictures/{slug}.png")),
+			thumbnail: url(&format!(
+				"https://example.org/thumbnails/{slug}.png"
+			)),
+			synonyms: Vec::new(),
+			relations: Vec::new(),
+			tags: Vec::new(),
+			duration: None,
+			score: None,
+			studios: None,
+			producers: None,
+		})
+	}
+
+	pub(crate) fn score(mut self, mean: f64) -> Self {
+		self.0.score = Some(Score {
+			arithmetic_geometric_mean: mean,
+			arithmetic_mean: mean,
+			median: mean,
+		});
+		self
+	}
+
+	pub(crate) fn kind(mut self, kind: AnimeKind) -> Self {
+		self.0.kind = kind;
+		self
+	}
+
+	pub(crate) fn status(mut self, status: AnimeStatus) -> Self {
+		self.0.status = status;
+		self
+	}
+
+	pub(crate) fn episodes(mut self, episodes: u32) -> Self {
+		self.0.episodes = episodes.into();
+		self
+	}
+
+	pub(crate) fn year(mut self, year: u32) -> Self {
+		self.0.anime_season.get_or_insert_with(Default::default).year =
+			Some(year);
+		self
+	}
+
+	pub(crate) fn season(mut self, season: Season) -> Self {
+		self.0.anime_season.get_or_insert_with(Default::default).season =
+			season;
+		self
+	}
+
+	pub(crate) fn synonym(mut self, synonym: &str) -> Self {
+		self.0.synonyms.push(synonym.into());
+		self
+	}
+
+	pub(crate) fn tag(mut self, tag: &str) -> Self {
+		self.0.tags.push(tag.into());
+		self
+	}
+
+	pub(crate) fn studio(mut self, name: &str) -> Self {
+		self.0.studios.get_or_insert_with(Vec::new).push(name.into());
+		self
+	}
+
+	pub(crate) fn build(self) -> Anime {
+		self.0
+	}
+}
+
+fn arb_url() -> impl Strategy<Value = Url> {
+	("[a-z]{1,12}", 1u32..100_000)
+		.prop_map(|(host, id)| url(&format!("https://{host}.example/{id}")))
+}
+
+fn arb_kind() -> impl Strategy<Value = AnimeKind> {
+	prop_oneof![
+		Just(AnimeKind::Tv),
+		Just(AnimeKind::Movie),
+		Just(AnimeKind::Ova),
+		Just(AnimeKind::Ona),
+		Just(AnimeKind::Special),
+		Just(AnimeKind::Unknown),
+	]
+}
+
+fn arb_status() -> impl Strategy<Value = AnimeStatus> {
+	prop_oneof![
+		Just(AnimeStatus::Finished),
+		Just(AnimeStatus::Ongoing),
+		Just(AnimeStatus::Upcoming),
+		Just(AnimeStatus::Unknown),
+	]
+}
+
+fn arb_season() -> impl Strategy<Value = AnimeSeason> {
+	let season = prop_oneof![
+		Just(Season::Spring),
+		Just(Season::Summer),
+		Just(Season::Fall),
+		Just(Season::Winter),
+		Just(Season::Undefined),
+	];
+
+	(season, proptest::option::of(1900u32..2100))
+		.prop_map(|(season, year)| AnimeSeason { season, year })
+}
+
+fn arb_duration() -> impl Strategy<Value = EpisodeDuration> {
+	let unit = prop_oneof![
+		Just(DurationUnit::Seconds),
+		Just(DurationUnit::Minutes),
+		Just(DurationUnit::Hours),
+	];
+
+	(0u64..10_000, unit)
+		.prop_map(|(value, unit)| EpisodeDuration { value, unit })
+}
+
+fn arb_score() -> impl Strategy<Value = Score> {
+	// Two decimal places, like upstream, so the values survive a trip
+	// through their textual representation unchanged.
+	let mean = (0u32..=1000).prop_map(|n| n as f64 / 100.0);
+
+	(mean.clone(), mean.clone(), mean).prop_map(
+		|(arithmetic_geometric_mean, arithmetic_mean, median)| Score {
+			arithmetic_geometric_mean,
+			arithmetic_mean,
+			median,
+		},
+	)
+}
+
+fn arb_strings() -> impl Strategy<Value = Vec<String>> {
+	vec("\\PC{1,16}", 0..4)
+}
+
+fn arb_shared_strings() -> impl Strategy<Value = Vec<Arc<str>>> {
+	arb_strings()
+		.prop_map(|strings| strings.into_iter().map(Arc::from).collect())
+}
+
+pub(crate) fn arb_anime() -> impl Strategy<Value = Anime> {
+	(
+		(
+			vec(arb_url(), 1..4),
+			"\\PC{1,32}",
+			arb_kind(),
+			(0u32..2000).prop_map(EpisodeCount::from),
+			arb_status(),
+			proptest::option::of(arb_season()),
+		),
+		(arb_url(), arb_url(), arb_strings(), vec(arb_url(), 0..4)),
+		(
+			arb_shared_strings(),
+			proptest::option::of(arb_duration()),
+			proptest::option::of(arb_score()),
+			proptest::option::of(arb_shared_strings()),
+			proptest::option::of(arb_shared_strings()),
+		),
+	)
+		.prop_map(
+			|(
+				(sources, title, kind, episodes, status, anime_season),
+				(picture, thumbnail, synonyms, relations),
+				(tags, duration, score, studios, producers),
+			)| Anime {
+				sources,
+				title,
+				kind,
+				episodes,
+				status,
+				anime_season,
+				picture,
+				thumbnail,
+				synonyms,
+				relations,
+				tags,
+				duration,
+				score,
+				studios,
+				producers,
+			},
+		)
+}
+
+pub(crate) fn arb_database() -> impl Strategy<Value = Database> {
+	(vec(arb_anime(), 0..16), 1900u16..2100, 1u8..=12, 1u8..=28).prop_map(
+		|(data, year, month, day)| {
+			let mut db = database(data);
+			db.last_update = SimpleDate { year, month, day };
+			db
+		},
+	)
+}
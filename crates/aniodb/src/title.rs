@@ -0,0 +1,224 @@
+//! Picking the title to display for an audience.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::normalize::normalize;
+use crate::Anime;
+
+/// Which of the titles of an entry to prefer, see
+/// [`Anime::preferred_title`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[repr(u8)]
+pub enum TitleLocale {
+	/// Always the canonical title, which upstream mostly keeps in romaji.
+	#[default]
+	Canonical,
+	/// A title in plain latin letters which doesn't read as romaji.
+	English,
+	/// A title in romaji, e.g. `"Shingeki no Kyojin"`.
+	Romaji,
+	/// A title in Japanese, Chinese or Korean script.
+	Native,
+}
+
+static DEFAULT_LOCALE: AtomicU8 = AtomicU8::new(TitleLocale::Canonical as u8);
+
+impl TitleLocale {
+	const ALL: [Self; 4] =
+		[Self::Canonical, Self::English, Self::Romaji, Self::Native];
+
+	/// Sets the crate-wide locale used by [`Anime::display_title`].
+	pub fn set_default(locale: Self) {
+		DEFAULT_LOCALE.store(locale as u8, Ordering::Relaxed);
+	}
+
+	/// The locale set with [`TitleLocale::set_default`],
+	/// [`TitleLocale::Canonical`] if none was set.
+	pub fn current() -> Self {
+		let locale = DEFAULT_LOCALE.load(Ordering::Relaxed);
+		Self::ALL[usize::from(locale)]
+	}
+
+	/// Guesses which locale `title` is written for from the scripts it uses.
+	///
+	/// Never returns [`TitleLocale::Canonical`].
+	pub fn detect(title: &str) -> Self {
+		if title.chars().any(is_native) {
+			return Self::Native;
+		}
+
+		let normalized = normalize(title);
+		let mut words = normalized
+			.split(' ')
+			.filter(|word| !word.chars().all(|c| c.is_ascii_digit()))
+			.peekable();
+
+		if words.peek().is_some() && words.all(is_romaji) {
+			Self::Romaji
+		} else {
+			Self::English
+		}
+	}
+}
+
+/// Kana, CJK ideographs and hangul.
+fn is_native(c: char) -> bool {
+	matches!(
+		c,
+		'\u{3040}'..='\u{30ff}'
+			| '\u{3400}'..='\u{4dbf}'
+			| '\u{4e00}'..='\u{9fff}'
+			| '\u{ac00}'..='\u{d7af}'
+			| '\u{ff66}'..='\u{ff9f}'
+	)
+}
+
+/// Consonants a Hepburn syllable can start with, longest first.
+const ONSETS: [&str; 28] = [
+	"ch", "sh", "ts", "ky", "gy", "ny", "hy", "by", "py", "my", "ry", "jy",
+	"k", "g", "s", "z", "t", "d", "n", "h", "b", "p", "m", "y", "r", "w", "f",
+	"j",
+];
+
+fn is_vowel(c: u8) -> bool {
+	matches!(c, b'a' | b'e' | b'i' | b'o' | b'u')
+}
+
+/// Whether `word` splits into Hepburn syllables, e.g. `"kyojin"` but not
+/// `"attack"`.
+fn is_romaji(word: &str) -> bool {
+	let word = word.as_bytes();
+	let mut rest = word;
+
+	while let [c, tail @ ..] = rest {
+		let next = tail.first().copied();
+
+		rest = if is_vowel(*c) {
+			tail
+		} else if *c == b'n' && !next.is_some_and(|n| is_vowel(n) || n == b'y')
+		{
+			// Syllabic `n`, e.g. `shinbun`.
+			tail
+		} else if next == Some(*c) || (*c == b't' && next == Some(b'c')) {
+			// Doubled consonants, e.g. `gakkou` or `matcha`.
+			tail
+		} else {
+			let Some(onset) =
+				ONSETS.iter().find(|onset| rest.starts_with(onset.as_bytes()))
+			else {
+				return false;
+			};
+
+			match &rest[onset.len()..] {
+				[v, tail @ ..] if is_vowel(*v) => tail,
+				_ => return false,
+			}
+		};
+	}
+
+	!word.is_empty()
+}
+
+/// Words telling English titles apart from other languages in latin script.
+const ENGLISH_WORDS: [&str; 14] = [
+	"an", "and", "at", "for", "from", "in", "is", "my", "of", "on", "the",
+	"to", "with", "your",
+];
+
+fn english_words(title: &str) -> usize {
+	normalize(title)
+		.split(' ')
+		.filter(|word| ENGLISH_WORDS.contains(word))
+		.count()
+}
+
+impl Anime {
+	/// Returns the first of the canonical title and the synonyms that
+	/// [looks like](TitleLocale::detect) it is written for `locale`, or the
+	/// canonical title if none does.
+	///
+	/// Upstream mixes synonyms of many languages, so English titles have to
+	/// be plain ASCII and the one with the most common English words (e.g.
+	/// `of` or `the`) wins.
+	pub fn preferred_title(&self, locale: TitleLocale) -> &str {
+		if locale == TitleLocale::Canonical {
+			return &self.title;
+		}
+
+		let mut candidates = std::iter::once(&self.title)
+			.chain(&self.synonyms)
+			.filter(|title| TitleLocale::detect(title) == locale);
+
+		let preferred = if locale == TitleLocale::English {
+			candidates
+				.filter(|title| title.is_ascii())
+				.map(|title| (english_words(title), title))
+				// The first one on ties.
+				.reduce(
+					|best, other| if other.0 > best.0 { other } else { best },
+				)
+				.map(|(_, title)| title)
+		} else {
+			candidates.next()
+		};
+
+		preferred.unwrap_or(&self.title)
+	}
+
+	/// [`Anime::preferred_title`] in the locale set with
+	/// [`TitleLocale::set_default`].
+	pub fn display_title(&self) -> &str {
+		self.preferred_title(TitleLocale::current())
+	}
+}
+
+#[test]
+fn title_detect() {
+	let detect = |title: &str| TitleLocale::detect(title);
+
+	assert_eq!(detect("Shingeki no Kyojin"), TitleLocale::Romaji);
+	assert_eq!(detect("Kimi no Na wa."), TitleLocale::Romaji);
+	assert_eq!(detect("Shōjo Kakumei Utena"), TitleLocale::Romaji);
+	assert_eq!(detect("Gakkou Gurashi!"), TitleLocale::Romaji);
+	assert_eq!(detect("Mobile Suit Gundam 00"), TitleLocale::English);
+	assert_eq!(detect("Attack on Titan"), TitleLocale::English);
+	assert_eq!(detect("Your Name."), TitleLocale::English);
+	assert_eq!(detect("進撃の巨人"), TitleLocale::Native);
+	assert_eq!(detect("しんげき"), TitleLocale::Native);
+	assert_eq!(detect("!!"), TitleLocale::English);
+}
+
+#[test]
+fn anime_preferred_title() {
+	use crate::testing::AnimeBuilder;
+
+	let anime = AnimeBuilder::new("Shingeki no Kyojin")
+		.synonym("進撃の巨人")
+		.synonym("Ataque a los Titanes")
+		.synonym("L’Attaque des Titans")
+		.synonym("Attack on Titan")
+		.build();
+
+	assert_eq!(
+		anime.preferred_title(TitleLocale::Canonical),
+		"Shingeki no Kyojin"
+	);
+	assert_eq!(anime.preferred_title(TitleLocale::Native), "進撃の巨人");
+	assert_eq!(anime.preferred_title(TitleLocale::English), "Attack on Titan");
+	assert_eq!(
+		anime.preferred_title(TitleLocale::Romaji),
+		"Shingeki no Kyojin"
+	);
+
+	let english = AnimeBuilder::new("Attack on Titan").build();
+	assert_eq!(
+		english.preferred_title(TitleLocale::Romaji),
+		"Attack on Titan"
+	);
+
+	assert_eq!(TitleLocale::current(), TitleLocale::Canonical);
+	assert_eq!(anime.display_title(), "Shingeki no Kyojin");
+	TitleLocale::set_default(TitleLocale::Native);
+	assert_eq!(anime.display_title(), "進撃の巨人");
+	TitleLocale::set_default(TitleLocale::Canonical);
+}
@@ -0,0 +1,213 @@
+//! Consistency checks over the whole dataset.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use url::Url;
+
+use crate::{Anime, AnimeSeason, AnimeStatus, Database, Season};
+
+/// Years before this are considered impossible.
+const FIRST_YEAR: u32 = 1900;
+
+/// How many years after the last update announced entries may air in.
+const MAX_YEARS_AHEAD: u32 = 10;
+
+/// A single problem found by [`Database::validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue<'a> {
+	/// The same source is listed by more than one entry.
+	DuplicateSource { source: &'a Url, entries: Vec<&'a Anime> },
+	/// A relation which isn't the source of any entry.
+	DanglingRelation { anime: &'a Anime, relation: &'a Url },
+	/// The entry only has upstream's placeholder picture.
+	MissingPicture { anime: &'a Anime },
+	/// A season without a year, a year outside of what is plausible or a
+	/// finished entry airing after the last update.
+	ImpossibleSeason { anime: &'a Anime, season: AnimeSeason },
+}
+
+impl fmt::Display for Issue<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::DuplicateSource { source, entries } => {
+				write!(f, "{source} is listed by")?;
+				for (idx, anime) in entries.iter().enumerate() {
+					let separator = if idx == 0 { " " } else { ", " };
+					write!(f, "{separator}`{}`", anime.title)?;
+				}
+				Ok(())
+			}
+			Self::DanglingRelation { anime, relation } => write!(
+				f,
+				"`{}` relates to {relation} which is not in the dataset",
+				anime.title
+			),
+			Self::MissingPicture { anime } => {
+				write!(f, "`{}` has no picture", anime.title)
+			}
+			Self::ImpossibleSeason { anime, season } => {
+				write!(
+					f,
+					"`{}` has an impossible season {season:?}",
+					anime.title
+				)
+			}
+		}
+	}
+}
+
+/// Issues found by [`Database::validate`].
+///
+/// Duplicate sources come first, followed by the issues of single entries in
+/// database order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationReport<'a> {
+	issues: Vec<Issue<'a>>,
+}
+
+impl<'a> ValidationReport<'a> {
+	pub fn issues(&self) -> &[Issue<'a>] {
+		&self.issues
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.issues.is_empty()
+	}
+
+	pub fn len(&self) -> usize {
+		self.issues.len()
+	}
+}
+
+/// One issue per line.
+impl fmt::Display for ValidationReport<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for issue in &self.issues {
+			writeln!(f, "{issue}")?;
+		}
+		Ok(())
+	}
+}
+
+/// Whether `url` is one of upstream's `no_pic` placeholders.
+fn is_placeholder(url: &Url) -> bool {
+	url.path_segments()
+		.and_then(|mut segments| segments.next_back())
+		.is_some_and(|name| name.starts_with("no_pic"))
+}
+
+impl Database {
+	/// Checks the dataset for malformed entries.
+	///
+	/// Meant for detecting upstream regressions, e.g. after every fetch. A
+	/// report with issues doesn't keep the database from being used.
+	pub fn validate(&self) -> ValidationReport<'_> {
+		let last_year = u32::from(self.last_update.year);
+		let years = FIRST_YEAR..=last_year + MAX_YEARS_AHEAD;
+
+		// Positions of the entries listing each source.
+		let mut sources: HashMap<&Url, Vec<usize>> = HashMap::new();
+		for (idx, anime) in self.data.iter().enumerate() {
+			for source in &anime.sources {
+				let entries = sources.entry(source).or_default();
+				if entries.last() != Some(&idx) {
+					entries.push(idx);
+				}
+			}
+		}
+
+		let mut duplicates: Vec<_> =
+			sources.iter().filter(|(_, entries)| entries.len() > 1).collect();
+		duplicates.sort_by_key(|&(source, entries)| (entries[0], *source));
+
+		let mut issues: Vec<_> = duplicates
+			.into_iter()
+			.map(|(&source, entries)| Issue::DuplicateSource {
+				source,
+				entries: entries.iter().map(|&idx| &self.data[idx]).collect(),
+			})
+			.collect();
+
+		for anime in self.data.iter() {
+			for relation in &anime.relations {
+				if !sources.contains_key(relation) {
+					issues.push(Issue::DanglingRelation { anime, relation });
+				}
+			}
+
+			if is_placeholder(&anime.picture) {
+				issues.push(Issue::MissingPicture { anime });
+			}
+
+			let season = anime.anime_season();
+			let impossible = match season.year {
+				None => season.season != Season::Undefined,
+				Some(year) => {
+					!years.contains(&year)
+						|| (anime.status == AnimeStatus::Finished
+							&& year > last_year)
+				}
+			};
+			if impossible {
+				issues.push(Issue::ImpossibleSeason { anime, season });
+			}
+		}
+
+		ValidationReport { issues }
+	}
+}
+
+#[test]
+fn db_validate() {
+	use crate::testing::{database, url, AnimeBuilder};
+
+	let mut duplicate = AnimeBuilder::new("Duplicate").year(2000).build();
+	duplicate.sources.push(url("https://example.org/anime/Original"));
+
+	let mut related = AnimeBuilder::new("Related").year(2000).build();
+	related.relations.push(url("https://example.org/anime/Original"));
+	related.relations.push(url("https://example.org/anime/Gone"));
+
+	let mut no_picture = AnimeBuilder::new("No Picture").year(2000).build();
+	no_picture.picture = url(
+		"https://raw.githubusercontent.com/manami-project/\
+		 anime-offline-database/master/pics/no_pic.png",
+	);
+
+	let db = database(vec![
+		AnimeBuilder::new("Original").year(2000).build(),
+		duplicate,
+		related,
+		no_picture,
+		AnimeBuilder::new("No Year").build(),
+		AnimeBuilder::new("Far Future").year(2100).build(),
+		AnimeBuilder::new("Finished Early").year(2023).build(),
+		AnimeBuilder::new("Upcoming")
+			.year(2023)
+			.status(AnimeStatus::Upcoming)
+			.build(),
+	]);
+
+	let report = db.validate();
+	let lines: Vec<_> = report.to_string().lines().map(String::from).collect();
+
+	assert_eq!(
+		lines,
+		[
+			"https://example.org/anime/Original is listed by `Original`, \
+			 `Duplicate`",
+			"`Related` relates to https://example.org/anime/Gone which is \
+			 not in the dataset",
+			"`No Picture` has no picture",
+			"`No Year` has an impossible season AnimeSeason { season: \
+			 Spring, year: None }",
+			"`Far Future` has an impossible season AnimeSeason { season: \
+			 Spring, year: Some(2100) }",
+			"`Finished Early` has an impossible season AnimeSeason { season: \
+			 Spring, year: Some(2023) }",
+		]
+	);
+	assert_eq!(report.len(), 6);
+	assert!(database(Vec::new()).validate().is_empty());
+}
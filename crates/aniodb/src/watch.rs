@@ -0,0 +1,313 @@
+//! Polling upstream for new versions of the database.
+
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+use crate::fetch::{Error, FetchOptions, Release};
+use crate::fetch_shared::{
+	release_url, LATEST_COMMIT_URL, LATEST_RELEASE_URL,
+};
+use crate::{Database, RefreshTask};
+
+/// What counts as a new version of the dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WatchTarget {
+	/// Every commit to the upstream repository, identified by its hash.
+	#[default]
+	Commit,
+	/// Tagged releases only, identified by their tag.
+	Release,
+}
+
+impl WatchTarget {
+	fn url(self) -> &'static str {
+		match self {
+			Self::Commit => LATEST_COMMIT_URL,
+			Self::Release => LATEST_RELEASE_URL,
+		}
+	}
+}
+
+/// A new version found by [`DatabaseWatcher`].
+#[derive(Debug)]
+pub struct Update {
+	revision: String,
+	database: Option<Database>,
+}
+
+impl Update {
+	/// The commit hash or release tag of the new version.
+	pub fn revision(&self) -> &str {
+		&self.revision
+	}
+
+	/// The database of [`Update::revision`], `None` unless
+	/// [`DatabaseWatcher::download`] is enabled.
+	pub fn database(&self) -> Option<&Database> {
+		self.database.as_ref()
+	}
+
+	pub fn into_database(self) -> Option<Database> {
+		self.database
+	}
+}
+
+/// Polls the upstream repository for new versions of the dataset.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use aniodb::{DatabaseWatcher, WatchTarget};
+///
+/// let (task, updates) = DatabaseWatcher::new(WatchTarget::Release)
+///     .interval(Duration::from_secs(6 * 60 * 60))
+///     .download(true)
+///     .channel();
+///
+/// for update in updates {
+///     println!("release {} is out", update.revision());
+/// }
+/// # task.stop();
+/// ```
+#[derive(Debug, Clone)]
+pub struct DatabaseWatcher {
+	target: WatchTarget,
+	url: String,
+	revision_url: String,
+	interval: Duration,
+	options: FetchOptions,
+	download: bool,
+	known: Option<String>,
+}
+
+impl DatabaseWatcher {
+	/// Polls `target` once an hour, without downloading new versions.
+	pub fn new(target: WatchTarget) -> Self {
+		Self {
+			target,
+			url: target.url().to_owned(),
+			revision_url: release_url("{revision}"),
+			interval: Duration::from_secs(60 * 60),
+			options: FetchOptions::new(),
+			download: false,
+			known: None,
+		}
+	}
+
+	/// Replaces the GitHub API url polled for the newest revision.
+	pub fn url(mut self, url: impl Into<String>) -> Self {
+		self.url = url.into();
+		self
+	}
+
+	/// Replaces the url new versions are downloaded from, with `{revision}`
+	/// standing for the commit hash or release tag.
+	pub fn revision_url(mut self, url: impl Into<String>) -> Self {
+		self.revision_url = url.into();
+		self
+	}
+
+	/// Time between polls of a spawned watcher.
+	pub fn interval(mut self, interval: Duration) -> Self {
+		self.interval = interval;
+		self
+	}
+
+	/// Options used for polling and downloading.
+	pub fn options(mut self, options: FetchOptions) -> Self {
+		self.options = options;
+		self
+	}
+
+	/// Whether to download and parse new versions before reporting them.
+	///
+	/// The database is downloaded from the
+	/// [`DatabaseWatcher::revision_url`] of the polled revision, so it
+	/// belongs to that revision even if upstream moved on since. The urls of
+	/// the [`FetchOptions`] aren't used.
+	pub fn download(mut self, download: bool) -> Self {
+		self.download = download;
+		self
+	}
+
+	/// Revision of the dataset already in use.
+	///
+	/// Without one the first poll only records the current revision.
+	pub fn known(mut self, revision: impl Into<String>) -> Self {
+		self.known = Some(revision.into());
+		self
+	}
+
+	/// The most recent revision seen.
+	pub fn revision(&self) -> Option<&str> {
+		self.known.as_deref()
+	}
+
+	/// Asks upstream for its newest revision.
+	pub fn latest(&self) -> Result<String, Error> {
+		let agent = self.options.agent()?;
+
+		let revision = match self.target {
+			WatchTarget::Commit => agent
+				.get(&self.url)
+				.set("Accept", "application/vnd.github.sha")
+				.call()?
+				.into_string()?
+				.trim()
+				.to_owned(),
+			WatchTarget::Release => {
+				let reader = agent
+					.get(&self.url)
+					.set("Accept", "application/vnd.github+json")
+					.call()?
+					.into_reader();
+				let release: Release = serde_json::from_reader(reader)?;
+				release.tag().to_owned()
+			}
+		};
+
+		if revision.is_empty() {
+			return Err(Error::Corrupt("empty revision".to_owned()));
+		}
+
+		Ok(revision)
+	}
+
+	/// Polls upstream once, returning the new version if there is one.
+	///
+	/// If downloading fails the revision isn't recorded, so the next poll
+	/// tries again.
+	pub fn check(&mut self) -> Result<Option<Update>, Error> {
+		let revision = self.latest()?;
+
+		match &self.known {
+			Some(known) if *known == revision => return Ok(None),
+			Some(_) => {}
+			None => {
+				self.known = Some(revision);
+				return Ok(None);
+			}
+		}
+
+		let database = if self.download {
+			let url = self.revision_url.replace("{revision}", &revision);
+			Some(self.options.fetch_parsed_from(&url)?)
+		} else {
+			None
+		};
+		self.known = Some(revision.clone());
+
+		Ok(Some(Update { revision, database }))
+	}
+
+	/// Polls upstream every interval on a background thread and calls
+	/// `on_update` with every new version.
+	///
+	/// Failed polls are skipped. The thread stops once the returned
+	/// [`RefreshTask`] is dropped.
+	pub fn spawn(
+		self,
+		mut on_update: impl FnMut(Update) + Send + 'static,
+	) -> RefreshTask {
+		self.run(move |update| {
+			on_update(update);
+			true
+		})
+	}
+
+	/// Like [`DatabaseWatcher::spawn`], sending new versions to the returned
+	/// receiver. Polling also stops once the receiver is dropped.
+	pub fn channel(self) -> (RefreshTask, Receiver<Update>) {
+		let (tx, rx) = mpsc::channel();
+		let task = self.run(move |update| tx.send(update).is_ok());
+
+		(task, rx)
+	}
+
+	fn run(
+		mut self,
+		mut on_update: impl FnMut(Update) -> bool + Send + 'static,
+	) -> RefreshTask {
+		RefreshTask::spawn(self.interval, move || match self.check() {
+			Ok(Some(update)) => on_update(update),
+			_ => true,
+		})
+	}
+}
+
+#[test]
+fn watch_check() {
+	use crate::fetch::{serve, serve_with};
+	use crate::testing::{database, AnimeBuilder};
+
+	let (url, server) = serve(vec![
+		(200, b"aaa\n".to_vec()),
+		(200, b"aaa\n".to_vec()),
+		(200, b"bbb\n".to_vec()),
+		(500, Vec::new()),
+	]);
+
+	let mut watcher = DatabaseWatcher::new(WatchTarget::Commit)
+		.url(url)
+		.options(FetchOptions::new().no_proxy());
+
+	assert!(watcher.check().unwrap().is_none());
+	assert_eq!(watcher.revision(), Some("aaa"));
+	assert!(watcher.check().unwrap().is_none());
+
+	let update = watcher.check().unwrap().unwrap();
+	assert_eq!(update.revision(), "bbb");
+	assert!(update.database().is_none());
+	assert!(watcher.check().is_err());
+	assert_eq!(watcher.revision(), Some("bbb"));
+	server.join().unwrap();
+
+	// Commits are downloaded by their hash, not from the branch.
+	let db = database(vec![AnimeBuilder::new("Committed").build()]);
+	let (url, server) = serve(vec![(200, b"ccc\n".to_vec())]);
+	let (db_url, db_server) = serve_with(
+		vec![(200, serde_json::to_vec(&db).unwrap())],
+		"GET /ccc/db.json ",
+	);
+
+	let mut watcher = DatabaseWatcher::new(WatchTarget::Commit)
+		.url(url)
+		.revision_url(db_url.replace("db.json", "{revision}/db.json"))
+		.options(FetchOptions::new().no_proxy())
+		.known("bbb")
+		.download(true);
+
+	let update = watcher.check().unwrap().unwrap();
+	assert_eq!(update.revision(), "ccc");
+	assert_eq!(update.into_database(), Some(db));
+	server.join().unwrap();
+	db_server.join().unwrap();
+
+	let release = br#"{"tag_name": "2023-02", "name": null}"#.to_vec();
+	let db = database(vec![AnimeBuilder::new("Released").build()]);
+	let (url, server) = serve(vec![(200, release)]);
+	let (db_url, db_server) = serve_with(
+		vec![(200, serde_json::to_vec(&db).unwrap())],
+		"GET /2023-02/db.json ",
+	);
+
+	// The download of the master branch isn't requested.
+	let (task, updates) = DatabaseWatcher::new(WatchTarget::Release)
+		.url(url)
+		.revision_url(db_url.replace("db.json", "{revision}/db.json"))
+		.options(
+			FetchOptions::new().url("http://127.0.0.1:1/db.json").no_proxy(),
+		)
+		.known("2023-01")
+		.interval(Duration::from_millis(5))
+		.download(true)
+		.channel();
+
+	let update = updates.recv_timeout(Duration::from_secs(5)).unwrap();
+	assert_eq!(update.revision(), "2023-02");
+	assert_eq!(update.into_database(), Some(db));
+	task.stop();
+
+	server.join().unwrap();
+	db_server.join().unwrap();
+}
@@ -0,0 +1,41 @@
+//! Downloading the database from within the browser, for builds targeting
+//! `wasm32-unknown-unknown` where the `fetch` feature isn't available.
+
+use gloo_net::http::Request;
+
+use crate::Database;
+
+/// Unlike `github.com/.../raw/...`, which redirects without CORS headers,
+/// this is readable from other origins.
+const DATABASE_URL: &str = "https://raw.githubusercontent.com/manami-project/anime-offline-database/master/anime-offline-database-minified.json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("Request failed: `{0}`")]
+	RequestError(#[from] gloo_net::Error),
+	#[error("Unexpected response status: `{0}`")]
+	StatusError(u16),
+	#[error("Io operation failed: `{0}`")]
+	IoError(#[from] std::io::Error),
+}
+
+impl Database {
+	/// Downloads and parses the newest version of the database using the
+	/// browser's `fetch()`.
+	pub async fn fetch_web() -> Result<Self, Error> {
+		Self::fetch_web_url(DATABASE_URL).await
+	}
+
+	/// Like [`Database::fetch_web`], downloading from `url` instead, e.g. a
+	/// copy served from the same origin.
+	pub async fn fetch_web_url(url: &str) -> Result<Self, Error> {
+		let response = Request::get(url).send().await?;
+
+		if !response.ok() {
+			return Err(Error::StatusError(response.status()));
+		}
+
+		let bytes = response.binary().await?;
+		Ok(Self::from_bytes(&bytes)?)
+	}
+}
@@ -0,0 +1,121 @@
+//! Playing animated GIFs and PNGs as text.
+
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::{AnimationDecoder, DynamicImage, Frames, ImageResult};
+
+use crate::{Options, Screen, TextImage};
+
+/// Delay of frames without one, as browsers show them.
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+/// The frames of an animation, each rendered as a [`TextImage`] with the
+/// time it stays on screen.
+pub struct TextAnimation {
+	frames: Vec<(TextImage, Duration)>,
+}
+
+impl TextAnimation {
+	/// Decodes and renders all frames of the GIF read from `reader`.
+	pub fn from_gif(reader: impl Read, opts: Options) -> ImageResult<Self> {
+		Self::from_frames(GifDecoder::new(reader)?.into_frames(), opts)
+	}
+
+	/// Decodes and renders all frames of the APNG read from `reader`. A
+	/// plain PNG becomes a single frame.
+	pub fn from_apng(reader: impl Read, opts: Options) -> ImageResult<Self> {
+		Self::from_frames(PngDecoder::new(reader)?.apng().into_frames(), opts)
+	}
+
+	fn from_frames(frames: Frames<'_>, opts: Options) -> ImageResult<Self> {
+		let frames = frames
+			.map(|frame| {
+				let frame = frame?;
+				let (numer, denom) = frame.delay().numer_denom_ms();
+				let delay = match Duration::from_millis(u64::from(numer))
+					.checked_div(denom)
+				{
+					Some(Duration::ZERO) | None => DEFAULT_DELAY,
+					Some(delay) => delay,
+				};
+
+				let image = DynamicImage::ImageRgba8(frame.into_buffer());
+				Ok((TextImage::from_image(image, opts.clone()), delay))
+			})
+			.collect::<ImageResult<_>>()?;
+
+		Ok(Self { frames })
+	}
+
+	/// The frames with their delays, for running the animation in a custom
+	/// loop.
+	pub fn frames(&self) -> impl Iterator<Item = (&TextImage, Duration)> {
+		self.frames.iter().map(|(image, delay)| (image, *delay))
+	}
+
+	pub fn len(&self) -> usize {
+		self.frames.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.frames.is_empty()
+	}
+
+	/// Plays the animation once at the cursor, drawing only what changed
+	/// from frame to frame with a [`Screen`] and leaving the cursor below
+	/// the last one.
+	pub fn play(&self, mut writer: impl Write) -> io::Result<()> {
+		// Hides the cursor jumping around meanwhile.
+		writer.write_all(b"\x1b[?25l")?;
+
+		let mut screen = Screen::new();
+		for (image, delay) in self.frames() {
+			screen.write_to(image, &mut writer)?;
+
+			writer.flush()?;
+			std::thread::sleep(delay);
+		}
+
+		writer.write_all(b"\x1b[?25h")?;
+		writer.flush()
+	}
+}
+
+#[test]
+fn animation_gif() {
+	use image::codecs::gif::GifEncoder;
+	use image::{Delay, Frame, Rgba, RgbaImage};
+
+	use crate::Fit;
+
+	let mut gif = Vec::new();
+	{
+		let frames = [[255, 0, 0, 255], [0, 0, 255, 255]].map(|color| {
+			Frame::from_parts(
+				RgbaImage::from_pixel(2, 2, Rgba(color)),
+				0,
+				0,
+				Delay::from_numer_denom_ms(20, 1),
+			)
+		});
+		GifEncoder::new(&mut gif).encode_frames(frames).unwrap();
+	}
+
+	let opts = Options::builder().size(2, 1).fit(Fit::Stretch).build();
+	let animation = TextAnimation::from_gif(&gif[..], opts).unwrap();
+	assert_eq!(animation.len(), 2);
+
+	let delays: Vec<_> = animation.frames().map(|(_, delay)| delay).collect();
+	assert_eq!(delays, [Duration::from_millis(20); 2]);
+
+	let mut output = Vec::new();
+	animation.play(&mut output).unwrap();
+	let output = String::from_utf8(output).unwrap();
+	assert!(output.starts_with("\x1b[?25l"));
+	// The second frame changes both cells of the line.
+	assert_eq!(output.matches("\x1b[1A\x1b[1G").count(), 1);
+	assert!(output.ends_with("\x1b[0m\x1b[1B\r\x1b[?25h"));
+}
@@ -0,0 +1,192 @@
+//! Reducing colors to the palettes of terminals without 24-bit colors.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Colors a [`crate::TextImage`] is written with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorDepth {
+	/// 24-bit colors, the exact pixel colors.
+	#[default]
+	TrueColor,
+	/// The 240 colors of the xterm palette which look the same everywhere,
+	/// leaving out the 16 the terminal theme may change.
+	Ansi256,
+	/// The 16 basic ANSI colors, in the xterm defaults.
+	Ansi16,
+}
+
+/// Default xterm colors of the 16 basic ANSI colors.
+const ANSI16: [(u8, u8, u8); 16] = [
+	(0, 0, 0),
+	(205, 0, 0),
+	(0, 205, 0),
+	(205, 205, 0),
+	(0, 0, 238),
+	(205, 0, 205),
+	(0, 205, 205),
+	(229, 229, 229),
+	(127, 127, 127),
+	(255, 0, 0),
+	(0, 255, 0),
+	(255, 255, 0),
+	(92, 92, 255),
+	(255, 0, 255),
+	(0, 255, 255),
+	(255, 255, 255),
+];
+
+/// Levels of the 6 x 6 x 6 color cube of the 256 colors.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Color of the xterm palette entry `idx`.
+fn xterm(idx: u8) -> (u8, u8, u8) {
+	match idx {
+		0..=15 => ANSI16[usize::from(idx)],
+		16..=231 => {
+			let idx = usize::from(idx - 16);
+			(CUBE[idx / 36], CUBE[idx / 6 % 6], CUBE[idx % 6])
+		}
+		232.. => {
+			let gray = 8 + (idx - 232) * 10;
+			(gray, gray, gray)
+		}
+	}
+}
+
+/// A color in the OKLab space, where distances match perceived differences
+/// much better than in sRGB.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Lab([f64; 3]);
+
+impl Lab {
+	fn new((r, g, b): (u8, u8, u8)) -> Self {
+		let linear = |c: u8| {
+			let c = f64::from(c) / 255.0;
+			if c <= 0.04045 {
+				c / 12.92
+			} else {
+				((c + 0.055) / 1.055).powf(2.4)
+			}
+		};
+		let (r, g, b) = (linear(r), linear(g), linear(b));
+
+		let l =
+			(0.412_221_470_8 * r + 0.536_332_536_3 * g + 0.051_445_992_9 * b)
+				.cbrt();
+		let m =
+			(0.211_903_498_2 * r + 0.680_699_545_1 * g + 0.107_396_956_6 * b)
+				.cbrt();
+		let s =
+			(0.088_302_461_9 * r + 0.281_718_837_6 * g + 0.629_978_700_5 * b)
+				.cbrt();
+
+		Self([
+			0.210_454_255_3 * l + 0.793_617_785 * m - 0.004_072_046_8 * s,
+			1.977_998_495_1 * l - 2.428_592_205 * m + 0.450_593_709_9 * s,
+			0.025_904_037_1 * l + 0.782_771_766_2 * m - 0.808_675_766 * s,
+		])
+	}
+
+	fn distance(&self, other: &Self) -> f64 {
+		self.0.iter().zip(other.0).map(|(a, b)| (a - b) * (a - b)).sum()
+	}
+}
+
+impl ColorDepth {
+	/// Palette indices and their colors in OKLab, `None` for
+	/// [`ColorDepth::TrueColor`].
+	fn palette(self) -> Option<&'static [(u8, Lab)]> {
+		static PALETTE256: OnceLock<Vec<(u8, Lab)>> = OnceLock::new();
+		static PALETTE16: OnceLock<Vec<(u8, Lab)>> = OnceLock::new();
+
+		let (palette, indices) = match self {
+			Self::TrueColor => return None,
+			Self::Ansi256 => (&PALETTE256, 16..=255),
+			Self::Ansi16 => (&PALETTE16, 0..=15),
+		};
+		let palette = palette.get_or_init(|| {
+			indices.map(|idx| (idx, Lab::new(xterm(idx)))).collect()
+		});
+
+		Some(palette)
+	}
+
+	/// Index of the palette entry closest to `color`, `None` for
+	/// [`ColorDepth::TrueColor`].
+	pub(crate) fn index(self, color: (u8, u8, u8)) -> Option<u8> {
+		let lab = Lab::new(color);
+
+		self.palette()?
+			.iter()
+			.min_by(|(_, a), (_, b)| {
+				lab.distance(a).total_cmp(&lab.distance(b))
+			})
+			.map(|&(idx, _)| idx)
+	}
+
+	/// Rough distance between neighboring colors of the palette in one
+	/// channel, for dithering.
+	pub(crate) fn step(self) -> f32 {
+		match self {
+			Self::TrueColor => 1.0,
+			Self::Ansi256 => 40.0,
+			Self::Ansi16 => 128.0,
+		}
+	}
+
+	/// `color` reduced to the closest color of the palette.
+	pub(crate) fn quantize(self, color: (u8, u8, u8)) -> (u8, u8, u8) {
+		self.index(color).map_or(color, xterm)
+	}
+
+	/// Writes the SGR parameters setting the foreground or background to
+	/// `color`, or the palette entry closest to it, e.g. `38;5;196`.
+	pub(crate) fn push_sgr(
+		self,
+		buffer: &mut impl fmt::Write,
+		(r, g, b): (u8, u8, u8),
+		background: bool,
+	) -> fmt::Result {
+		let Some(idx) = self.index((r, g, b)) else {
+			let code = if background { 48 } else { 38 };
+			return write!(buffer, "{code};2;{r};{g};{b}");
+		};
+
+		match (self, background) {
+			(Self::Ansi16, false) if idx < 8 => write!(buffer, "{}", 30 + idx),
+			(Self::Ansi16, false) => write!(buffer, "{}", 90 + idx - 8),
+			(Self::Ansi16, true) if idx < 8 => write!(buffer, "{}", 40 + idx),
+			(Self::Ansi16, true) => write!(buffer, "{}", 100 + idx - 8),
+			(_, false) => write!(buffer, "38;5;{idx}"),
+			(_, true) => write!(buffer, "48;5;{idx}"),
+		}
+	}
+}
+
+#[test]
+fn color_palette() {
+	assert_eq!(xterm(16), (0, 0, 0));
+	assert_eq!(xterm(196), (255, 0, 0));
+	assert_eq!(xterm(231), (255, 255, 255));
+	assert_eq!(xterm(244), (128, 128, 128));
+
+	assert_eq!(ColorDepth::TrueColor.index((1, 2, 3)), None);
+	assert_eq!(ColorDepth::TrueColor.quantize((1, 2, 3)), (1, 2, 3));
+	assert_eq!(ColorDepth::Ansi256.index((250, 5, 5)), Some(196));
+	assert_eq!(ColorDepth::Ansi256.index((130, 130, 130)), Some(244));
+	assert_eq!(ColorDepth::Ansi256.quantize((0, 0, 0)), (0, 0, 0));
+	assert_eq!(ColorDepth::Ansi16.index((250, 5, 5)), Some(9));
+	assert_eq!(ColorDepth::Ansi16.index((10, 10, 10)), Some(0));
+
+	let sgr = |depth: ColorDepth, color, background| {
+		let mut buffer = String::new();
+		depth.push_sgr(&mut buffer, color, background).unwrap();
+		buffer
+	};
+	assert_eq!(sgr(ColorDepth::Ansi256, (255, 0, 0), false), "38;5;196");
+	assert_eq!(sgr(ColorDepth::Ansi256, (255, 0, 0), true), "48;5;196");
+	assert_eq!(sgr(ColorDepth::Ansi16, (205, 0, 0), false), "31");
+	assert_eq!(sgr(ColorDepth::Ansi16, (255, 255, 255), true), "107");
+	assert_eq!(sgr(ColorDepth::TrueColor, (255, 0, 0), true), "48;2;255;0;0");
+}
@@ -0,0 +1,418 @@
+//! Guessing what the attached terminal can show.
+
+use std::path::PathBuf;
+
+/// Ways to show an image on a terminal, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Backend {
+	/// The kitty graphics protocol.
+	Kitty,
+	/// Sixel graphics.
+	Sixel,
+	/// The inline images of iTerm2.
+	ITerm,
+	/// Characters in 24-bit colors.
+	TrueColor,
+	/// Characters in the 256 colors of xterm.
+	Colors256,
+	/// Plain characters.
+	Ascii,
+}
+
+impl Backend {
+	/// Parses the names accepted by [`OVERRIDE`].
+	fn from_name(name: &str) -> Option<Self> {
+		match name.trim().to_ascii_lowercase().as_str() {
+			"kitty" => Some(Self::Kitty),
+			"sixel" => Some(Self::Sixel),
+			"iterm" => Some(Self::ITerm),
+			"truecolor" => Some(Self::TrueColor),
+			"256color" => Some(Self::Colors256),
+			"ascii" => Some(Self::Ascii),
+			_ => None,
+		}
+	}
+}
+
+/// Environment variable forcing the backend picked by [`detect`], one of
+/// `kitty`, `sixel`, `iterm`, `truecolor`, `256color` or `ascii`.
+pub const OVERRIDE: &str = "TXTIMG_BACKEND";
+
+/// How long to wait for the terminal to answer queries.
+#[cfg(unix)]
+const TIMEOUT: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Picks the best backend the attached terminal supports.
+///
+/// Honors [`OVERRIDE`] first. Otherwise terminals known by `$TERM`,
+/// `$TERM_PROGRAM` or their own variables are trusted, then the terminal is
+/// asked whether it supports kitty graphics and for its device attributes,
+/// which list sixel support. Colors come from `$COLORTERM` and the terminfo
+/// entry of `$TERM`.
+///
+/// Querying waits up to 100ms for an answer, and reads from the terminal in
+/// the meantime, so don't call this while something else reads input.
+pub fn detect() -> Backend {
+	let var = |name: &str| std::env::var(name).ok();
+
+	if let Some(backend) =
+		var(OVERRIDE).as_deref().and_then(Backend::from_name)
+	{
+		return backend;
+	}
+	if let Some(backend) = from_env(var) {
+		return backend;
+	}
+	if let Some(backend) =
+		query(GRAPHICS_QUERY).as_deref().and_then(from_response)
+	{
+		return backend;
+	}
+
+	colors(var, |term| terminfo(term).as_deref().and_then(max_colors))
+}
+
+/// Whether the terminal shows a light background, `None` if unknown.
+///
+/// Asks the terminal for its background color, which most terminals besides
+/// the Linux console answer, and falls back to `$COLORFGBG` set by rxvt and
+/// Konsole. Querying blocks like [`detect`].
+pub fn light_background() -> Option<bool> {
+	query(BACKGROUND_QUERY)
+		.as_deref()
+		.and_then(from_background)
+		.or_else(|| from_colorfgbg(&std::env::var("COLORFGBG").ok()?))
+}
+
+/// Asks for kitty graphics support with a tiny image.
+const GRAPHICS_QUERY: &[u8] = b"\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\";
+
+/// Asks for the background color, `OSC 11`.
+const BACKGROUND_QUERY: &[u8] = b"\x1b]11;?\x1b\\";
+
+/// Whether the color in the answer to [`BACKGROUND_QUERY`] is light, e.g.
+/// `ESC ] 11 ; rgb:ffff/ffff/dddd ESC \`.
+fn from_background(response: &[u8]) -> Option<bool> {
+	let prefix = b"\x1b]11;rgb:";
+	let start = find(response, prefix)? + prefix.len();
+	let len = response[start..]
+		.iter()
+		.position(|&b| b == b'\x1b' || b == b'\x07')?;
+	let color = std::str::from_utf8(&response[start..start + len]).ok()?;
+
+	// Channels have 1 to 4 hex digits.
+	let mut channels = color.split('/').map(|channel| {
+		let value = u32::from_str_radix(channel, 16).ok()?;
+		let max = 16u32.checked_pow(channel.len() as u32)? - 1;
+		Some(value as f32 / max as f32)
+	});
+	let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+
+	Some(0.299 * r + 0.587 * g + 0.114 * b > 0.5)
+}
+
+/// Whether the background in `$COLORFGBG`, like `15;0`, is light. The last
+/// field is the ANSI color of the background.
+fn from_colorfgbg(value: &str) -> Option<bool> {
+	let background: u8 = value.rsplit(';').next()?.trim().parse().ok()?;
+
+	match background {
+		7 | 9..=15 => Some(true),
+		0..=6 | 8 => Some(false),
+		_ => None,
+	}
+}
+
+/// Terminals which are recognized by their environment alone.
+fn from_env(var: impl Fn(&str) -> Option<String>) -> Option<Backend> {
+	let term = var("TERM").unwrap_or_default();
+
+	if term == "xterm-kitty" || var("KITTY_WINDOW_ID").is_some() {
+		return Some(Backend::Kitty);
+	}
+	match var("TERM_PROGRAM").as_deref() {
+		Some("WezTerm") => return Some(Backend::Kitty),
+		Some("iTerm.app") => return Some(Backend::ITerm),
+		_ => {}
+	}
+	if term.starts_with("foot") || term.starts_with("mlterm") {
+		return Some(Backend::Sixel);
+	}
+
+	None
+}
+
+/// The graphics backend announced in the answers to [`query`].
+fn from_response(response: &[u8]) -> Option<Backend> {
+	if contains(response, b"\x1b_Gi=31;OK") {
+		return Some(Backend::Kitty);
+	}
+
+	// Primary device attributes, `ESC [ ? 62 ; 4 ; 22 c`, where `4` means
+	// sixel.
+	let start = find(response, b"\x1b[?")? + 3;
+	let len = response[start..].iter().position(|&b| b == b'c')?;
+	let sixel = response[start..start + len]
+		.split(|&b| b == b';')
+		.any(|attribute| attribute == b"4");
+
+	sixel.then_some(Backend::Sixel)
+}
+
+/// The color backend, by `$COLORTERM` and the number of colors `max_colors`
+/// finds for `$TERM`.
+fn colors(
+	var: impl Fn(&str) -> Option<String>,
+	max_colors: impl Fn(&str) -> Option<u32>,
+) -> Backend {
+	if matches!(var("COLORTERM").as_deref(), Some("truecolor" | "24bit")) {
+		return Backend::TrueColor;
+	}
+
+	let term = var("TERM").unwrap_or_default();
+	if term.is_empty() || term == "dumb" {
+		return Backend::Ascii;
+	}
+
+	match max_colors(&term) {
+		Some(0x100_0000..) => Backend::TrueColor,
+		Some(256..) => Backend::Colors256,
+		Some(_) => Backend::Ascii,
+		None if term.ends_with("256color") => Backend::Colors256,
+		None => Backend::Ascii,
+	}
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+	haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+	find(haystack, needle).is_some()
+}
+
+/// Sends `request` and asks for the primary device attributes, returning
+/// everything the terminal answered.
+///
+/// Every terminal answers the device attributes, and does so after the
+/// queries before, so the answer is complete once they arrived.
+#[cfg(unix)]
+fn query(request: &[u8]) -> Option<Vec<u8>> {
+	use std::fs::OpenOptions;
+	use std::mem::MaybeUninit;
+	use std::os::fd::AsRawFd;
+
+	let mut tty =
+		OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+	let fd = tty.as_raw_fd();
+
+	let mut saved = MaybeUninit::uninit();
+	// SAFETY: `fd` is open and `saved` is only read after it was filled.
+	if unsafe { libc::tcgetattr(fd, saved.as_mut_ptr()) } != 0 {
+		return None;
+	}
+	let saved = unsafe { saved.assume_init() };
+	let mut raw = saved;
+	// SAFETY: `raw` is a valid termios.
+	unsafe { libc::cfmakeraw(&mut raw) };
+	if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+		return None;
+	}
+
+	let response = exchange(&mut tty, request);
+
+	// SAFETY: `saved` holds the settings read above.
+	unsafe { libc::tcsetattr(fd, libc::TCSANOW, &saved) };
+
+	response
+}
+
+/// Sends `request` and the device attributes query to the raw `tty` and
+/// reads until the device attributes arrived.
+#[cfg(unix)]
+fn exchange(tty: &mut std::fs::File, request: &[u8]) -> Option<Vec<u8>> {
+	use std::io::{Read, Write};
+	use std::os::fd::AsRawFd;
+	use std::time::Instant;
+
+	tty.write_all(request).ok()?;
+	tty.write_all(b"\x1b[c").ok()?;
+	tty.flush().ok()?;
+
+	let deadline = Instant::now() + TIMEOUT;
+	let mut response = Vec::new();
+	while !find(&response, b"\x1b[?")
+		.is_some_and(|start| response[start..].contains(&b'c'))
+	{
+		let left = deadline.checked_duration_since(Instant::now())?;
+		let mut poll = libc::pollfd {
+			fd: tty.as_raw_fd(),
+			events: libc::POLLIN,
+			revents: 0,
+		};
+		// SAFETY: `poll` is a single valid pollfd.
+		let ready = unsafe { libc::poll(&mut poll, 1, left.as_millis() as _) };
+		if ready <= 0 {
+			return None;
+		}
+
+		let mut buffer = [0; 64];
+		let len = tty.read(&mut buffer).ok().filter(|&len| len > 0)?;
+		response.extend_from_slice(&buffer[..len]);
+	}
+
+	Some(response)
+}
+
+#[cfg(not(unix))]
+fn query(_request: &[u8]) -> Option<Vec<u8>> {
+	None
+}
+
+/// The compiled terminfo entry of `term`.
+fn terminfo(term: &str) -> Option<Vec<u8>> {
+	let first = term.chars().next()?;
+	let var = |name: &str| std::env::var_os(name).map(PathBuf::from);
+
+	let mut dirs: Vec<PathBuf> = Vec::new();
+	dirs.extend(var("TERMINFO"));
+	dirs.extend(var("HOME").map(|home| home.join(".terminfo")));
+	if let Some(list) = std::env::var_os("TERMINFO_DIRS") {
+		dirs.extend(std::env::split_paths(&list));
+	}
+	dirs.extend(
+		["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"]
+			.map(PathBuf::from),
+	);
+
+	// Entries are grouped by their first letter, or its hex code on macOS.
+	let groups = [first.to_string(), format!("{:x}", u32::from(first))];
+
+	dirs.iter()
+		.flat_map(|dir| groups.iter().map(move |group| dir.join(group)))
+		.find_map(|group| std::fs::read(group.join(term)).ok())
+}
+
+/// The `colors` capability of a compiled terminfo entry, see `term(5)`.
+fn max_colors(entry: &[u8]) -> Option<u32> {
+	/// Index of `colors` among the numbers.
+	const COLORS: usize = 13;
+
+	let short = |idx: usize| {
+		let bytes = entry.get(idx * 2..idx * 2 + 2)?;
+		Some(usize::from(u16::from_le_bytes([bytes[0], bytes[1]])))
+	};
+	let number_size = match short(0)? {
+		0o432 => 2,
+		0o1036 => 4,
+		_ => return None,
+	};
+	let (names, bools, numbers) = (short(1)?, short(2)?, short(3)?);
+	if numbers <= COLORS {
+		return None;
+	}
+
+	// Numbers start on an even offset after the header of six shorts.
+	let start = (12 + names + bools).next_multiple_of(2);
+	let offset = start + COLORS * number_size;
+	let bytes = entry.get(offset..offset + number_size)?;
+
+	// Absent capabilities are negative.
+	let colors = match *bytes {
+		[a, b] => i32::from(i16::from_le_bytes([a, b])),
+		[a, b, c, d] => i32::from_le_bytes([a, b, c, d]),
+		_ => unreachable!("numbers are two or four bytes"),
+	};
+	u32::try_from(colors).ok()
+}
+
+#[test]
+fn detect_env() {
+	use std::collections::HashMap;
+
+	let env = |vars: &[(&str, &str)]| {
+		let vars: HashMap<_, _> = vars
+			.iter()
+			.map(|&(name, value)| (name.to_owned(), value.to_owned()))
+			.collect();
+		move |name: &str| vars.get(name).cloned()
+	};
+
+	assert_eq!(
+		from_env(env(&[("TERM", "xterm-kitty")])),
+		Some(Backend::Kitty)
+	);
+	assert_eq!(
+		from_env(env(&[("TERM_PROGRAM", "iTerm.app")])),
+		Some(Backend::ITerm)
+	);
+	assert_eq!(from_env(env(&[("TERM", "foot")])), Some(Backend::Sixel));
+	assert_eq!(from_env(env(&[("TERM", "xterm-256color")])), None);
+
+	let no_terminfo = |_: &str| None;
+	assert_eq!(
+		colors(env(&[("COLORTERM", "truecolor")]), no_terminfo),
+		Backend::TrueColor
+	);
+	assert_eq!(
+		colors(env(&[("TERM", "xterm-256color")]), no_terminfo),
+		Backend::Colors256
+	);
+	assert_eq!(colors(env(&[("TERM", "dumb")]), no_terminfo), Backend::Ascii);
+	assert_eq!(colors(env(&[]), no_terminfo), Backend::Ascii);
+	assert_eq!(
+		colors(env(&[("TERM", "xterm-direct")]), |_| Some(0x100_0000)),
+		Backend::TrueColor
+	);
+	assert_eq!(colors(env(&[("TERM", "vt100")]), |_| Some(8)), Backend::Ascii);
+
+	assert_eq!(Backend::from_name(" Sixel\n"), Some(Backend::Sixel));
+	assert_eq!(Backend::from_name("256color"), Some(Backend::Colors256));
+	assert_eq!(Backend::from_name("x"), None);
+	assert!(Backend::Kitty < Backend::Ascii);
+}
+
+#[test]
+fn detect_response() {
+	assert_eq!(
+		from_response(b"\x1b_Gi=31;OK\x1b\\\x1b[?62;22c"),
+		Some(Backend::Kitty)
+	);
+	assert_eq!(from_response(b"\x1b[?62;4;22c"), Some(Backend::Sixel));
+	assert_eq!(from_response(b"\x1b[?64;22;42c"), None);
+	assert_eq!(from_response(b"garbage"), None);
+
+	assert_eq!(
+		from_background(b"\x1b]11;rgb:ffff/ffff/dddd\x1b\\\x1b[?62c"),
+		Some(true)
+	);
+	assert_eq!(from_background(b"\x1b]11;rgb:1e/1e/2e\x07"), Some(false));
+	assert_eq!(from_background(b"\x1b]11;rgb:f/f\x07"), None);
+	assert_eq!(from_background(b"\x1b[?62c"), None);
+
+	assert_eq!(from_colorfgbg("0;15"), Some(true));
+	assert_eq!(from_colorfgbg("15;default;0"), Some(false));
+	assert_eq!(from_colorfgbg("default"), None);
+}
+
+#[test]
+fn detect_terminfo() {
+	// The header, a name of 5 bytes, 2 bools padded to an even offset and
+	// 14 numbers with `colors` last.
+	let mut entry: Vec<u8> = [0o432, 5, 2, 14, 0, 0]
+		.iter()
+		.flat_map(|short: &u16| short.to_le_bytes())
+		.collect();
+	entry.extend(b"test\0");
+	entry.extend([1, 1, 0]);
+	entry.extend([0xff; 26]);
+	entry.extend(256u16.to_le_bytes());
+	assert_eq!(max_colors(&entry), Some(256));
+
+	// Absent.
+	let len = entry.len();
+	entry[len - 2..].copy_from_slice(&[0xff, 0xff]);
+	assert_eq!(max_colors(&entry), None);
+
+	assert_eq!(max_colors(b"not terminfo"), None);
+}
@@ -0,0 +1,146 @@
+//! Dithering, hiding the bands left by reducing the colors of an image.
+
+use image::{Rgb, RgbImage};
+
+/// How pixels are spread over the few colors left after reducing them to a
+/// [`crate::ColorDepth`] or the dots of [`crate::RenderMode::Braille`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Dither {
+	/// Every pixel gets its closest color.
+	#[default]
+	None,
+	/// Floyd-Steinberg error diffusion, passing what a pixel is off to its
+	/// neighbors. Smooth gradients, but the noise changes with every frame.
+	FloydSteinberg,
+	/// Ordered dithering with a 4 x 4 Bayer matrix, nudging pixels by their
+	/// position only. A visible cross-hatch pattern, which stays in place
+	/// across the frames of an animation.
+	Ordered,
+}
+
+/// Thresholds of the ordered dithering, in sixteenths.
+const BAYER: [[u8; 4]; 4] =
+	[[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+impl Dither {
+	/// Reduces every pixel of `image` to a color `quantize` returns, `None`
+	/// leaves the colors for later. `step` is about the distance between
+	/// two neighboring colors of `quantize` in one channel.
+	pub(crate) fn apply(
+		self,
+		image: &mut RgbImage,
+		step: f32,
+		quantize: impl Fn(Rgb<u8>) -> Rgb<u8>,
+	) {
+		match self {
+			Self::None => {}
+			Self::FloydSteinberg => floyd_steinberg(image, quantize),
+			Self::Ordered => ordered(image, step, quantize),
+		}
+	}
+}
+
+fn ordered(
+	image: &mut RgbImage,
+	step: f32,
+	quantize: impl Fn(Rgb<u8>) -> Rgb<u8>,
+) {
+	for (x, y, pixel) in image.enumerate_pixels_mut() {
+		let threshold = f32::from(BAYER[y as usize % 4][x as usize % 4]);
+		let offset = ((threshold + 0.5) / 16.0 - 0.5) * step;
+
+		let Rgb(p) = *pixel;
+		*pixel = quantize(Rgb(p.map(|c| {
+			(f32::from(c) + offset).round().clamp(0.0, 255.0) as u8
+		})));
+	}
+}
+
+fn floyd_steinberg(
+	image: &mut RgbImage,
+	quantize: impl Fn(Rgb<u8>) -> Rgb<u8>,
+) {
+	let (width, height) = (image.width() as usize, image.height() as usize);
+	let mut values: Vec<[f32; 3]> =
+		image.pixels().map(|Rgb(p)| p.map(f32::from)).collect();
+
+	for y in 0..height {
+		for x in 0..width {
+			let old = values[y * width + x];
+			let new =
+				quantize(Rgb(old.map(|c| c.round().clamp(0.0, 255.0) as u8)));
+			image.put_pixel(x as u32, y as u32, new);
+
+			let error: [f32; 3] =
+				std::array::from_fn(|c| old[c] - f32::from(new.0[c]));
+			let mut spread = |x: Option<usize>, y: usize, weight: f32| {
+				let Some(x) = x.filter(|&x| x < width && y < height) else {
+					return;
+				};
+				for (value, error) in
+					values[y * width + x].iter_mut().zip(error)
+				{
+					*value += error * weight;
+				}
+			};
+
+			spread(Some(x + 1), y, 7.0 / 16.0);
+			spread(x.checked_sub(1), y + 1, 3.0 / 16.0);
+			spread(Some(x), y + 1, 5.0 / 16.0);
+			spread(Some(x + 1), y + 1, 1.0 / 16.0);
+		}
+	}
+}
+
+#[test]
+fn dither_floyd_steinberg() {
+	let black_white = |Rgb([r, _, _]): Rgb<u8>| {
+		if r >= 128 {
+			Rgb([255; 3])
+		} else {
+			Rgb([0; 3])
+		}
+	};
+	let whites =
+		|image: &RgbImage| image.pixels().filter(|p| p.0[0] == 255).count();
+
+	let mut image = RgbImage::from_pixel(4, 1, Rgb([128; 3]));
+	Dither::FloydSteinberg.apply(&mut image, 255.0, black_white);
+	let row: Vec<_> = image.pixels().map(|p| p.0[0]).collect();
+	assert_eq!(row, [255, 0, 255, 0]);
+
+	// A quarter of the pixels of a dark gray.
+	let mut image = RgbImage::from_pixel(8, 8, Rgb([64; 3]));
+	Dither::FloydSteinberg.apply(&mut image, 255.0, black_white);
+	assert!((12..=20).contains(&whites(&image)), "{}", whites(&image));
+
+	let mut image = RgbImage::from_pixel(8, 8, Rgb([64; 3]));
+	Dither::None.apply(&mut image, 255.0, black_white);
+	assert_eq!(image, RgbImage::from_pixel(8, 8, Rgb([64; 3])));
+}
+
+#[test]
+fn dither_ordered() {
+	let black_white = |Rgb([r, _, _]): Rgb<u8>| {
+		if r >= 128 {
+			Rgb([255; 3])
+		} else {
+			Rgb([0; 3])
+		}
+	};
+
+	// Half of a medium gray, in the same places every time.
+	let mut image = RgbImage::from_pixel(4, 4, Rgb([128; 3]));
+	Dither::Ordered.apply(&mut image, 255.0, black_white);
+	let white: Vec<_> = image
+		.enumerate_pixels()
+		.filter(|(_, _, p)| p.0[0] == 255)
+		.map(|(x, y, _)| (x, y))
+		.collect();
+	assert_eq!(white.len(), 8);
+	assert!(white.contains(&(1, 0)) && !white.contains(&(0, 0)));
+
+	let mut again = RgbImage::from_pixel(4, 4, Rgb([128; 3]));
+	Dither::Ordered.apply(&mut again, 255.0, black_white);
+	assert_eq!(image, again);
+}
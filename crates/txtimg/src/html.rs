@@ -0,0 +1,149 @@
+//! Exporting text images as HTML, for web pages and reports.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use crate::TextImage;
+
+type Color = Option<(u8, u8, u8)>;
+
+impl TextImage {
+	/// The image as a `<pre>` of spans with their colors in inline styles,
+	/// to paste into a page as is. Looks best with a `line-height` of `1`
+	/// and a monospace font covering the characters of the render mode.
+	pub fn to_html(&self) -> String {
+		self.html(|fg, bg| {
+			let styles: Vec<_> = [("color", fg), ("background-color", bg)]
+				.into_iter()
+				.filter_map(|(property, color)| {
+					Some(format!("{property}: {}", hex(color?)))
+				})
+				.collect();
+			format!("style=\"{}\"", styles.join("; "))
+		})
+	}
+
+	/// The image like [`TextImage::to_html`], but with the colors in CSS
+	/// classes starting with `prefix`, and the stylesheet defining them.
+	/// Keeps the markup small for large images in few colors.
+	pub fn to_html_classes(&self, prefix: &str) -> (String, String) {
+		let mut classes: HashMap<((u8, u8, u8), bool), String> =
+			HashMap::new();
+		let mut css = String::new();
+
+		let html = self.html(|fg, bg| {
+			let mut class = |color: Color, background: bool| {
+				let color = color?;
+				let next = classes.len();
+				let name =
+					classes.entry((color, background)).or_insert_with(|| {
+						let (kind, property) = if background {
+							('b', "background-color")
+						} else {
+							('f', "color")
+						};
+						let name = format!("{prefix}{kind}{next}");
+						let _ = writeln!(
+							css,
+							".{name} {{ {property}: {} }}",
+							hex(color)
+						);
+						name
+					});
+				Some(name.clone())
+			};
+
+			let names: Vec<_> = [class(fg, false), class(bg, true)]
+				.into_iter()
+				.flatten()
+				.collect();
+			format!("class=\"{}\"", names.join(" "))
+		});
+
+		(html, css)
+	}
+
+	/// Writes the `<pre>`, with `attributes` giving those of the spans
+	/// around runs in color.
+	fn html(
+		&self,
+		mut attributes: impl FnMut(Color, Color) -> String,
+	) -> String {
+		let mut html = String::from("<pre>");
+
+		for line in self.lines() {
+			for run in line {
+				if (run.fg(), run.bg()) == (None, None) {
+					escape(&mut html, run.text());
+					continue;
+				}
+
+				let _ =
+					write!(html, "<span {}>", attributes(run.fg(), run.bg()));
+				escape(&mut html, run.text());
+				html.push_str("</span>");
+			}
+			html.push('\n');
+		}
+
+		html.push_str("</pre>");
+		html
+	}
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+	format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Appends `text` with the characters HTML reserves replaced.
+fn escape(html: &mut String, text: &str) {
+	for c in text.chars() {
+		match c {
+			'&' => html.push_str("&amp;"),
+			'<' => html.push_str("&lt;"),
+			'>' => html.push_str("&gt;"),
+			c => html.push(c),
+		}
+	}
+}
+
+#[test]
+fn html_export() {
+	use image::{DynamicImage, Rgb, RgbImage};
+
+	use crate::{Fit, Options, RenderMode};
+
+	// A red column between black ones.
+	let image = RgbImage::from_fn(3, 2, |x, _| {
+		Rgb([if x == 1 { 255 } else { 0 }, 0, 0])
+	});
+	let render = |mode| {
+		let opts =
+			Options::builder().size(3, 1).fit(Fit::Stretch).mode(mode).build();
+		TextImage::from_image(DynamicImage::ImageRgb8(image.clone()), opts)
+	};
+
+	assert_eq!(
+		render(RenderMode::Blocks).to_html(),
+		"<pre><span style=\"background-color: #000000\"> </span><span \
+		 style=\"background-color: #ff0000\"> </span><span \
+		 style=\"background-color: #000000\"> </span>\n</pre>"
+	);
+	assert_eq!(render(RenderMode::Ascii).to_html(), "<pre> ; \n</pre>");
+
+	let (html, css) = render(RenderMode::HalfBlocks).to_html_classes("t-");
+	assert_eq!(
+		html,
+		"<pre><span class=\"t-f0 t-b1\">▀</span><span class=\"t-f2 \
+		 t-b3\">▀</span><span class=\"t-f0 t-b1\">▀</span>\n</pre>"
+	);
+	assert_eq!(
+		css,
+		".t-f0 { color: #000000 }\n.t-b1 { background-color: #000000 \
+		 }\n.t-f2 { color: #ff0000 }\n.t-b3 { background-color: #ff0000 }\n"
+	);
+
+	let mut html = String::new();
+	escape(&mut html, "<&>");
+	assert_eq!(html, "&lt;&amp;&gt;");
+}
@@ -0,0 +1,103 @@
+//! The inline images of iTerm2, also understood by WezTerm and mintty.
+
+use std::fmt::Write as _;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use image::DynamicImage;
+
+use crate::{encode_png, Options, CELL_PIXELS};
+
+/// An image encoded as PNG, shown over a number of cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ITermImage {
+	columns: u32,
+	rows: u32,
+	preserve_aspect: bool,
+	png: Vec<u8>,
+}
+
+impl ITermImage {
+	/// Scales `image` into the cells of `opts` like [`crate::TextImage`], at
+	/// 8 x 16 pixels per cell. The terminal scales it to the actual cell
+	/// size, keeping its aspect ratio unless
+	/// [disabled](crate::OptionsBuilder::preserve_aspect). The render mode
+	/// and palette don't apply.
+	pub fn from_image(image: DynamicImage, opts: Options) -> Self {
+		let preserve_aspect = opts.preserve_aspect;
+		let Some(image) = opts.resize(image, CELL_PIXELS) else {
+			return Self {
+				columns: 0,
+				rows: 0,
+				preserve_aspect,
+				png: Vec::new(),
+			};
+		};
+		let (columns, rows) =
+			(image.width() / CELL_PIXELS.0, image.height() / CELL_PIXELS.1);
+
+		Self { columns, rows, preserve_aspect, png: encode_png(image) }
+	}
+
+	/// Number of cells per line.
+	pub fn columns(&self) -> u32 {
+		self.columns
+	}
+
+	/// Number of lines.
+	pub fn rows(&self) -> u32 {
+		self.rows
+	}
+
+	/// Writes the sequence showing the image at the cursor.
+	pub fn to_buffer(&self, buffer: &mut String) {
+		if self.png.is_empty() {
+			return;
+		}
+
+		let _ = write!(
+			buffer,
+			"\x1b]1337;File=inline=1;size={};width={};height={};\
+			 preserveAspectRatio={}:",
+			self.png.len(),
+			self.columns,
+			self.rows,
+			u8::from(self.preserve_aspect),
+		);
+		STANDARD.encode_string(&self.png, buffer);
+		buffer.push('\x07');
+	}
+}
+
+#[test]
+fn iterm_to_buffer() {
+	use crate::Fit;
+
+	let image = ITermImage {
+		columns: 2,
+		rows: 1,
+		preserve_aspect: false,
+		png: vec![0; 3],
+	};
+	let mut buffer = String::new();
+	image.to_buffer(&mut buffer);
+	assert_eq!(
+		buffer,
+		"\x1b]1337;File=inline=1;size=3;width=2;height=1;\
+		 preserveAspectRatio=0:AAAA\x07"
+	);
+
+	let image = ITermImage::from_image(
+		DynamicImage::new_rgb8(4, 2),
+		Options::builder().size(2, 2).build(),
+	);
+	assert_eq!((image.columns(), image.rows()), (2, 1));
+	assert!(image.preserve_aspect);
+	assert!(image.png.starts_with(b"\x89PNG"));
+
+	let image = ITermImage::from_image(
+		DynamicImage::new_rgb8(4, 2),
+		Options::builder().size(2, 2).fit(Fit::Stretch).build(),
+	);
+	assert_eq!((image.columns(), image.rows()), (2, 2));
+}
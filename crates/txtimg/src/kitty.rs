@@ -0,0 +1,140 @@
+//! The kitty graphics protocol, drawing real pixels on kitty, WezTerm and
+//! Konsole.
+
+use std::fmt::Write as _;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use image::DynamicImage;
+
+use crate::{encode_png, Options, CELL_PIXELS};
+
+/// Most base64 bytes the terminal accepts per escape sequence.
+const CHUNK: usize = 4096;
+
+/// An image encoded as PNG, placed over a number of cells.
+///
+/// Drawing it again with the same [id](KittyImage::with_id) replaces the
+/// previous one in place, so covers can change without clearing the screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KittyImage {
+	id: u32,
+	columns: u32,
+	rows: u32,
+	png: Vec<u8>,
+}
+
+impl KittyImage {
+	/// Scales `image` into the cells of `opts` like [`crate::TextImage`], at
+	/// 8 x 16 pixels per cell. The terminal scales it to the actual cell
+	/// size. The render mode and palette don't apply.
+	pub fn from_image(image: DynamicImage, opts: Options) -> Self {
+		let Some(image) = opts.resize(image, CELL_PIXELS) else {
+			return Self { id: 1, columns: 0, rows: 0, png: Vec::new() };
+		};
+		let (columns, rows) =
+			(image.width() / CELL_PIXELS.0, image.height() / CELL_PIXELS.1);
+
+		Self { id: 1, columns, rows, png: encode_png(image) }
+	}
+
+	/// Identifies the image on the terminal, defaults to `1`. Must not be
+	/// `0`.
+	pub fn with_id(mut self, id: u32) -> Self {
+		self.id = id;
+		self
+	}
+
+	pub fn id(&self) -> u32 {
+		self.id
+	}
+
+	/// Number of cells per line.
+	pub fn columns(&self) -> u32 {
+		self.columns
+	}
+
+	/// Number of lines.
+	pub fn rows(&self) -> u32 {
+		self.rows
+	}
+
+	/// Writes the sequences transmitting the image and placing it at the
+	/// cursor, replacing an earlier image with the same id.
+	pub fn to_buffer(&self, buffer: &mut String) {
+		if self.png.is_empty() {
+			return;
+		}
+
+		let data = STANDARD.encode(&self.png);
+		let count = data.len().div_ceil(CHUNK);
+
+		for (idx, chunk) in data.as_bytes().chunks(CHUNK).enumerate() {
+			buffer.push_str("\x1b_G");
+			if idx == 0 {
+				// Quiet, as nobody reads the responses.
+				let _ = write!(
+					buffer,
+					"a=T,f=100,i={},p=1,c={},r={},q=2,",
+					self.id, self.columns, self.rows
+				);
+			}
+			let _ = write!(buffer, "m={};", u8::from(idx + 1 < count));
+			buffer.push_str(
+				std::str::from_utf8(chunk).expect("base64 is plain ASCII"),
+			);
+			buffer.push_str("\x1b\\");
+		}
+	}
+}
+
+/// Writes the sequence removing the image with `id` from the screen and
+/// the memory of the terminal.
+pub fn delete(id: u32, buffer: &mut String) {
+	let _ = write!(buffer, "\x1b_Ga=d,d=I,i={id},q=2\x1b\\");
+}
+
+/// Writes the sequence removing all images from the screen and the memory
+/// of the terminal.
+pub fn delete_all(buffer: &mut String) {
+	buffer.push_str("\x1b_Ga=d,d=A,q=2\x1b\\");
+}
+
+#[test]
+fn kitty_to_buffer() {
+	use crate::Fit;
+
+	let render = |image: &KittyImage| {
+		let mut buffer = String::new();
+		image.to_buffer(&mut buffer);
+		buffer
+	};
+
+	let image = KittyImage { id: 1, columns: 2, rows: 1, png: vec![0; 3] };
+	assert_eq!(
+		render(&image.with_id(7)),
+		"\x1b_Ga=T,f=100,i=7,p=1,c=2,r=1,q=2,m=0;AAAA\x1b\\"
+	);
+
+	// 4000 bytes take 5336 in base64.
+	let image = KittyImage { id: 1, columns: 2, rows: 1, png: vec![0; 4000] };
+	let buffer = render(&image);
+	let chunks: Vec<_> = buffer.split_terminator("\x1b\\").collect();
+	assert_eq!(chunks.len(), 2);
+	assert!(chunks[0].starts_with("\x1b_Ga=T,"));
+	assert!(chunks[0].contains(",m=1;"));
+	assert!(chunks[1].starts_with("\x1b_Gm=0;"));
+	assert_eq!(chunks[1].len(), "\x1b_Gm=0;".len() + 5336 - CHUNK);
+
+	let image = KittyImage::from_image(
+		DynamicImage::new_rgb8(3, 3),
+		Options::builder().size(2, 1).fit(Fit::Stretch).build(),
+	);
+	assert_eq!((image.columns(), image.rows()), (2, 1));
+	assert!(image.png.starts_with(b"\x89PNG"));
+
+	let mut buffer = String::new();
+	delete(7, &mut buffer);
+	delete_all(&mut buffer);
+	assert_eq!(buffer, "\x1b_Ga=d,d=I,i=7,q=2\x1b\\\x1b_Ga=d,d=A,q=2\x1b\\");
+}
@@ -1,14 +1,234 @@
-use colored::Colorize;
-use image::DynamicImage;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Cursor, Write as _};
+use std::ops::Range;
 
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageOutputFormat, Rgb, RgbImage, RgbaImage};
+use terminal_size::{Height, Width};
+
+pub use crate::animation::TextAnimation;
+pub use crate::color::ColorDepth;
+pub use crate::detect::{detect, Backend};
+pub use crate::dither::Dither;
+use crate::iterm::ITermImage;
+use crate::kitty::KittyImage;
 use crate::pallete::rgb;
+pub use crate::sample::Sampling;
+pub use crate::screen::Screen;
+use crate::sixel::SixelImage;
+
+mod animation;
+mod color;
+pub mod detect;
+mod dither;
+mod html;
+pub mod iterm;
+pub mod kitty;
+mod sample;
+mod screen;
+pub mod sixel;
+#[cfg(feature = "svg")]
+pub mod svg;
+pub mod text;
+
+/// Marks the second cell covered by a double-width character.
+const WIDE_TAIL: char = '\0';
 
 mod pallete {
 	use image::Rgb;
 
-	const PALETTE: &[char] =
+	pub(crate) const ASCII10: &[char] =
 		&[' ', '.', ':', ';', '+', 'o', 'O', '&', '@', '#'];
 
+	/// Paul Bourke's 70 level ramp.
+	pub(crate) const ASCII70: &[char] = &[
+		' ', '.', '\'', '`', '^', '"', ',', ':', ';', 'I', 'l', '!', 'i', '>',
+		'<', '~', '+', '_', '-', '?', ']', '[', '}', '{', '1', ')', '(', '|',
+		'\\', '/', 't', 'f', 'j', 'r', 'x', 'n', 'u', 'v', 'c', 'z', 'X', 'Y',
+		'U', 'J', 'C', 'L', 'Q', '0', 'O', 'Z', 'm', 'w', 'q', 'p', 'd', 'b',
+		'k', 'h', 'a', 'o', '*', '#', 'M', 'W', '&', '8', '%', 'B', '@', '$',
+	];
+
+	pub(crate) const BLOCKS: &[char] = &[' ', '░', '▒', '▓', '█'];
+
+	/// Bits of the braille dots, for the pixels of a 2 x 4 block row by
+	/// row.
+	const BRAILLE_DOTS: [u8; 8] =
+		[0x01, 0x08, 0x02, 0x10, 0x04, 0x20, 0x40, 0x80];
+
+	/// The braille character with a dot for every set pixel of a 2 x 4
+	/// block, given row by row.
+	pub(crate) fn braille(set: impl IntoIterator<Item = bool>) -> char {
+		let dots = set
+			.into_iter()
+			.zip(BRAILLE_DOTS)
+			.filter(|&(set, _)| set)
+			.fold(0, |dots, (_, dot)| dots | dot);
+
+		char::from_u32(0x2800 + u32::from(dots))
+			.expect("the braille block is fully assigned")
+	}
+
+	/// Quadrant blocks by the mask of their set quarters, bit `0` top left
+	/// to bit `3` bottom right.
+	pub(crate) const QUADRANTS: [char; 16] = [
+		' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙',
+		'▟', '█',
+	];
+
+	/// The sextant block with the set sixths of `mask`, bit `0` top left
+	/// to bit `5` bottom right.
+	pub(crate) fn sextant(mask: u8) -> char {
+		// The halves and the full block existed before and are left out of
+		// the sextant block.
+		let c = match mask & 0b11_1111 {
+			0 => return ' ',
+			0b01_0101 => return '▌',
+			0b10_1010 => return '▐',
+			0b11_1111 => return '█',
+			mask => {
+				let skipped =
+					u8::from(mask > 0b01_0101) + u8::from(mask > 0b10_1010);
+				0x1fb00 + u32::from(mask - 1 - skipped)
+			}
+		};
+
+		char::from_u32(c).expect("the sextant block is fully assigned")
+	}
+
+	/// The pixels of an 8 x 8 block covered by the rectangle from
+	/// `(left, top)` to `(right, bottom)`, bit `0` top left.
+	const fn rect(left: u32, top: u32, right: u32, bottom: u32) -> u64 {
+		let row = ((1u64 << right) - 1) & !((1u64 << left) - 1);
+		let mut mask = 0;
+		let mut y = top;
+		while y < bottom {
+			mask |= row << (y * 8);
+			y += 1;
+		}
+		mask
+	}
+
+	/// Block elements by the pixels of an 8 x 8 block they cover. Leaves out
+	/// the complements of others, which swapping the colors shows.
+	pub(crate) const BLOCK_ELEMENTS: [(u64, char); 22] = [
+		(0, ' '),
+		(rect(0, 0, 8, 4), '▀'),
+		(rect(0, 0, 4, 8), '▌'),
+		(rect(0, 0, 4, 4), '▘'),
+		(rect(4, 0, 8, 4), '▝'),
+		(rect(0, 4, 4, 8), '▖'),
+		(rect(4, 4, 8, 8), '▗'),
+		(rect(0, 0, 4, 4) | rect(4, 4, 8, 8), '▚'),
+		(rect(0, 7, 8, 8), '▁'),
+		(rect(0, 6, 8, 8), '▂'),
+		(rect(0, 5, 8, 8), '▃'),
+		(rect(0, 3, 8, 8), '▅'),
+		(rect(0, 2, 8, 8), '▆'),
+		(rect(0, 1, 8, 8), '▇'),
+		(rect(0, 0, 8, 1), '▔'),
+		(rect(0, 0, 1, 8), '▏'),
+		(rect(0, 0, 2, 8), '▎'),
+		(rect(0, 0, 3, 8), '▍'),
+		(rect(0, 0, 5, 8), '▋'),
+		(rect(0, 0, 6, 8), '▊'),
+		(rect(0, 0, 7, 8), '▉'),
+		(rect(7, 0, 8, 8), '▕'),
+	];
+
+	/// Splits a block of up to eight pixels into the two colors and the
+	/// mask of the pixels in the first one with the least squared error.
+	/// The first pixel always gets the second color, so an unset mask
+	/// means a single color.
+	pub(crate) fn split(
+		block: &[Rgb<u8>],
+	) -> (u8, (u8, u8, u8), (u8, u8, u8)) {
+		let masks = (0..1u64 << (block.len() - 1)).map(|half| half << 1);
+		let (mask, fg, bg) = split_by(block, masks);
+
+		(mask as u8, fg, bg)
+	}
+
+	/// The one of `masks` splitting `block` into the two colors with the
+	/// least squared error, and the colors of its set and unset pixels.
+	/// The first one wins ties.
+	pub(crate) fn split_by(
+		block: &[Rgb<u8>],
+		masks: impl IntoIterator<Item = u64>,
+	) -> (u64, (u8, u8, u8), (u8, u8, u8)) {
+		let is_set = |mask: u64, idx: usize| mask & (1 << idx) != 0;
+		let mean = |mask: u64, set: bool| {
+			let pixels = block
+				.iter()
+				.enumerate()
+				.filter(|&(idx, _)| is_set(mask, idx) == set);
+			let (mut sum, mut n) = ([0u32; 3], 0);
+			for (_, Rgb(p)) in pixels {
+				for (sum, c) in sum.iter_mut().zip(p) {
+					*sum += u32::from(*c);
+				}
+				n += 1;
+			}
+
+			let [r, g, b] =
+				sum.map(|c| (c + n / 2).checked_div(n).unwrap_or(0));
+			(r as u8, g as u8, b as u8)
+		};
+		let error = |mask: u64, fg: (u8, u8, u8), bg: (u8, u8, u8)| -> u32 {
+			block
+				.iter()
+				.enumerate()
+				.map(|(idx, Rgb(p))| {
+					let (r, g, b) = if is_set(mask, idx) { fg } else { bg };
+					p.iter()
+						.zip([r, g, b])
+						.map(|(a, b)| u32::from(a.abs_diff(b)).pow(2))
+						.sum::<u32>()
+				})
+				.sum()
+		};
+
+		masks
+			.into_iter()
+			.map(|mask| (mask, mean(mask, true), mean(mask, false)))
+			.min_by_key(|&(mask, fg, bg)| error(mask, fg, bg))
+			.expect("there are masks to pick from")
+	}
+
+	/// The line best following the edge through a block of 3 x 3 pixels,
+	/// found with the Sobel operator, or `None` if its contrast is below
+	/// `threshold`.
+	pub(crate) fn edge(block: &[Rgb<u8>], threshold: u8) -> Option<char> {
+		let l: Vec<i32> =
+			block.iter().map(|p| i32::from(luminance(p))).collect();
+
+		let gx = (l[2] + 2 * l[5] + l[8]) - (l[0] + 2 * l[3] + l[6]);
+		let gy = (l[6] + 2 * l[7] + l[8]) - (l[0] + 2 * l[1] + l[2]);
+		let (ax, ay) = (gx.abs(), gy.abs());
+		if ax.max(ay) / 4 < i32::from(threshold) {
+			return None;
+		}
+
+		// Edges run across the gradient, in steps of 45 degrees.
+		let c = if ay * 5 < ax * 2 {
+			'|'
+		} else if ax * 5 < ay * 2 {
+			let row = |y: usize| l[y * 3] + l[y * 3 + 1] + l[y * 3 + 2];
+			if (row(2) - row(1)).abs() > (row(1) - row(0)).abs() {
+				'_'
+			} else {
+				'-'
+			}
+		} else if (gx > 0) == (gy > 0) {
+			'/'
+		} else {
+			'\\'
+		};
+
+		Some(c)
+	}
+
 	pub(crate) fn rgb(Rgb([r, g, b]): &Rgb<u8>) -> (u8, u8, u8) {
 		(*r, *g, *b)
 	}
@@ -19,120 +239,1744 @@ mod pallete {
 		(((r << 1) + r + (g << 2) + b) >> 3) as u8
 	}
 
-	pub(crate) fn get_char(luminance: u8) -> char {
-		let idx = luminance as f32 / (u8::MAX as f32 / PALETTE.len() as f32);
-		let idx = idx as usize;
+	/// Picks from `palette` in equally sized luminance steps.
+	pub(crate) fn get_char(palette: &[char], luminance: u8) -> char {
+		let idx = usize::from(luminance) * palette.len() / 256;
+
+		palette.get(idx).copied().unwrap_or(' ')
+	}
+}
+
+/// Terminal cells are about twice as tall as they are wide.
+const CELL_ASPECT: f32 = 2.0;
+
+/// Assumed size of a terminal cell in pixels for the protocols drawing real
+/// pixels, matching [`CELL_ASPECT`]. Terminals don't reliably report it.
+const CELL_PIXELS: (u32, u32) = (8, 16);
+
+/// How an image is scaled into the `width` x `height` cells of [`Options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Fit {
+	/// Scales the image to fit inside, keeping its aspect ratio. The text
+	/// image may be smaller than requested in one dimension, unless it is
+	/// [letterboxed](OptionsBuilder::letterbox).
+	#[default]
+	Contain,
+	/// Scales the image to fill all cells, keeping its aspect ratio and
+	/// cutting off what sticks out on either side.
+	Cover,
+	/// Scales the image to exactly the requested size, distorting it.
+	Stretch,
+}
+
+/// How pixels are interpolated when scaling an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Filter {
+	/// The closest pixel, fastest but aliased. Keeps pixel art crisp.
+	Nearest,
+	/// Linear interpolation.
+	Triangle,
+	/// Cubic interpolation, sharper than [`Filter::Triangle`].
+	CatmullRom,
+	/// Lanczos with a window of 3, the sharpest, and slowest.
+	#[default]
+	Lanczos3,
+}
+
+impl From<Filter> for FilterType {
+	fn from(filter: Filter) -> Self {
+		match filter {
+			Filter::Nearest => Self::Nearest,
+			Filter::Triangle => Self::Triangle,
+			Filter::CatmullRom => Self::CatmullRom,
+			Filter::Lanczos3 => Self::Lanczos3,
+		}
+	}
+}
+
+/// How pixels are turned into cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RenderMode {
+	/// Spaces with the pixel color as background.
+	#[default]
+	Blocks,
+	/// Characters denser the brighter the pixel, without colors. Works on
+	/// terminals without color support and in plain text files.
+	Ascii,
+	/// Characters like [`RenderMode::Ascii`], in the pixel color.
+	AsciiColored,
+	/// Braille characters with a dot for every pixel of 2 x 4 per cell
+	/// at least as bright as the [threshold](OptionsBuilder::threshold),
+	/// without colors. Eight times the resolution of the other modes.
+	Braille,
+	/// Upper half blocks in the color of the top pixel on the color of the
+	/// bottom one, twice the vertical resolution of [`RenderMode::Blocks`].
+	HalfBlocks,
+	/// Quadrant blocks covering 2 x 2 pixels per cell, picking the shape
+	/// and the two colors closest to the pixels.
+	Quadrants,
+	/// Sextant blocks covering 2 x 3 pixels per cell, like
+	/// [`RenderMode::Quadrants`]. Needs a font supporting Unicode 13.
+	Sextants,
+	/// Block elements covering 8 x 8 pixels per cell: halves, quadrants
+	/// and eighths, picking the shape and the two colors closest to the
+	/// pixels. Finer edges than [`RenderMode::Quadrants`] with fonts
+	/// lacking the sextants.
+	BlockArt,
+	/// Line art for logos and screenshots: `/ \ | - _` following the edges
+	/// with a contrast of at least the
+	/// [threshold](OptionsBuilder::threshold), characters like
+	/// [`RenderMode::Ascii`] elsewhere. Samples 3 x 3 pixels per cell,
+	/// without colors.
+	Edges,
+}
+
+impl RenderMode {
+	/// Number of pixels sampled per cell, horizontally and vertically.
+	fn cell_size(self) -> (u32, u32) {
+		match self {
+			Self::Blocks | Self::Ascii | Self::AsciiColored => (1, 1),
+			Self::HalfBlocks => (1, 2),
+			Self::Quadrants => (2, 2),
+			Self::Sextants => (2, 3),
+			Self::Braille => (2, 4),
+			Self::Edges => (3, 3),
+			Self::BlockArt => (8, 8),
+		}
+	}
+}
+
+/// Luminance weights of red, green and blue from ITU-R BT.601, matching
+/// most other tools.
+pub const REC601: [f32; 3] = [0.299, 0.587, 0.114];
 
-		PALETTE[idx.clamp(0, PALETTE.len() - 1)]
+/// Luminance weights of red, green and blue from ITU-R BT.709, for the
+/// primaries of sRGB.
+pub const REC709: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// How [`render`] draws an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OutputProtocol {
+	/// Characters in the [`RenderMode`] of the options, shown by every
+	/// terminal.
+	#[default]
+	Text,
+	/// Sixel graphics with real pixels, see [`SixelImage`].
+	Sixel,
+	/// The kitty graphics protocol, see [`KittyImage`].
+	Kitty,
+	/// The inline images of iTerm2, see [`ITermImage`].
+	ITerm,
+}
+
+/// Characters used by the ASCII [`RenderMode`]s, ordered from the sparsest
+/// to the densest, so brighter pixels get denser characters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Palette {
+	/// `" .:;+oO&@#"`, readable in every font.
+	#[default]
+	Ascii10,
+	/// A 70 character ramp with finer steps, best with small fonts.
+	Ascii70,
+	/// Shade blocks `" ░▒▓█"`.
+	Blocks,
+	/// Characters ordered from sparsest to densest. An empty palette
+	/// renders spaces only.
+	Custom(Cow<'static, [char]>),
+}
+
+impl Palette {
+	/// A [`Palette::Custom`] from a slice or vector.
+	pub fn custom(chars: impl Into<Cow<'static, [char]>>) -> Self {
+		Self::Custom(chars.into())
+	}
+
+	pub fn chars(&self) -> &[char] {
+		match self {
+			Self::Ascii10 => pallete::ASCII10,
+			Self::Ascii70 => pallete::ASCII70,
+			Self::Blocks => pallete::BLOCKS,
+			Self::Custom(chars) => chars,
+		}
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Options {
 	width: u32,
 	height: u32,
+	fit: Fit,
+	sampling: Sampling,
+	filter: Filter,
+	preserve_aspect: bool,
+	mode: RenderMode,
+	palette: Palette,
+	threshold: u8,
+	protocol: OutputProtocol,
+	color_depth: ColorDepth,
+	dither: Dither,
+	grayscale: Option<[f32; 3]>,
+	brightness: f32,
+	contrast: f32,
+	gamma: f32,
+	saturation: f32,
+	invert_colors: bool,
+	light_background: bool,
+	background: (u8, u8, u8),
+	letterbox: Option<Letterbox>,
+	crop: Option<(u32, u32, u32, u32)>,
+}
+
+/// The cells padding [letterboxed](OptionsBuilder::letterbox) images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Letterbox {
+	fill: char,
+	color: Option<(u8, u8, u8)>,
+}
+
+impl Options {
+	/// Starts with 80 x 24 cells, [`Fit::Contain`], [`Filter::Lanczos3`],
+	/// aspect preservation, [`RenderMode::Blocks`] and [`Palette::Ascii10`].
+	pub fn builder() -> OptionsBuilder {
+		OptionsBuilder(Self::default())
+	}
+
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	/// Scales and crops `image` to its cells with `cell` pixels each, `None`
+	/// if nothing of it would be left.
+	pub(crate) fn resize(
+		&self,
+		image: DynamicImage,
+		(cell_w, cell_h): (u32, u32),
+	) -> Option<DynamicImage> {
+		let image = match self.crop {
+			Some((x, y, width, height)) => image.crop_imm(x, y, width, height),
+			None => image,
+		};
+		let (width, height) = self.scaled_size(image.width(), image.height());
+		if width == 0 || height == 0 {
+			return None;
+		}
+
+		let size = (width * cell_w, height * cell_h);
+		let mut image = self.sampling.resize(image, size, self.filter);
+		if self.fit == Fit::Cover {
+			let (x, y) =
+				((width - self.width) / 2, (height - self.height) / 2);
+			image = image.crop_imm(
+				x * cell_w,
+				y * cell_h,
+				self.width * cell_w,
+				self.height * cell_h,
+			);
+		}
+
+		image = self.adjust(image);
+		if let Some(weights) = self.grayscale {
+			image = grayscale(image, weights);
+		}
+
+		Some(image)
+	}
+
+	/// Applies the contrast, brightness, gamma and saturation to the colors
+	/// of `image`, in that order, and inverts them if asked to.
+	fn adjust(&self, image: DynamicImage) -> DynamicImage {
+		let (brightness, contrast, gamma, saturation) =
+			(self.brightness, self.contrast, self.gamma, self.saturation);
+		if (brightness, contrast, gamma, saturation) == (0.0, 1.0, 1.0, 1.0)
+			&& !self.invert_colors
+		{
+			return image;
+		}
+
+		// The same for every channel, so computed once per value.
+		let levels: [f32; 256] = std::array::from_fn(|c| {
+			let c = (c as f32 / 255.0 - 0.5) * contrast + 0.5 + brightness;
+			let c = c.clamp(0.0, 1.0).powf(1.0 / gamma.max(f32::EPSILON));
+			// Mixing with gray below keeps the inversion intact.
+			if self.invert_colors {
+				(1.0 - c) * 255.0
+			} else {
+				c * 255.0
+			}
+		});
+
+		let mut image = image.into_rgba8();
+		for pixel in image.pixels_mut() {
+			let [r, g, b, a] = pixel.0;
+			let rgb = [r, g, b].map(|c| levels[usize::from(c)]);
+			let gray: f32 = rgb.iter().zip(REC601).map(|(c, w)| c * w).sum();
+			let [r, g, b] = rgb.map(|c| {
+				(c * saturation + gray * (1.0 - saturation))
+					.round()
+					.clamp(0.0, 255.0) as u8
+			});
+
+			pixel.0 = [r, g, b, a];
+		}
+
+		DynamicImage::ImageRgba8(image)
+	}
+
+	/// Size in cells an image of `width` x `height` pixels is scaled to
+	/// before [`Fit::Cover`] cuts it down to the requested size.
+	fn scaled_size(&self, width: u32, height: u32) -> (u32, u32) {
+		if [self.width, self.height, width, height].contains(&0) {
+			return (0, 0);
+		}
+		if self.fit == Fit::Stretch {
+			return (self.width, self.height);
+		}
+
+		let aspect = if self.preserve_aspect { CELL_ASPECT } else { 1.0 };
+		let (width, height) = (width as f32, height as f32 / aspect);
+		let scale_x = self.width as f32 / width;
+		let scale_y = self.height as f32 / height;
+
+		let scale = match self.fit {
+			Fit::Contain => scale_x.min(scale_y),
+			Fit::Cover | Fit::Stretch => scale_x.max(scale_y),
+		};
+		let scaled = |size: f32, max: u32| {
+			((size * scale).round() as u32).clamp(1, max)
+		};
+
+		match self.fit {
+			Fit::Contain => {
+				(scaled(width, self.width), scaled(height, self.height))
+			}
+			Fit::Cover | Fit::Stretch => (
+				scaled(width, u32::MAX).max(self.width),
+				scaled(height, u32::MAX).max(self.height),
+			),
+		}
+	}
+
+	/// `image` with its translucent pixels blended over the
+	/// [background](OptionsBuilder::background).
+	pub(crate) fn blend(&self, image: &RgbaImage) -> RgbImage {
+		let (br, bg, bb) = self.background;
+
+		RgbImage::from_fn(image.width(), image.height(), |x, y| {
+			let [r, g, b, a] = image.get_pixel(x, y).0;
+			let a = u16::from(a);
+			let blend = |c: u8, background: u8| {
+				let c = u16::from(c) * a + u16::from(background) * (255 - a);
+				((c + 127) / 255) as u8
+			};
+
+			Rgb([blend(r, br), blend(g, bg), blend(b, bb)])
+		})
+	}
+
+	/// Reduces the pixels of `image` to the colors they end up in with the
+	/// [dithering](OptionsBuilder::dither).
+	fn dither_image(&self, image: &mut RgbImage) {
+		if self.mode == RenderMode::Braille {
+			// Dots on the dark pixels of light backgrounds, so those turn
+			// black.
+			self.dither.apply(image, 255.0, |p| {
+				let dot = self.ink(&p) >= self.threshold;
+				Rgb([if dot != self.light_background { 255 } else { 0 }; 3])
+			});
+		} else if self.color_depth != ColorDepth::TrueColor
+			&& !matches!(self.mode, RenderMode::Ascii | RenderMode::Edges)
+		{
+			let depth = self.color_depth;
+			self.dither.apply(image, depth.step(), |Rgb([r, g, b])| {
+				let (r, g, b) = depth.quantize((r, g, b));
+				Rgb([r, g, b])
+			});
+		}
+	}
+
+	/// How much a character covers `pixel`: its luminance, inverted on
+	/// [light backgrounds](OptionsBuilder::light_background).
+	fn ink(&self, pixel: &Rgb<u8>) -> u8 {
+		let luminance = crate::pallete::luminance(pixel);
+
+		if self.light_background {
+			255 - luminance
+		} else {
+			luminance
+		}
+	}
+
+	/// Turns the pixels sampled for a cell, row by row, into the cell.
+	fn cell(&self, block: &[Rgb<u8>]) -> Pixel {
+		use crate::pallete::{
+			braille, edge, get_char, sextant, split_by, BLOCK_ELEMENTS,
+			QUADRANTS,
+		};
+
+		let first = &block[0];
+
+		match self.mode {
+			RenderMode::Blocks => {
+				Pixel { c: ' ', b: Some(rgb(first)), f: None }
+			}
+			RenderMode::Ascii => Pixel {
+				c: get_char(self.palette.chars(), self.ink(first)),
+				b: None,
+				f: None,
+			},
+			RenderMode::AsciiColored => Pixel {
+				c: get_char(self.palette.chars(), self.ink(first)),
+				b: None,
+				f: Some(rgb(first)),
+			},
+			RenderMode::Braille => {
+				let set = block.iter().map(|p| self.ink(p) >= self.threshold);
+				Pixel { c: braille(set), b: None, f: None }
+			}
+			RenderMode::HalfBlocks => {
+				Pixel {
+					c: '▀', b: Some(rgb(&block[1])), f: Some(rgb(first))
+				}
+			}
+			RenderMode::Quadrants => {
+				split_cell(block, |mask| QUADRANTS[usize::from(mask)])
+			}
+			RenderMode::Sextants => split_cell(block, sextant),
+			RenderMode::BlockArt => {
+				let elements = BLOCK_ELEMENTS.map(|(mask, _)| mask);
+				let (mask, fg, bg) = split_by(block, elements);
+				let c = BLOCK_ELEMENTS
+					.iter()
+					.find_map(|&(m, c)| (m == mask).then_some(c))
+					.expect("the mask is one of the elements");
+
+				Pixel { c, b: Some(bg), f: (mask != 0).then_some(fg) }
+			}
+			RenderMode::Edges => {
+				let c = edge(block, self.threshold).unwrap_or_else(|| {
+					let sum: usize =
+						block.iter().map(|p| usize::from(self.ink(p))).sum();
+					get_char(self.palette.chars(), (sum / block.len()) as u8)
+				});
+				Pixel { c, b: None, f: None }
+			}
+		}
+	}
+}
+
+/// A cell showing the pixels of `block` in two colors, with the glyph for the
+/// mask of the foreground pixels.
+fn split_cell(block: &[Rgb<u8>], glyph: fn(u8) -> char) -> Pixel {
+	let (mask, fg, bg) = crate::pallete::split(block);
+
+	Pixel { c: glyph(mask), b: Some(bg), f: (mask != 0).then_some(fg) }
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			width: 80,
+			height: 24,
+			fit: Fit::Contain,
+			sampling: Sampling::Average,
+			filter: Filter::Lanczos3,
+			preserve_aspect: true,
+			mode: RenderMode::Blocks,
+			palette: Palette::Ascii10,
+			threshold: 128,
+			protocol: OutputProtocol::Text,
+			color_depth: ColorDepth::TrueColor,
+			dither: Dither::None,
+			grayscale: None,
+			brightness: 0.0,
+			contrast: 1.0,
+			gamma: 1.0,
+			saturation: 1.0,
+			invert_colors: false,
+			light_background: false,
+			background: (0, 0, 0),
+			letterbox: None,
+			crop: None,
+		}
+	}
+}
+
+/// Builds [`Options`], see [`Options::builder`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionsBuilder(Options);
+
+impl OptionsBuilder {
+	/// Number of cells per line.
+	pub fn width(mut self, width: u32) -> Self {
+		self.0.width = width;
+		self
+	}
+
+	/// Number of lines.
+	pub fn height(mut self, height: u32) -> Self {
+		self.0.height = height;
+		self
+	}
+
+	pub fn size(self, width: u32, height: u32) -> Self {
+		self.width(width).height(height)
+	}
+
+	pub fn fit(mut self, fit: Fit) -> Self {
+		self.0.fit = fit;
+		self
+	}
+
+	/// How the pixels sampled for the cells are picked from a larger
+	/// image, defaults to [`Sampling::Average`].
+	pub fn sampling(mut self, sampling: Sampling) -> Self {
+		self.0.sampling = sampling;
+		self
+	}
+
+	/// How the image is interpolated when enlarging it, or shrinking it
+	/// with [`Sampling::Resample`], defaults to [`Filter::Lanczos3`].
+	pub fn filter(mut self, filter: Filter) -> Self {
+		self.0.filter = filter;
+		self
+	}
+
+	/// Whether to account for cells being about twice as tall as wide,
+	/// which keeps circles round. Otherwise a pixel maps to a single cell.
+	///
+	/// Has no effect with [`Fit::Stretch`].
+	pub fn preserve_aspect(mut self, preserve_aspect: bool) -> Self {
+		self.0.preserve_aspect = preserve_aspect;
+		self
+	}
+
+	pub fn mode(mut self, mode: RenderMode) -> Self {
+		self.0.mode = mode;
+		self
+	}
+
+	/// Characters used by the ASCII render modes.
+	pub fn palette(mut self, palette: Palette) -> Self {
+		self.0.palette = palette;
+		self
+	}
+
+	/// Luminance from which [`RenderMode::Braille`] sets a dot, and contrast
+	/// from which [`RenderMode::Edges`] draws a line, defaults to `128`.
+	pub fn threshold(mut self, threshold: u8) -> Self {
+		self.0.threshold = threshold;
+		self
+	}
+
+	/// How [`render`] draws the image, defaults to
+	/// [`OutputProtocol::Text`].
+	pub fn protocol(mut self, protocol: OutputProtocol) -> Self {
+		self.0.protocol = protocol;
+		self
+	}
+
+	/// Colors written by [`TextImage::to_buffer`], defaults to
+	/// [`ColorDepth::TrueColor`].
+	pub fn color_depth(mut self, color_depth: ColorDepth) -> Self {
+		self.0.color_depth = color_depth;
+		self
+	}
+
+	/// How colors are reduced to the [color depth](Self::color_depth) and
+	/// pixels to braille dots, defaults to [`Dither::None`].
+	pub fn dither(mut self, dither: Dither) -> Self {
+		self.0.dither = dither;
+		self
+	}
+
+	/// Turns the image gray before rendering it, weighting red, green and
+	/// blue with `weights`, e.g. [`REC601`]. The weights are scaled to add
+	/// up to one.
+	pub fn grayscale(mut self, weights: [f32; 3]) -> Self {
+		self.0.grayscale = Some(weights);
+		self
+	}
+
+	/// Added to every channel as a fraction of the full range, e.g. `0.2`
+	/// lifts dark images. Defaults to `0.0`.
+	pub fn brightness(mut self, brightness: f32) -> Self {
+		self.0.brightness = brightness;
+		self
+	}
+
+	/// Factor stretching the channels away from medium gray, `0.0` leaves
+	/// only gray. Defaults to `1.0`.
+	pub fn contrast(mut self, contrast: f32) -> Self {
+		self.0.contrast = contrast;
+		self
+	}
+
+	/// Gamma correction, values above `1.0` brighten the dark parts while
+	/// keeping black and white. Defaults to `1.0`.
+	pub fn gamma(mut self, gamma: f32) -> Self {
+		self.0.gamma = gamma;
+		self
+	}
+
+	/// Factor of how far colors are from gray, `0.0` turns the image gray
+	/// and values above `1.0` make colors more vivid. Defaults to `1.0`.
+	pub fn saturation(mut self, saturation: f32) -> Self {
+		self.0.saturation = saturation;
+		self
+	}
+
+	/// Turns the colors into their negative, after the other adjustments.
+	pub fn invert_colors(mut self, invert_colors: bool) -> Self {
+		self.0.invert_colors = invert_colors;
+		self
+	}
+
+	/// Whether the terminal shows dark characters on a light background.
+	/// Then the ramp of the palette is used the other way around, with the
+	/// densest characters for the darkest pixels, and
+	/// [`RenderMode::Braille`] sets dots on the pixels darker than the
+	/// threshold. Doesn't change the colors, see
+	/// [`invert_colors`](Self::invert_colors).
+	pub fn light_background(mut self, light_background: bool) -> Self {
+		self.0.light_background = light_background;
+		self
+	}
+
+	/// Sets [`light_background`](Self::light_background) if the terminal
+	/// has a light background, see [`detect::light_background`]. Keeps the
+	/// current setting if that is unknown.
+	pub fn detect_background(self) -> Self {
+		match detect::light_background() {
+			Some(light) => self.light_background(light),
+			None => self,
+		}
+	}
+
+	/// Color translucent pixels are blended over, defaults to black. Fully
+	/// transparent cells stay empty instead.
+	pub fn background(mut self, color: (u8, u8, u8)) -> Self {
+		self.0.background = color;
+		self
+	}
+
+	/// Pads text images with [`Fit::Contain`] to all cells, centering the
+	/// image between cells showing `c` on `color`, or on the terminal
+	/// background for `None`. `c` should be a single cell wide. Lines up
+	/// thumbnails of different shapes in a grid.
+	pub fn letterbox(mut self, c: char, color: Option<(u8, u8, u8)>) -> Self {
+		self.0.letterbox = Some(Letterbox { fill: c, color });
+		self
+	}
+
+	/// Shows only the `width` x `height` pixels of the image from `(x, y)`,
+	/// before scaling it. Parts outside the image are left out.
+	pub fn crop(mut self, x: u32, y: u32, width: u32, height: u32) -> Self {
+		self.0.crop = Some((x, y, width, height));
+		self
+	}
+
+	/// Shows the image with `backend`, e.g. the one picked by [`detect`].
+	/// The graphics protocols replace the render mode, plain characters use
+	/// [`RenderMode::Ascii`].
+	pub fn backend(self, backend: Backend) -> Self {
+		match backend {
+			Backend::Kitty => self.protocol(OutputProtocol::Kitty),
+			Backend::Sixel => self.protocol(OutputProtocol::Sixel),
+			Backend::ITerm => self.protocol(OutputProtocol::ITerm),
+			Backend::TrueColor => self
+				.protocol(OutputProtocol::Text)
+				.color_depth(ColorDepth::TrueColor),
+			Backend::Colors256 => self
+				.protocol(OutputProtocol::Text)
+				.color_depth(ColorDepth::Ansi256),
+			Backend::Ascii => {
+				self.protocol(OutputProtocol::Text).mode(RenderMode::Ascii)
+			}
+		}
+	}
+
+	/// Shows the image with the best backend the terminal supports, see
+	/// [`detect`].
+	pub fn detect(self) -> Self {
+		self.backend(detect())
+	}
+
+	/// Uses the size of the terminal, leaving the last line free for the
+	/// prompt. Keeps the current size if no terminal is attached.
+	pub fn fit_terminal(self) -> Self {
+		match terminal_size::terminal_size() {
+			Some((Width(width), Height(height))) => {
+				self.size(width.into(), u32::from(height).saturating_sub(1))
+			}
+			None => self,
+		}
+	}
+
+	pub fn build(self) -> Options {
+		self.0
+	}
+}
+
+/// Writes `image` to `buffer` in the [protocol](OptionsBuilder::protocol) of
+/// `opts`, scaled into its cells.
+pub fn render(image: DynamicImage, opts: Options, buffer: &mut String) {
+	match opts.protocol {
+		OutputProtocol::Text => {
+			TextImage::from_image(image, opts).to_buffer(buffer)
+		}
+		OutputProtocol::Sixel => {
+			SixelImage::from_image(image, opts).to_buffer(buffer)
+		}
+		OutputProtocol::Kitty => {
+			KittyImage::from_image(image, opts).to_buffer(buffer)
+		}
+		OutputProtocol::ITerm => {
+			ITermImage::from_image(image, opts).to_buffer(buffer)
+		}
+	}
+}
+
+/// `image` in gray, with the luminance weighted by `weights`.
+fn grayscale(image: DynamicImage, weights: [f32; 3]) -> DynamicImage {
+	let sum: f32 = weights.iter().sum();
+	let weights = if sum > 0.0 { weights.map(|w| w / sum) } else { REC601 };
+
+	let mut image = image.into_rgba8();
+	for pixel in image.pixels_mut() {
+		let [r, g, b, a] = pixel.0;
+		let gray = [r, g, b]
+			.iter()
+			.zip(weights)
+			.map(|(&c, weight)| f32::from(c) * weight)
+			.sum::<f32>()
+			.round()
+			.clamp(0.0, 255.0) as u8;
+
+		pixel.0 = [gray, gray, gray, a];
+	}
+
+	DynamicImage::ImageRgba8(image)
+}
+
+/// The results of `row` for the lines `0` to `height`, in order. Spread over
+/// all cores with the `rayon` feature.
+fn map_rows<T: Send>(
+	height: u32,
+	row: impl Fn(u32) -> T + Send + Sync,
+) -> Vec<T> {
+	#[cfg(feature = "rayon")]
+	{
+		use rayon::prelude::*;
+
+		(0..height).into_par_iter().map(row).collect()
+	}
+
+	#[cfg(not(feature = "rayon"))]
+	{
+		(0..height).map(row).collect()
+	}
+}
+
+/// Encodes `image` as PNG for the protocols transmitting files.
+fn encode_png(image: DynamicImage) -> Vec<u8> {
+	let mut png = Vec::new();
+	DynamicImage::ImageRgba8(image.into_rgba8())
+		.write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+		.expect("encoding into memory doesn't fail");
+
+	png
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Pixel {
 	c: char,
 	b: Option<(u8, u8, u8)>,
 	f: Option<(u8, u8, u8)>,
 }
 
+/// Consecutive cells of a line sharing the same colors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyledRun {
+	text: String,
+	fg: Option<(u8, u8, u8)>,
+	bg: Option<(u8, u8, u8)>,
+}
+
+impl StyledRun {
+	pub fn text(&self) -> &str {
+		&self.text
+	}
+
+	pub fn fg(&self) -> Option<(u8, u8, u8)> {
+		self.fg
+	}
+
+	pub fn bg(&self) -> Option<(u8, u8, u8)> {
+		self.bg
+	}
+}
+
+impl Pixel {
+	fn quantized(self, depth: ColorDepth) -> Self {
+		Self {
+			b: self.b.map(|b| depth.quantize(b)),
+			f: self.f.map(|f| depth.quantize(f)),
+			..self
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TextImage {
 	width: u32,
 	height: u32,
 	pixels: Vec<Pixel>,
+	depth: ColorDepth,
 }
 
 impl TextImage {
 	pub fn from_image(image: DynamicImage, opts: Options) -> Self {
-		use crate::pallete::{get_char, luminance};
+		// Sampled at a multiple of the cell size for modes showing more
+		// than one pixel per cell.
+		let (cell_w, cell_h) = opts.mode.cell_size();
+		let Some(image) = opts.resize(image, (cell_w, cell_h)) else {
+			return Self {
+				width: 0,
+				height: 0,
+				pixels: Vec::new(),
+				depth: opts.color_depth,
+			};
+		};
+		let (width, height) =
+			(image.width() / cell_w, image.height() / cell_h);
+		let image = image.into_rgba8();
+		let mut colors = opts.blend(&image);
+		opts.dither_image(&mut colors);
 
-		let Options { width, height } = opts;
+		let rows = map_rows(height, |y| {
+			let mut block = Vec::with_capacity((cell_w * cell_h) as usize);
 
-		let image = image.thumbnail_exact(width, height);
-		let image = image.into_rgb8();
+			(0..width)
+				.map(|x| {
+					block.clear();
+					let mut opaque = false;
+					for dy in 0..cell_h {
+						for dx in 0..cell_w {
+							let (px, py) = (x * cell_w + dx, y * cell_h + dy);
+							block.push(*colors.get_pixel(px, py));
+							opaque |= image.get_pixel(px, py).0[3] != 0;
+						}
+					}
 
-		let mut pixels: Vec<Pixel> =
-			Vec::with_capacity(width as usize * height as usize);
+					// Cells without anything visible let the terminal show
+					// through.
+					if !opaque {
+						return Pixel { c: ' ', b: None, f: None };
+					}
+
+					// Reduced right away, so runs merge cells which end up
+					// in the same color.
+					opts.cell(&block).quantized(opts.color_depth)
+				})
+				.collect::<Vec<_>>()
+		});
+		let pixels = rows.concat();
+
+		let image = Self { width, height, pixels, depth: opts.color_depth };
+		match opts.letterbox {
+			Some(letterbox) if opts.fit == Fit::Contain => {
+				image.letterbox(opts.width, opts.height, letterbox)
+			}
+			_ => image,
+		}
+	}
+
+	/// Centers the image in `width` x `height` cells, which must be at
+	/// least its size, and fills the others with `letterbox`.
+	fn letterbox(
+		self,
+		width: u32,
+		height: u32,
+		Letterbox { fill: c, color: bg }: Letterbox,
+	) -> Self {
+		let (left, top) =
+			((width - self.width) / 2, (height - self.height) / 2);
+		let (columns, rows) =
+			(left..left + self.width, top..top + self.height);
+		let bg = bg.map(|bg| self.depth.quantize(bg));
 
+		let mut inner = self.pixels.into_iter();
+		let mut pixels = Vec::with_capacity(width as usize * height as usize);
 		for y in 0..height {
 			for x in 0..width {
-				let p = image.get_pixel(x, y);
-				let color = rgb(p);
-				let b = Some(color);
-				let f = None;
-
-				let c = if f.is_some() {
-					let l = luminance(p);
-					get_char(l)
+				let pixel = if columns.contains(&x) && rows.contains(&y) {
+					inner.next()
 				} else {
-					' '
+					None
 				};
+				pixels.push(pixel.unwrap_or(Pixel { c, b: bg, f: None }));
+			}
+		}
+
+		Self { width, height, pixels, depth: self.depth }
+	}
+
+	/// Number of cells per line.
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	/// Number of lines.
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	fn idx(&self, x: u32, y: u32) -> usize {
+		y as usize * self.width as usize + x as usize
+	}
+
+	/// Blanks the double-width character partially covering `(x, y)`, so
+	/// overwriting one of its halves doesn't leave a broken glyph behind.
+	fn break_wide(&mut self, x: u32, y: u32) {
+		let idx = self.idx(x, y);
+
+		if self.pixels[idx].c == WIDE_TAIL {
+			self.pixels[idx].c = ' ';
+			if x > 0 {
+				self.pixels[idx - 1].c = ' ';
+			}
+		} else if x + 1 < self.width && self.pixels[idx + 1].c == WIDE_TAIL {
+			self.pixels[idx + 1].c = ' ';
+		}
+	}
+
+	/// Writes `text` into row `y` starting at column `x`, keeping the
+	/// background of the covered cells.
+	///
+	/// Double-width characters cover two cells. Text running past the right
+	/// edge is cut off, see [`text::truncate`] for shortening it beforehand.
+	pub fn draw_text(
+		&mut self,
+		x: u32,
+		y: u32,
+		text: &str,
+		fg: Option<(u8, u8, u8)>,
+	) {
+		if y >= self.height {
+			return;
+		}
+
+		let mut x = x;
+
+		for c in text.chars() {
+			let width = text::char_width(c) as u32;
+
+			if width == 0 {
+				continue;
+			}
+
+			if x + width > self.width {
+				// Half of a wide character would stick out, fill the last
+				// cell instead.
+				if x < self.width {
+					self.break_wide(x, y);
+					let idx = self.idx(x, y);
+					self.pixels[idx].c = ' ';
+				}
+				break;
+			}
+
+			for offset in 0..width {
+				self.break_wide(x + offset, y);
+			}
+
+			let idx = self.idx(x, y);
+			self.pixels[idx].c = c;
+			self.pixels[idx].f = fg;
+
+			if width == 2 {
+				self.pixels[idx + 1].c = WIDE_TAIL;
+				self.pixels[idx + 1].f = fg;
+			}
+
+			x += width;
+		}
+	}
+
+	/// Returns the runs of a single line, merging neighbouring cells with
+	/// identical colors.
+	pub fn line(&self, y: u32) -> Vec<StyledRun> {
+		self.runs(y, 0..self.width)
+	}
+
+	/// The runs of the cells `columns` of line `y`.
+	fn runs(&self, y: u32, columns: Range<u32>) -> Vec<StyledRun> {
+		let mut runs: Vec<StyledRun> = Vec::new();
 
-				pixels.push(Pixel { c, b, f });
+		for x in columns {
+			let Pixel { c, b, f } = self.pixels[self.idx(x, y)];
+
+			// Already covered by the preceding double-width character.
+			if c == WIDE_TAIL {
+				continue;
+			}
+
+			match runs.last_mut() {
+				Some(run) if run.fg == f && run.bg == b => run.text.push(c),
+				_ => runs.push(StyledRun { text: c.into(), fg: f, bg: b }),
 			}
 		}
 
-		Self { width, height, pixels }
+		runs
+	}
+
+	/// Returns the runs of every line, top to bottom.
+	pub fn lines(&self) -> Vec<Vec<StyledRun>> {
+		(0..self.height).map(|y| self.line(y)).collect()
 	}
 
 	pub fn to_buffer(&self, buffer: &mut String) {
+		// Writing into a string never fails.
+		let _ = self.write_text(buffer);
+	}
+
+	/// Streams the image into `writer` like [`TextImage::to_buffer`],
+	/// without building the whole output in memory first. Buffers the
+	/// writes on its own.
+	pub fn write_to(&self, writer: &mut impl io::Write) -> io::Result<()> {
+		write_io(writer, |out| self.write_text(out))
+	}
+
+	fn write_text(&self, out: &mut impl fmt::Write) -> fmt::Result {
+		// Formats all lines at once, and writes them in order.
+		#[cfg(feature = "rayon")]
+		for line in map_rows(self.height, |y| {
+			let mut line = String::new();
+			let _ = self.write_line(&mut line, y);
+			line
+		}) {
+			out.write_str(&line)?;
+		}
+
+		#[cfg(not(feature = "rayon"))]
 		for y in 0..self.height {
-			for x in 0..self.width {
-				let idx = y as usize * self.width as usize + x as usize;
-				let Pixel { c, b, f } = self.pixels[idx];
-
-				match (f, b) {
-					(Some((fr, fg, fb)), Some((br, bg, bb))) => {
-						let s = format!("{}", c);
-
-						buffer.push_str(&format!(
-							"{}",
-							s.truecolor(fr, fg, fb).on_truecolor(br, bg, bb)
-						));
-					}
-					(Some((r, g, b)), None) => {
-						let s = format!("{}", c);
+			self.write_line(out, y)?;
+		}
 
-						buffer.push_str(&format!("{}", s.truecolor(r, g, b)));
-					}
-					(None, Some((r, g, b))) => {
-						let s = format!("{}", c);
+		Ok(())
+	}
 
-						buffer
-							.push_str(&format!("{}", s.on_truecolor(r, g, b)));
-					}
-					_ => {
-						buffer.push(c);
-					}
-				};
+	fn write_line(&self, out: &mut impl fmt::Write, y: u32) -> fmt::Result {
+		self.write_runs(out, self.line(y))?;
+		out.write_str("\r\n")
+	}
+
+	/// Writes `runs` from the cursor on, leaving the colors reset.
+	fn write_runs(
+		&self,
+		out: &mut impl fmt::Write,
+		runs: Vec<StyledRun>,
+	) -> fmt::Result {
+		// Colors carry over from run to run, and are only set again where
+		// they change.
+		let mut current = (None, None);
+
+		for StyledRun { text, fg, bg } in runs {
+			// Unsetting one of them takes a full reset.
+			if (current.0.is_some() && fg.is_none())
+				|| (current.1.is_some() && bg.is_none())
+			{
+				out.write_str("\x1b[0m")?;
+				current = (None, None);
+			}
+
+			let changes = [(fg, current.0, false), (bg, current.1, true)]
+				.into_iter()
+				.filter(|(color, before, _)| color != before)
+				.filter_map(|(color, _, background)| {
+					Some((color?, background))
+				});
+			for (idx, (color, background)) in changes.enumerate() {
+				out.write_str(if idx == 0 { "\x1b[" } else { ";" })?;
+				self.depth.push_sgr(out, color, background)?;
+			}
+			if (fg, bg) != current {
+				out.write_str("m")?;
 			}
 
-			buffer.push('\r');
-			buffer.push('\n');
+			current = (fg, bg);
+			out.write_str(&text)?;
+		}
+
+		// Resets before any line break, which would otherwise fill the
+		// next line with the background when the terminal scrolls.
+		if current != (None, None) {
+			out.write_str("\x1b[0m")?;
+		}
+
+		Ok(())
+	}
+}
+
+impl fmt::Display for TextImage {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.write_text(f)
+	}
+}
+
+/// Passes formatted text on to `writer`, keeping the error formatting can't
+/// carry.
+struct IoWriter<W> {
+	writer: W,
+	error: Option<io::Error>,
+}
+
+/// Runs `write` on a buffered `writer`, returning the error of `writer` if
+/// it failed.
+fn write_io<W: io::Write>(
+	writer: W,
+	write: impl FnOnce(&mut IoWriter<io::BufWriter<W>>) -> fmt::Result,
+) -> io::Result<()> {
+	let mut out = IoWriter { writer: io::BufWriter::new(writer), error: None };
+
+	match write(&mut out) {
+		Ok(()) => out.writer.flush(),
+		Err(fmt::Error) => Err(out
+			.error
+			.unwrap_or_else(|| io::Error::other("formatting failed"))),
+	}
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.writer.write_all(s.as_bytes()).map_err(|error| {
+			self.error = Some(error);
+			fmt::Error
+		})
+	}
+}
+
+#[test]
+fn draw_text_wide() {
+	let mut img = TextImage {
+		width: 5,
+		height: 1,
+		pixels: (0..5).map(|_| Pixel { c: ' ', b: None, f: None }).collect(),
+		depth: ColorDepth::TrueColor,
+	};
+	let row = |img: &TextImage| -> String {
+		let mut buf = String::new();
+		img.to_buffer(&mut buf);
+		buf
+	};
+
+	img.draw_text(0, 0, "巨人x", None);
+	assert_eq!(row(&img), "巨人x\r\n");
+
+	// Overwriting the second half of `巨` blanks the first half.
+	img.draw_text(1, 0, "a", None);
+	assert_eq!(row(&img), " a人x\r\n");
+
+	// `人` does not fit into the last cell.
+	img.draw_text(3, 0, "b人", None);
+	assert_eq!(row(&img), " a b \r\n");
+}
+
+#[test]
+fn text_image_write_to() {
+	let opts = Options::builder().size(4, 2).mode(RenderMode::Ascii).build();
+	let img = TextImage::from_image(DynamicImage::new_rgb8(8, 8), opts);
+
+	let mut buffer = String::new();
+	img.to_buffer(&mut buffer);
+	let mut written = Vec::new();
+	img.write_to(&mut written).unwrap();
+
+	assert_eq!(String::from_utf8(written).unwrap(), buffer);
+	assert_eq!(img.to_string(), buffer);
+	assert_eq!(buffer, "    \r\n    \r\n");
+}
+
+#[test]
+fn ansi_coalesced() {
+	let (red, blue) = (Some((255, 0, 0)), Some((0, 0, 255)));
+	let img = TextImage {
+		width: 4,
+		height: 2,
+		pixels: vec![
+			Pixel { c: 'a', b: red, f: None },
+			Pixel { c: 'b', b: red, f: blue },
+			Pixel { c: 'c', b: None, f: blue },
+			Pixel { c: 'd', b: None, f: None },
+			Pixel { c: 'e', b: red, f: None },
+			Pixel { c: 'f', b: red, f: None },
+			Pixel { c: 'g', b: red, f: None },
+			Pixel { c: 'h', b: red, f: None },
+		],
+		depth: ColorDepth::TrueColor,
+	};
+
+	assert_eq!(
+		img.to_string(),
+		concat!(
+			"\x1b[48;2;255;0;0ma\x1b[38;2;0;0;255mb\x1b[0m",
+			"\x1b[38;2;0;0;255mc\x1b[0md\r\n",
+			"\x1b[48;2;255;0;0mefgh\x1b[0m\r\n",
+		)
+	);
+}
+
+#[test]
+fn lines_merge_runs() {
+	let red = Some((255, 0, 0));
+	let img = TextImage {
+		width: 4,
+		height: 1,
+		pixels: vec![
+			Pixel { c: 'a', b: red, f: None },
+			Pixel { c: 'b', b: red, f: None },
+			Pixel { c: 'c', b: None, f: red },
+			Pixel { c: 'd', b: red, f: None },
+		],
+		depth: ColorDepth::TrueColor,
+	};
+
+	let runs: Vec<_> =
+		img.line(0).iter().map(|r| r.text().to_owned()).collect();
+	assert_eq!(runs, ["ab", "c", "d"]);
+	assert_eq!(img.lines()[0][0].bg(), red);
+}
+
+#[test]
+fn options_fit() {
+	let image = DynamicImage::new_rgb8(100, 50);
+	let size = |opts: OptionsBuilder| {
+		let img = TextImage::from_image(image.clone(), opts.build());
+		(img.width(), img.height())
+	};
+	let opts = Options::builder().size(40, 40);
+
+	assert_eq!(size(opts.clone()), (40, 10));
+	assert_eq!(size(opts.clone().preserve_aspect(false)), (40, 20));
+	assert_eq!(size(opts.clone().fit(Fit::Cover)), (40, 40));
+	assert_eq!(size(opts.clone().fit(Fit::Stretch)), (40, 40));
+	assert_eq!(size(opts.clone().height(4)), (16, 4));
+	assert_eq!(size(opts.clone().width(0)), (0, 0));
+
+	let tall = DynamicImage::new_rgb8(10, 400);
+	let img =
+		TextImage::from_image(tall, Options::builder().size(8, 4).build());
+	assert_eq!((img.width(), img.height()), (1, 4));
+}
+
+#[test]
+fn options_letterbox() {
+	// Scaled to 2 x 4 cells.
+	let image =
+		DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 2, Rgb([255, 0, 0])));
+	let opts = Options::builder()
+		.size(4, 4)
+		.preserve_aspect(false)
+		.letterbox('.', Some((0, 0, 255)));
+
+	let img = TextImage::from_image(image.clone(), opts.clone().build());
+	assert_eq!((img.width(), img.height()), (4, 4));
+	let blue = Some((0, 0, 255));
+	for y in 0..4 {
+		let cells: Vec<_> = img
+			.line(y)
+			.iter()
+			.map(|run| (run.text().to_owned(), run.bg()))
+			.collect();
+		assert_eq!(
+			cells,
+			[
+				(".".to_owned(), blue),
+				("  ".to_owned(), Some((255, 0, 0))),
+				(".".to_owned(), blue),
+			]
+		);
+	}
+
+	// Only images fit inside are padded.
+	let img = TextImage::from_image(image, opts.fit(Fit::Cover).build());
+	assert_eq!((img.width(), img.height()), (4, 4));
+	assert!(img.line(0).iter().all(|run| run.bg() == Some((255, 0, 0))));
+}
+
+#[test]
+fn options_filter() {
+	// Stripes shrunk to half their width.
+	let image = RgbImage::from_fn(8, 1, |x, _| Rgb([(x % 2 * 255) as u8; 3]));
+	let render = |filter| {
+		let opts = Options::builder()
+			.size(4, 1)
+			.fit(Fit::Stretch)
+			.sampling(Sampling::Resample)
+			.filter(filter)
+			.build();
+		let img = TextImage::from_image(
+			DynamicImage::ImageRgb8(image.clone()),
+			opts,
+		);
+		img.line(0).iter().filter_map(|run| run.bg()).collect::<Vec<_>>()
+	};
+
+	let nearest = render(Filter::Nearest);
+	assert!(nearest.iter().all(|&(r, _, _)| r == 0 || r == 255));
+	let triangle = render(Filter::Triangle);
+	assert!(triangle.iter().all(|&(r, _, _)| r > 64 && r < 192));
+}
+
+#[test]
+fn options_crop() {
+	let mut image = RgbImage::new(4, 4);
+	image.put_pixel(2, 1, Rgb([255, 0, 0]));
+	image.put_pixel(3, 1, Rgb([0, 255, 0]));
+
+	let opts = Options::builder()
+		.size(10, 10)
+		.preserve_aspect(false)
+		.crop(2, 1, 2, 1)
+		.build();
+	let img = TextImage::from_image(DynamicImage::ImageRgb8(image), opts);
+	assert_eq!((img.width(), img.height()), (10, 5));
+}
+
+#[test]
+fn render_modes() {
+	let mut image = RgbImage::new(3, 1);
+	image.put_pixel(1, 0, Rgb([128, 128, 128]));
+	image.put_pixel(2, 0, Rgb([255, 0, 0]));
+	let render = |mode| {
+		let opts =
+			Options::builder().size(3, 1).fit(Fit::Stretch).mode(mode).build();
+		TextImage::from_image(DynamicImage::ImageRgb8(image.clone()), opts)
+			.line(0)
+	};
+
+	let blocks = render(RenderMode::Blocks);
+	assert_eq!(blocks.len(), 3);
+	assert_eq!(blocks[0].text(), " ");
+	assert_eq!(blocks[2].bg(), Some((255, 0, 0)));
+	assert_eq!(blocks[2].fg(), None);
+
+	let ascii = render(RenderMode::Ascii);
+	assert_eq!(ascii.len(), 1);
+	assert_eq!(ascii[0].text(), " o;");
+	assert_eq!((ascii[0].fg(), ascii[0].bg()), (None, None));
+
+	let colored = render(RenderMode::AsciiColored);
+	let cells: Vec<_> =
+		colored.iter().map(|run| (run.text(), run.fg(), run.bg())).collect();
+	assert_eq!(
+		cells,
+		[
+			(" ", Some((0, 0, 0)), None),
+			("o", Some((128, 128, 128)), None),
+			(";", Some((255, 0, 0)), None),
+		]
+	);
+}
+
+#[test]
+fn braille_mode() {
+	// The left cell is lit completely, the right one in its left column
+	// and the top right pixel, which is too dark.
+	let mut image = RgbImage::new(4, 4);
+	for (x, y, pixel) in image.enumerate_pixels_mut() {
+		if x <= 2 {
+			*pixel = Rgb([255, 255, 255]);
+		} else if y == 0 {
+			*pixel = Rgb([100, 100, 100]);
 		}
 	}
+
+	let opts = Options::builder()
+		.size(2, 1)
+		.fit(Fit::Stretch)
+		.mode(RenderMode::Braille)
+		.build();
+	let img = TextImage::from_image(DynamicImage::ImageRgb8(image), opts);
+
+	assert_eq!(img.line(0)[0].text(), "⣿⡇");
+	assert_eq!(crate::pallete::braille([false; 8]), '\u{2800}');
+
+	let sized = TextImage::from_image(
+		DynamicImage::new_rgb8(100, 50),
+		Options::builder().size(40, 40).mode(RenderMode::Braille).build(),
+	);
+	assert_eq!((sized.width(), sized.height()), (40, 10));
+}
+
+#[test]
+fn half_blocks_mode() {
+	let mut image = RgbImage::new(2, 2);
+	image.put_pixel(0, 0, Rgb([255, 0, 0]));
+	image.put_pixel(1, 1, Rgb([0, 0, 255]));
+
+	let opts = Options::builder()
+		.size(2, 1)
+		.fit(Fit::Stretch)
+		.mode(RenderMode::HalfBlocks)
+		.build();
+	let img = TextImage::from_image(DynamicImage::ImageRgb8(image), opts);
+	let line = img.line(0);
+
+	assert_eq!(line.len(), 2);
+	assert_eq!(line[0].text(), "▀");
+	assert_eq!(line[0].fg(), Some((255, 0, 0)));
+	assert_eq!(line[0].bg(), Some((0, 0, 0)));
+	assert_eq!(line[1].fg(), Some((0, 0, 0)));
+	assert_eq!(line[1].bg(), Some((0, 0, 255)));
+
+	// Two square pixels per cell keep the aspect of the image.
+	let sized = TextImage::from_image(
+		DynamicImage::new_rgb8(100, 50),
+		Options::builder().size(40, 40).mode(RenderMode::HalfBlocks).build(),
+	);
+	assert_eq!((sized.width(), sized.height()), (40, 10));
+}
+
+#[test]
+fn split_modes() {
+	use crate::pallete::sextant;
+
+	let (red, blue) = (Rgb([255, 0, 0]), Rgb([0, 0, 255]));
+	let render = |image: RgbImage, mode| {
+		let size = (image.width() / 2, 1);
+		let opts = Options::builder()
+			.size(size.0, size.1)
+			.fit(Fit::Stretch)
+			.mode(mode)
+			.build();
+		TextImage::from_image(DynamicImage::ImageRgb8(image), opts).line(0)
+	};
+
+	// Red on top of blue, and a single color.
+	let mut image = RgbImage::from_pixel(4, 2, red);
+	image.put_pixel(0, 1, blue);
+	image.put_pixel(1, 1, blue);
+	let line = render(image, RenderMode::Quadrants);
+	assert_eq!(line[0].text(), "▄");
+	assert_eq!(line[0].fg(), Some((0, 0, 255)));
+	assert_eq!(line[0].bg(), Some((255, 0, 0)));
+	assert_eq!(line[1].text(), " ");
+	assert_eq!(line[1].fg(), None);
+	assert_eq!(line[1].bg(), Some((255, 0, 0)));
+
+	// The closest shape, averaging the colors of its pixels.
+	let mut image = RgbImage::new(2, 3);
+	image.put_pixel(1, 2, Rgb([200, 200, 200]));
+	image.put_pixel(0, 2, Rgb([10, 10, 10]));
+	let line = render(image, RenderMode::Sextants);
+	assert_eq!(line[0].text(), "\u{1fb1e}");
+	assert_eq!(line[0].fg(), Some((200, 200, 200)));
+	assert_eq!(line[0].bg(), Some((2, 2, 2)));
+
+	let sextants: std::collections::HashSet<_> =
+		(0..64).map(sextant).collect();
+	assert_eq!(sextants.len(), 64);
+	assert_eq!(sextant(0b10_1010), '▐');
+	assert_eq!(sextant(0b01_0110), '\u{1fb14}');
+}
+
+#[test]
+fn block_art_mode() {
+	use crate::pallete::BLOCK_ELEMENTS;
+
+	let (red, blue) = (Rgb([255, 0, 0]), Rgb([0, 0, 255]));
+	// Blue in the bottom three rows, the left column and nowhere.
+	let image = RgbImage::from_fn(24, 8, |x, y| match x / 8 {
+		0 if y >= 5 => blue,
+		1 if x % 8 == 0 => blue,
+		_ => red,
+	});
+	let opts = Options::builder()
+		.size(3, 1)
+		.fit(Fit::Stretch)
+		.mode(RenderMode::BlockArt)
+		.build();
+	let line =
+		TextImage::from_image(DynamicImage::ImageRgb8(image), opts).line(0);
+	let cells: Vec<_> =
+		line.iter().map(|run| (run.text(), run.fg(), run.bg())).collect();
+	assert_eq!(
+		cells,
+		[
+			("▃▏", Some((0, 0, 255)), Some((255, 0, 0))),
+			(" ", None, Some((255, 0, 0))),
+		]
+	);
+
+	let masks: std::collections::HashSet<_> =
+		BLOCK_ELEMENTS.iter().map(|&(mask, _)| mask).collect();
+	assert_eq!(masks.len(), BLOCK_ELEMENTS.len());
+	assert_eq!(BLOCK_ELEMENTS[1].0, 0xffff_ffff);
+}
+
+#[test]
+fn palettes() {
+	use crate::pallete::{get_char, ASCII70};
+
+	for palette in [Palette::Ascii10, Palette::Ascii70, Palette::Blocks] {
+		let chars = palette.chars();
+		assert_eq!(get_char(chars, 0), ' ');
+		assert_eq!(get_char(chars, 255), chars[chars.len() - 1]);
+	}
+	assert_eq!(ASCII70.len(), 70);
+	assert_eq!(get_char(Palette::Blocks.chars(), 128), '▒');
+
+	let ramp = Palette::custom(vec!['a', 'b']);
+	assert_eq!(get_char(ramp.chars(), 127), 'a');
+	assert_eq!(get_char(ramp.chars(), 128), 'b');
+	assert_eq!(
+		get_char(Palette::custom(Vec::<char>::new()).chars(), 200),
+		' '
+	);
+
+	let image = DynamicImage::new_rgb8(1, 1).brighten(255);
+	let opts = Options::builder()
+		.size(1, 1)
+		.mode(RenderMode::Ascii)
+		.palette(Palette::Blocks)
+		.build();
+	assert_eq!(TextImage::from_image(image, opts).line(0)[0].text(), "█");
+}
+
+#[test]
+fn color_depths() {
+	let mut image = RgbImage::from_pixel(2, 1, Rgb([250, 5, 5]));
+	image.put_pixel(1, 0, Rgb([255, 0, 0]));
+	let render = |depth| {
+		let opts = Options::builder()
+			.size(2, 1)
+			.fit(Fit::Stretch)
+			.color_depth(depth)
+			.build();
+		let img = TextImage::from_image(
+			DynamicImage::ImageRgb8(image.clone()),
+			opts,
+		);
+
+		let mut buffer = String::new();
+		img.to_buffer(&mut buffer);
+		(img.lines(), buffer)
+	};
+
+	// Both reduce to the same color, so they share a run.
+	let (lines, buffer) = render(ColorDepth::Ansi256);
+	assert_eq!(lines[0].len(), 1);
+	assert_eq!(lines[0][0].bg(), Some((255, 0, 0)));
+	assert_eq!(buffer, "\x1b[48;5;196m  \x1b[0m\r\n");
+
+	let (_, buffer) = render(ColorDepth::Ansi16);
+	assert_eq!(buffer, "\x1b[101m  \x1b[0m\r\n");
+
+	let (lines, _) = render(ColorDepth::TrueColor);
+	assert_eq!(lines[0].len(), 2);
+}
+
+#[test]
+fn edges_mode() {
+	// White pixels of the cells, left to right: the right two columns, the
+	// bottom row, the lower right and lower left triangles, everything.
+	let cells: [[u8; 9]; 5] = [
+		[0, 1, 1, 0, 1, 1, 0, 1, 1],
+		[0, 0, 0, 0, 0, 0, 1, 1, 1],
+		[0, 0, 1, 0, 1, 1, 1, 1, 1],
+		[1, 0, 0, 1, 1, 0, 1, 1, 1],
+		[1; 9],
+	];
+	let image = RgbImage::from_fn(15, 3, |x, y| {
+		let white = cells[x as usize / 3][(y * 3 + x % 3) as usize];
+		Rgb([white * 255; 3])
+	});
+
+	let opts = Options::builder()
+		.size(5, 1)
+		.fit(Fit::Stretch)
+		.mode(RenderMode::Edges)
+		.build();
+	let line =
+		TextImage::from_image(DynamicImage::ImageRgb8(image), opts).line(0);
+	assert_eq!(line.len(), 1);
+	assert_eq!(line[0].text(), "|_/\\#");
+	assert_eq!((line[0].fg(), line[0].bg()), (None, None));
+}
+
+#[test]
+fn dithered_braille() {
+	let render = |dither| {
+		let opts = Options::builder()
+			.size(4, 2)
+			.fit(Fit::Stretch)
+			.mode(RenderMode::Braille)
+			.dither(dither)
+			.build();
+		let image = RgbImage::from_pixel(8, 8, Rgb([100; 3]));
+		let img = TextImage::from_image(DynamicImage::ImageRgb8(image), opts);
+
+		img.lines().concat().iter().map(|run| run.text().to_owned()).collect()
+	};
+
+	// Too dark for any dot without dithering.
+	let plain: String = render(Dither::None);
+	assert!(plain.chars().all(|c| c == '\u{2800}'));
+	let dithered: String = render(Dither::FloydSteinberg);
+	assert!(dithered.chars().any(|c| c != '\u{2800}'));
+}
+
+#[test]
+fn grayscale_option() {
+	let render = |weights| {
+		let opts = Options::builder()
+			.size(1, 1)
+			.fit(Fit::Stretch)
+			.grayscale(weights)
+			.build();
+		let image = RgbImage::from_pixel(1, 1, Rgb([255, 0, 0]));
+		let img = TextImage::from_image(DynamicImage::ImageRgb8(image), opts);
+		img.line(0)[0].bg()
+	};
+
+	assert_eq!(render(REC601), Some((76, 76, 76)));
+	assert_eq!(render(REC709), Some((54, 54, 54)));
+	// Scaled to add up to one.
+	assert_eq!(render([2.0, 1.0, 1.0]), Some((128, 128, 128)));
+}
+
+#[test]
+fn adjust_levels() {
+	let render = |color, builder: fn(OptionsBuilder) -> OptionsBuilder| {
+		let opts = builder(Options::builder().size(1, 1).fit(Fit::Stretch));
+		let image = RgbImage::from_pixel(1, 1, Rgb(color));
+		let img = TextImage::from_image(
+			DynamicImage::ImageRgb8(image),
+			opts.build(),
+		);
+		img.line(0)[0].bg()
+	};
+
+	assert_eq!(render([10, 20, 30], |b| b), Some((10, 20, 30)));
+	assert_eq!(
+		render([0, 100, 250], |b| b.brightness(0.2)),
+		Some((51, 151, 255))
+	);
+	assert_eq!(
+		render([64, 128, 192], |b| b.contrast(0.0)),
+		Some((128, 128, 128))
+	);
+	assert_eq!(
+		render([64, 128, 192], |b| b.contrast(1.5)),
+		Some((32, 128, 224))
+	);
+	assert_eq!(render([0, 64, 255], |b| b.gamma(2.0)), Some((0, 128, 255)));
+	assert_eq!(render([255, 0, 0], |b| b.saturation(0.0)), Some((76, 76, 76)));
+	assert_eq!(
+		render([200, 100, 100], |b| b.saturation(2.0)),
+		Some((255, 70, 70))
+	);
+}
+
+#[test]
+fn light_background() {
+	// Black on the left, white on the right.
+	let render = |(width, height), cells, mode, light| {
+		let image = RgbImage::from_fn(width, height, |x, _| {
+			Rgb([if x < width / 2 { 0 } else { 255 }; 3])
+		});
+		let opts = Options::builder()
+			.size(cells, 1)
+			.fit(Fit::Stretch)
+			.mode(mode)
+			.light_background(light)
+			.build();
+		TextImage::from_image(DynamicImage::ImageRgb8(image), opts).line(0)[0]
+			.text()
+			.to_owned()
+	};
+
+	assert_eq!(render((2, 1), 2, RenderMode::Ascii, false), " #");
+	assert_eq!(render((2, 1), 2, RenderMode::Ascii, true), "# ");
+	// The dots of the black column.
+	assert_eq!(render((2, 4), 1, RenderMode::Braille, true), "⡇");
+	assert_eq!(render((2, 4), 1, RenderMode::Braille, false), "⢸");
+
+	let opts = Options::builder()
+		.size(1, 1)
+		.fit(Fit::Stretch)
+		.invert_colors(true)
+		.build();
+	let image = RgbImage::from_pixel(1, 1, Rgb([10, 20, 30]));
+	let img = TextImage::from_image(DynamicImage::ImageRgb8(image), opts);
+	assert_eq!(img.line(0)[0].bg(), Some((245, 235, 225)));
+}
+
+#[test]
+fn alpha_blend() {
+	use image::Rgba;
+
+	let mut image = RgbaImage::new(3, 1);
+	image.put_pixel(1, 0, Rgba([255, 0, 0, 128]));
+	image.put_pixel(2, 0, Rgba([0, 255, 0, 255]));
+
+	let opts = Options::builder()
+		.size(3, 1)
+		.fit(Fit::Stretch)
+		.background((0, 0, 255))
+		.build();
+	let img = TextImage::from_image(DynamicImage::ImageRgba8(image), opts);
+	let runs: Vec<_> = img
+		.line(0)
+		.iter()
+		.map(|run| (run.text().to_owned(), run.bg()))
+		.collect();
+
+	assert_eq!(
+		runs,
+		[
+			(" ".to_owned(), None),
+			(" ".to_owned(), Some((128, 0, 127))),
+			(" ".to_owned(), Some((0, 255, 0))),
+		]
+	);
+}
+
+#[test]
+fn options_backend() {
+	let opts = Options::builder().backend(Backend::Sixel).build();
+	assert_eq!(opts.protocol, OutputProtocol::Sixel);
+
+	let opts = Options::builder()
+		.protocol(OutputProtocol::Kitty)
+		.backend(Backend::Ascii)
+		.build();
+	assert_eq!(opts.protocol, OutputProtocol::Text);
+	assert_eq!(opts.mode, RenderMode::Ascii);
+
+	let opts = Options::builder().backend(Backend::Colors256).build();
+	assert_eq!(opts.color_depth, ColorDepth::Ansi256);
 }
 
 #[test]
 fn img() {
 	use std::io::Write as _;
 
-	use terminal_size::{terminal_size, Height, Width};
-	let (Width(w), Height(h)) = terminal_size().unwrap();
-	let (width, height) = (w as u32 / 2, h as u32);
-
-	println!("{:?}", (width, height));
+	let opts = Options::builder().fit_terminal().build();
+	println!("{:?}", (opts.width(), opts.height()));
 
 	let img = image::open("assets/tux.png").unwrap();
-	let img = TextImage::from_image(img, Options { width, height });
+	let img = TextImage::from_image(img, opts);
 
 	let mut buf = String::new();
 	img.to_buffer(&mut buf);
 
 	let mut stdout = std::io::stdout();
-	stdout.write(buf.as_bytes()).unwrap();
+	stdout.write_all(buf.as_bytes()).unwrap();
 	stdout.flush().unwrap();
 }
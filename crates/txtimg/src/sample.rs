@@ -0,0 +1,149 @@
+//! Shrinking images by the regions of the source each pixel covers.
+
+use std::collections::BTreeMap;
+
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use crate::{map_rows, Filter};
+
+/// How the pixels sampled for a cell are picked from the image when it
+/// shrinks. Enlarging always interpolates with the [`Filter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Sampling {
+	/// The average of all pixels a sampled pixel covers. Keeps thin lines
+	/// and small details which filters with a narrow window skip.
+	#[default]
+	Average,
+	/// The most common color of the pixels a sampled pixel covers, keeping
+	/// edges sharp and colors pure, e.g. for pixel art and flat logos.
+	Dominant,
+	/// Interpolates with the [`Filter`] only.
+	Resample,
+}
+
+impl Sampling {
+	/// Scales `image` to exactly `width` x `height` pixels.
+	pub(crate) fn resize(
+		self,
+		image: DynamicImage,
+		(width, height): (u32, u32),
+		filter: Filter,
+	) -> DynamicImage {
+		if (image.width(), image.height()) == (width, height) {
+			return image;
+		}
+
+		let shrinks = image.width() >= width && image.height() >= height;
+		let pick: fn(&[Rgba<u8>]) -> Rgba<u8> = match self {
+			Self::Average if shrinks => average,
+			Self::Dominant if shrinks => dominant,
+			_ => return image.resize_exact(width, height, filter.into()),
+		};
+
+		DynamicImage::ImageRgba8(shrink(
+			&image.into_rgba8(),
+			width,
+			height,
+			pick,
+		))
+	}
+}
+
+/// Scales `image` down to `width` x `height` pixels, each `pick`ed from the
+/// pixels of the region it covers.
+fn shrink(
+	image: &RgbaImage,
+	width: u32,
+	height: u32,
+	pick: fn(&[Rgba<u8>]) -> Rgba<u8>,
+) -> RgbaImage {
+	// Start of the region of the `idx`th pixel out of `len` of `size`.
+	let start = |idx: u32, len: u32, size: u32| {
+		(u64::from(idx) * u64::from(size) / u64::from(len)) as u32
+	};
+
+	let rows = map_rows(height, |y| {
+		let y0 = start(y, height, image.height());
+		let y1 = start(y + 1, height, image.height()).max(y0 + 1);
+		let mut region = Vec::new();
+
+		(0..width)
+			.flat_map(|x| {
+				let x0 = start(x, width, image.width());
+				let x1 = start(x + 1, width, image.width()).max(x0 + 1);
+
+				region.clear();
+				for y in y0..y1 {
+					region.extend((x0..x1).map(|x| *image.get_pixel(x, y)));
+				}
+				pick(&region).0
+			})
+			.collect::<Vec<_>>()
+	});
+
+	RgbaImage::from_raw(width, height, rows.concat())
+		.expect("the rows cover the image")
+}
+
+/// The average of `pixels`, each color weighted by its opacity.
+fn average(pixels: &[Rgba<u8>]) -> Rgba<u8> {
+	let (mut sums, mut alpha, mut len) = ([0u64; 3], 0u64, 0u64);
+	for Rgba([r, g, b, a]) in pixels {
+		for (sum, c) in sums.iter_mut().zip([r, g, b]) {
+			*sum += u64::from(*c) * u64::from(*a);
+		}
+		alpha += u64::from(*a);
+		len += 1;
+	}
+	if alpha == 0 {
+		return Rgba([0; 4]);
+	}
+
+	let [r, g, b] = sums.map(|sum| ((sum + alpha / 2) / alpha) as u8);
+	Rgba([r, g, b, ((alpha + len / 2) / len) as u8])
+}
+
+/// The average of the pixels in the most common of the coarse color ranges
+/// of the visible `pixels`.
+fn dominant(pixels: &[Rgba<u8>]) -> Rgba<u8> {
+	let range = |Rgba([r, g, b, _]): &Rgba<u8>| (r >> 4, g >> 4, b >> 4);
+	let visible = || pixels.iter().filter(|p| p.0[3] != 0);
+
+	let mut counts = BTreeMap::new();
+	for pixel in visible() {
+		*counts.entry(range(pixel)).or_insert(0u32) += 1;
+	}
+	let Some((&common, _)) = counts.iter().max_by_key(|&(_, count)| count)
+	else {
+		return Rgba([0; 4]);
+	};
+
+	let common: Vec<_> =
+		visible().filter(|&pixel| range(pixel) == common).copied().collect();
+	let Rgba([r, g, b, _]) = average(&common);
+	let Rgba([.., a]) = average(pixels);
+	Rgba([r, g, b, a])
+}
+
+#[test]
+fn sample_shrink() {
+	let (red, blue) = (Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 255]));
+	let mut image = RgbaImage::from_pixel(4, 2, red);
+	image.put_pixel(1, 1, blue);
+	image.put_pixel(2, 0, blue);
+	image.put_pixel(3, 0, blue);
+	image.put_pixel(3, 1, Rgba([0; 4]));
+
+	let average = shrink(&image, 2, 1, average);
+	assert_eq!(average.get_pixel(0, 0), &Rgba([191, 0, 64, 255]));
+	// Only the visible pixels count for the color.
+	assert_eq!(average.get_pixel(1, 0), &Rgba([85, 0, 170, 191]));
+
+	let dominant = shrink(&image, 2, 1, dominant);
+	assert_eq!(dominant.get_pixel(0, 0), &red);
+	assert_eq!(dominant.get_pixel(1, 0), &Rgba([0, 0, 255, 191]));
+
+	let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, red));
+	let enlarged = Sampling::Average.resize(image, (2, 2), Filter::Nearest);
+	assert_eq!(enlarged.into_rgba8(), RgbaImage::from_pixel(2, 2, red));
+}
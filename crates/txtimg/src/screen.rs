@@ -0,0 +1,173 @@
+//! Redrawing text images in place, writing only the cells which changed.
+
+use std::{fmt, io};
+
+use crate::{write_io, TextImage, WIDE_TAIL};
+
+/// The text image last drawn on the terminal, for drawing the next one
+/// over it without redrawing what stays the same.
+///
+/// The first image is drawn at the cursor, which should be at the start of
+/// a line. Every draw leaves the cursor at the start of the line below the
+/// image, and expects it there again for the next one, so nothing else
+/// should move it in between.
+#[derive(Debug, Clone, Default)]
+pub struct Screen {
+	shown: Option<TextImage>,
+}
+
+impl Screen {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Forgets the image on screen, so the next one is drawn in full below
+	/// the cursor, e.g. after the terminal was cleared.
+	pub fn reset(&mut self) {
+		self.shown = None;
+	}
+
+	/// Writes the sequences turning the image on screen into `image`. Only
+	/// the changed cells are written if both have the same size, otherwise
+	/// `image` replaces the previous one completely.
+	pub fn to_buffer(&mut self, image: &TextImage, buffer: &mut String) {
+		// Writing into a string never fails.
+		let _ = self.write_diff(image, buffer);
+	}
+
+	/// Streams the changes into `writer` like [`Screen::to_buffer`].
+	pub fn write_to(
+		&mut self,
+		image: &TextImage,
+		writer: &mut impl io::Write,
+	) -> io::Result<()> {
+		write_io(writer, |out| self.write_diff(image, out))
+	}
+
+	fn write_diff(
+		&mut self,
+		image: &TextImage,
+		out: &mut impl fmt::Write,
+	) -> fmt::Result {
+		match &self.shown {
+			Some(shown)
+				if (shown.width, shown.height)
+					== (image.width, image.height) =>
+			{
+				diff(shown, image, out)?;
+			}
+			Some(shown) => {
+				if shown.height > 0 {
+					write!(out, "\x1b[{}A", shown.height)?;
+				}
+				// Clears what the new image doesn't cover.
+				out.write_str("\x1b[J")?;
+				image.write_text(out)?;
+			}
+			None => image.write_text(out)?,
+		}
+
+		self.shown = Some(image.clone());
+		Ok(())
+	}
+}
+
+/// Writes the cells of `after` which differ from `before` of the same size,
+/// moving the cursor from below the image to them and back.
+fn diff(
+	before: &TextImage,
+	after: &TextImage,
+	out: &mut impl fmt::Write,
+) -> fmt::Result {
+	let changed = |x: u32, y: u32| {
+		let idx = after.idx(x, y);
+		before.pixels[idx] != after.pixels[idx]
+	};
+	// Terminals clear all of a double-width character when writing over
+	// half of it, so those are redrawn whole.
+	let wide_tail = |x: u32, y: u32| {
+		let idx = after.idx(x, y);
+		before.pixels[idx].c == WIDE_TAIL || after.pixels[idx].c == WIDE_TAIL
+	};
+
+	// Line of the cursor, counted from the top of the image.
+	let mut row = after.height;
+
+	for y in 0..after.height {
+		let mut x = 0;
+		while x < after.width {
+			if !changed(x, y) {
+				x += 1;
+				continue;
+			}
+
+			let mut start = x;
+			if start > 0 && wide_tail(start, y) {
+				start -= 1;
+			}
+			let mut end = x + 1;
+			while end < after.width && (changed(end, y) || wide_tail(end, y)) {
+				end += 1;
+			}
+
+			if row > y {
+				write!(out, "\x1b[{}A", row - y)?;
+			}
+			if row < y {
+				write!(out, "\x1b[{}B", y - row)?;
+			}
+			row = y;
+			write!(out, "\x1b[{}G", start + 1)?;
+			after.write_runs(out, after.runs(y, start..end))?;
+
+			x = end;
+		}
+	}
+
+	if row < after.height {
+		write!(out, "\x1b[{}B\r", after.height - row)?;
+	}
+
+	Ok(())
+}
+
+#[test]
+fn screen_diff() {
+	use image::{DynamicImage, Rgb, RgbImage};
+
+	use crate::{Fit, Options, RenderMode};
+
+	let render = |image: RgbImage| {
+		let opts = Options::builder()
+			.size(image.width(), image.height())
+			.fit(Fit::Stretch)
+			.mode(RenderMode::Ascii)
+			.build();
+		TextImage::from_image(DynamicImage::ImageRgb8(image), opts)
+	};
+	let white = Rgb([255; 3]);
+
+	let mut screen = Screen::new();
+	let mut draw = |image: &TextImage| {
+		let mut buffer = String::new();
+		screen.to_buffer(image, &mut buffer);
+		buffer
+	};
+
+	let first = render(RgbImage::new(4, 2));
+	assert_eq!(draw(&first), "    \r\n    \r\n");
+	assert_eq!(draw(&first), "");
+
+	// Two cells on the top line, and the last one on the bottom line.
+	let mut image = RgbImage::new(4, 2);
+	image.put_pixel(1, 0, white);
+	image.put_pixel(2, 0, white);
+	image.put_pixel(3, 1, white);
+	assert_eq!(
+		draw(&render(image)),
+		"\x1b[2A\x1b[2G##\x1b[1B\x1b[4G#\x1b[1B\r"
+	);
+
+	// Different sizes replace the image.
+	assert_eq!(draw(&render(RgbImage::new(1, 1))), "\x1b[2A\x1b[J \r\n");
+}
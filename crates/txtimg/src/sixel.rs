@@ -0,0 +1,280 @@
+//! Sixel graphics, drawing real pixels on terminals supporting them, e.g.
+//! xterm, foot or WezTerm.
+
+use std::fmt::Write as _;
+use std::ops::Range;
+
+use image::{DynamicImage, Rgb};
+
+use crate::{Options, CELL_PIXELS};
+
+/// Most colors a sixel palette holds on common terminals.
+const MAX_COLORS: usize = 256;
+
+/// An image quantized to a palette, ready to be written as sixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SixelImage {
+	width: u32,
+	height: u32,
+	palette: Vec<(u8, u8, u8)>,
+	/// Palette index of every pixel, row by row.
+	pixels: Vec<u8>,
+}
+
+impl SixelImage {
+	/// Scales `image` into the cells of `opts` like [`crate::TextImage`], at
+	/// 8 x 16 pixels per cell. The render mode and palette don't apply.
+	pub fn from_image(image: DynamicImage, opts: Options) -> Self {
+		let Some(image) = opts.resize(image, CELL_PIXELS) else {
+			return Self {
+				width: 0,
+				height: 0,
+				palette: Vec::new(),
+				pixels: Vec::new(),
+			};
+		};
+		// Sixels can't be translucent.
+		let image = opts.blend(&image.into_rgba8());
+
+		let pixels: Vec<_> = image.pixels().copied().collect();
+		let (palette, pixels) = quantize(&pixels, MAX_COLORS);
+
+		Self { width: image.width(), height: image.height(), palette, pixels }
+	}
+
+	/// Width in pixels.
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	/// Height in pixels.
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	/// Colors the image was quantized to.
+	pub fn palette(&self) -> &[(u8, u8, u8)] {
+		&self.palette
+	}
+
+	/// Writes the sixel sequence drawing the image at the cursor.
+	pub fn to_buffer(&self, buffer: &mut String) {
+		if self.pixels.is_empty() {
+			return;
+		}
+
+		// Square pixels, then the size.
+		let _ = write!(buffer, "\x1bPq\"1;1;{};{}", self.width, self.height);
+		for (idx, &(r, g, b)) in self.palette.iter().enumerate() {
+			let [r, g, b] =
+				[r, g, b].map(|c| (u32::from(c) * 100 + 127) / 255);
+			let _ = write!(buffer, "#{idx};2;{r};{g};{b}");
+		}
+
+		let width = self.width as usize;
+		for (band, rows) in self.pixels.chunks(width * 6).enumerate() {
+			if band > 0 {
+				buffer.push('-');
+			}
+
+			let mut used = [false; MAX_COLORS];
+			for &idx in rows {
+				used[usize::from(idx)] = true;
+			}
+
+			let colors = (0..self.palette.len()).filter(|&color| used[color]);
+			for (n, color) in colors.enumerate() {
+				if n > 0 {
+					// Back to the start of the band for the next color.
+					buffer.push('$');
+				}
+				let _ = write!(buffer, "#{color}");
+
+				let sixels = (0..width).map(|x| {
+					rows.chunks(width)
+						.enumerate()
+						.filter(|(_, row)| usize::from(row[x]) == color)
+						.fold(0, |bits, (y, _)| bits | (1 << y))
+				});
+				push_sixels(buffer, sixels);
+			}
+		}
+
+		buffer.push_str("\x1b\\");
+	}
+}
+
+/// Writes sixels of the bits set in `sixels`, run-length encoded and
+/// without the unset ones at the end.
+fn push_sixels(buffer: &mut String, sixels: impl Iterator<Item = u8>) {
+	let push_run = |buffer: &mut String, sixel: u8, len: usize| {
+		let c = char::from(b'?' + sixel);
+		if len > 3 {
+			let _ = write!(buffer, "!{len}{c}");
+		} else {
+			for _ in 0..len {
+				buffer.push(c);
+			}
+		}
+	};
+
+	let mut run: Option<(u8, usize)> = None;
+	for sixel in sixels {
+		run = match run {
+			Some((last, len)) if last == sixel => Some((last, len + 1)),
+			Some((last, len)) => {
+				push_run(buffer, last, len);
+				Some((sixel, 1))
+			}
+			None => Some((sixel, 1)),
+		};
+	}
+
+	if let Some((last @ 1.., len)) = run {
+		push_run(buffer, last, len);
+	}
+}
+
+/// A box of the median cut, the pixels of `order[range]`.
+struct ColorBox {
+	range: Range<usize>,
+	/// Channel with the widest range of values and that range.
+	channel: usize,
+	spread: u8,
+}
+
+impl ColorBox {
+	fn new(pixels: &[Rgb<u8>], order: &[usize], range: Range<usize>) -> Self {
+		let (channel, spread) = (0..3)
+			.map(|channel| {
+				let values = order[range.clone()]
+					.iter()
+					.map(|&pixel| pixels[pixel].0[channel]);
+				let min = values.clone().min().unwrap_or(0);
+				let max = values.max().unwrap_or(0);
+
+				(channel, max - min)
+			})
+			.max_by_key(|&(_, spread)| spread)
+			.expect("there are three channels");
+
+		Self { range, channel, spread }
+	}
+}
+
+/// Reduces `pixels` to at most `colors` colors with a median cut, returning
+/// the palette and the palette index of every pixel.
+///
+/// Starting with all pixels in one box, the box with the widest range in one
+/// channel is split at its median in that channel until there are enough
+/// boxes or all of them hold a single color. Every box becomes the mean of
+/// its pixels.
+fn quantize(
+	pixels: &[Rgb<u8>],
+	colors: usize,
+) -> (Vec<(u8, u8, u8)>, Vec<u8>) {
+	let mut order: Vec<usize> = (0..pixels.len()).collect();
+	let mut boxes = vec![ColorBox::new(pixels, &order, 0..pixels.len())];
+
+	while boxes.len() < colors.min(MAX_COLORS) {
+		let Some((idx, widest)) = boxes
+			.iter()
+			.enumerate()
+			.filter(|(_, color_box)| color_box.spread > 0)
+			.max_by_key(|(_, color_box)| color_box.spread)
+		else {
+			break;
+		};
+
+		let (range, channel) = (widest.range.clone(), widest.channel);
+		let mid = range.start + range.len() / 2;
+		order[range.clone()]
+			.select_nth_unstable_by_key(range.len() / 2, |&pixel| {
+				pixels[pixel].0[channel]
+			});
+
+		boxes[idx] = ColorBox::new(pixels, &order, range.start..mid);
+		boxes.push(ColorBox::new(pixels, &order, mid..range.end));
+	}
+
+	let mut indices = vec![0; pixels.len()];
+	let palette = boxes
+		.iter()
+		.enumerate()
+		.map(|(idx, color_box)| {
+			let mut sum = [0u32; 3];
+			for &pixel in &order[color_box.range.clone()] {
+				indices[pixel] = idx as u8;
+				for (sum, c) in sum.iter_mut().zip(pixels[pixel].0) {
+					*sum += u32::from(c);
+				}
+			}
+
+			let n = color_box.range.len() as u32;
+			let [r, g, b] =
+				sum.map(|c| (c + n / 2).checked_div(n).unwrap_or(0) as u8);
+			(r, g, b)
+		})
+		.collect();
+
+	(palette, indices)
+}
+
+#[test]
+fn sixel_quantize() {
+	let pixels = [
+		Rgb([250, 0, 0]),
+		Rgb([0, 0, 250]),
+		Rgb([240, 10, 0]),
+		Rgb([10, 0, 240]),
+	];
+
+	let (palette, indices) = quantize(&pixels, 2);
+	assert_eq!(palette.len(), 2);
+	assert_eq!(indices[0], indices[2]);
+	assert_eq!(indices[1], indices[3]);
+	assert_eq!(palette[usize::from(indices[0])], (245, 5, 0));
+	assert_eq!(palette[usize::from(indices[1])], (5, 0, 245));
+
+	// No more colors than there are.
+	let (palette, _) = quantize(&[Rgb([1, 2, 3]); 10], 16);
+	assert_eq!(palette, [(1, 2, 3)]);
+}
+
+#[test]
+fn sixel_to_buffer() {
+	use crate::Fit;
+
+	let render = |image: SixelImage| {
+		let mut buffer = String::new();
+		image.to_buffer(&mut buffer);
+		buffer
+	};
+
+	let image = SixelImage {
+		width: 2,
+		height: 1,
+		palette: vec![(255, 0, 0), (0, 0, 255)],
+		pixels: vec![0, 1],
+	};
+	assert_eq!(
+		render(image),
+		"\x1bPq\"1;1;2;1#0;2;100;0;0#1;2;0;0;100#0@$#1?@\x1b\\"
+	);
+
+	// Runs, and a second band.
+	let image = SixelImage {
+		width: 5,
+		height: 7,
+		palette: vec![(0, 0, 0)],
+		pixels: vec![0; 35],
+	};
+	assert_eq!(render(image), "\x1bPq\"1;1;5;7#0;2;0;0;0#0!5~-#0!5@\x1b\\");
+
+	let image = SixelImage::from_image(
+		DynamicImage::new_rgb8(3, 3),
+		Options::builder().size(2, 1).fit(Fit::Stretch).build(),
+	);
+	assert_eq!((image.width(), image.height()), (16, 16));
+	assert_eq!(image.palette(), [(0, 0, 0)]);
+}
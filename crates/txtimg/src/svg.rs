@@ -0,0 +1,91 @@
+//! Rasterizing SVG images, right at the resolution they are shown in.
+
+use image::{DynamicImage, RgbaImage};
+use tiny_skia::{Pixmap, Transform};
+pub use usvg::Error;
+use usvg::{FitTo, Tree};
+
+use crate::{Options, OutputProtocol, TextImage, CELL_PIXELS};
+
+/// Rasterizes the SVG `data` at the size the cells of `opts` sample, so the
+/// image isn't scaled again afterwards. Transparent where the SVG doesn't
+/// draw.
+///
+/// With a [crop](crate::OptionsBuilder::crop) the SVG is rasterized at its
+/// own size instead, which the rectangle refers to.
+pub fn rasterize(data: &[u8], opts: &Options) -> Result<DynamicImage, Error> {
+	let tree = Tree::from_data(data, &usvg::Options::default())?;
+	let (width, height) = (tree.size.width(), tree.size.height());
+	let own = (width.ceil() as u32, height.ceil() as u32);
+
+	let (width_px, height_px) = if opts.crop.is_some() {
+		own
+	} else {
+		let (cell_w, cell_h) = match opts.protocol {
+			OutputProtocol::Text => opts.mode.cell_size(),
+			_ => CELL_PIXELS,
+		};
+		let (columns, rows) = opts.scaled_size(own.0, own.1);
+		(columns * cell_w, rows * cell_h)
+	};
+	let Some(mut pixmap) = Pixmap::new(width_px, height_px) else {
+		// Nothing to draw into.
+		return Ok(DynamicImage::new_rgba8(0, 0));
+	};
+
+	let transform = Transform::from_scale(
+		width_px as f32 / width as f32,
+		height_px as f32 / height as f32,
+	);
+	resvg::render(&tree, FitTo::Original, transform, pixmap.as_mut())
+		.ok_or(Error::InvalidSize)?;
+
+	// Pixmaps hold premultiplied colors.
+	let pixels = pixmap
+		.pixels()
+		.iter()
+		.flat_map(|pixel| {
+			let color = pixel.demultiply();
+			[color.red(), color.green(), color.blue(), color.alpha()]
+		})
+		.collect();
+	let image = RgbaImage::from_raw(width_px, height_px, pixels)
+		.expect("the pixmap has the size of the image");
+
+	Ok(DynamicImage::ImageRgba8(image))
+}
+
+impl TextImage {
+	/// Renders the SVG `data` like [`TextImage::from_image`], rasterized
+	/// with [`rasterize`].
+	pub fn from_svg(data: &[u8], opts: Options) -> Result<Self, Error> {
+		Ok(Self::from_image(rasterize(data, &opts)?, opts))
+	}
+}
+
+#[test]
+fn svg_rasterize() {
+	use crate::{Fit, RenderMode};
+
+	let svg =
+		br##"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+		<rect width="10" height="10" fill="#ff0000"/>
+	</svg>"##;
+
+	let opts = Options::builder()
+		.size(4, 1)
+		.fit(Fit::Stretch)
+		.mode(RenderMode::HalfBlocks)
+		.build();
+	let image = rasterize(svg, &opts).unwrap();
+	assert_eq!((image.width(), image.height()), (4, 2));
+
+	let line = TextImage::from_svg(svg, opts.clone()).unwrap().line(0);
+	assert_eq!(line.len(), 2);
+	assert_eq!(line[0].text(), "▀▀");
+	assert_eq!(line[0].fg(), Some((255, 0, 0)));
+	assert_eq!(line[1].text(), "  ");
+	assert_eq!((line[1].fg(), line[1].bg()), (None, None));
+
+	assert!(rasterize(b"not svg", &opts).is_err());
+}
@@ -0,0 +1,65 @@
+//! Width-aware text helpers.
+//!
+//! CJK characters and most emoji occupy two terminal cells, so the number of
+//! chars in a string is not the number of cells it takes up.
+
+use std::borrow::Cow;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Returns the number of terminal cells `c` occupies.
+///
+/// Control characters are treated as zero width.
+pub fn char_width(c: char) -> usize {
+	c.width().unwrap_or(0)
+}
+
+/// Returns the number of terminal cells `s` occupies.
+pub fn width(s: &str) -> usize {
+	s.width()
+}
+
+/// Truncates `s` to at most `max_width` cells, ending it with `ellipsis` if
+/// anything was cut off.
+///
+/// A double-width character is never split, so the result may be one cell
+/// narrower than `max_width`.
+pub fn truncate<'a>(
+	s: &'a str,
+	max_width: usize,
+	ellipsis: &str,
+) -> Cow<'a, str> {
+	if width(s) <= max_width {
+		return Cow::Borrowed(s);
+	}
+
+	let ellipsis = if width(ellipsis) <= max_width { ellipsis } else { "" };
+	let available = max_width - width(ellipsis);
+
+	let mut used = 0;
+	let end = s
+		.char_indices()
+		.find(|&(_, c)| {
+			used += char_width(c);
+			used > available
+		})
+		.map_or(s.len(), |(idx, _)| idx);
+
+	Cow::Owned(format!("{}{}", &s[..end], ellipsis))
+}
+
+#[test]
+fn text_width() {
+	assert_eq!(width("abc"), 3);
+	assert_eq!(width("進撃の巨人"), 10);
+	assert_eq!(char_width('\n'), 0);
+}
+
+#[test]
+fn text_truncate() {
+	assert_eq!(truncate("abc", 3, "…"), "abc");
+	assert_eq!(truncate("abcdef", 4, "…"), "abc…");
+	assert_eq!(truncate("進撃の巨人", 6, "…"), "進撃…");
+	assert_eq!(truncate("進撃の巨人", 5, ""), "進撃");
+	assert_eq!(truncate("abc", 2, "..."), "ab");
+}